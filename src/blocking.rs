@@ -1 +1,2315 @@
-pub struct Client;
+use reqwest::blocking::{RequestBuilder, Response};
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+use reqwest::{Method, NoProxy};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::io::Read;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::dtypes::{
+    ApiObject, Assistant, Batch, ChatCompletionChunk, ChatCompletionMessage, ChatCompletionObject,
+    ChatCompletionRequest, ChunkEvent, CompletionChunk, CompletionRequest, CompletionResponse,
+    CreateAssistantRequest, CreateBatchRequest, CreateEmbeddingRequest, CreateEmbeddingResponse,
+    CreateFineTuningJobRequest, CreateImageEditRequest, CreateImageRequest,
+    CreateImageVariationRequest, CreateMessageRequest, CreateRunRequest, CreateThreadRequest,
+    CreateTranslationRequest, DeleteAssistantResponse, DeleteFileResponse, DeleteModelResponse,
+    DeleteThreadResponse, EmbeddingInput, FileObject, FineTuningJob, FineTuningJobEvent,
+    ImageResponse, ListModelsResponse, ModelObject, ModerationRequest, ModerationResponse,
+    ModifyAssistantRequest, Role, Run, RunStatus, SpeechRequest, SubmitToolOutputsRequest, Thread,
+    ThreadMessage, TranslationResponse,
+};
+use crate::hooks::{self, Hooks, RequestLog, ResponseLog};
+use crate::page::{self, ListParams, Page};
+use crate::retry::{new_idempotency_key, IdempotencyKeyFn, RetryPolicy, IDEMPOTENCY_KEY_HEADER};
+use crate::sse::SseDecoder;
+use crate::stream::{self, StreamEvent};
+use crate::{
+    api_error, beta_header, cosine_similarity, escape_path_segment, format_url, mask_key, ApiType,
+    Error, Result, API_KEY_ENV_VAR, BASE_URL, BASE_URL_ENV_VAR, OPENAI_BETA_HEADER, ORG_ID_ENV_VAR,
+    PROJECT_ID_ENV_VAR, VERSION_PREFIX,
+};
+
+/// A synchronous client for the OpenAI API, for programs
+/// that don't want an async runtime.
+///
+/// It sends the same requests, returns the same types and
+/// errors as the async `ohairs::Client`, just without the
+/// `.await`.
+///
+/// Note that, like `reqwest::blocking::Client`, it must
+/// not be used from within an async runtime.
+///
+/// # Example
+///
+/// ```no_run
+/// use ohairs::blocking::Client;
+///
+/// # fn run() -> ohairs::Result<()> {
+/// let client = Client::new("test");
+/// let models = client.list_models()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Client {
+    pub base_url: String,
+    pub api_key: String,
+    pub org_id: Option<String>,
+    pub project_id: Option<String>,
+    pub req_client: reqwest::blocking::Client,
+
+    /// The version prefix the API's paths are sent under.
+    ///
+    /// See `ohairs::Client::version_prefix`.
+    pub version_prefix: String,
+
+    /// Which flavor of the API the client talks to, which
+    /// determines how URLs and auth headers are built.
+    pub api_type: ApiType,
+
+    /// The most recent result of `list_models`, along with
+    /// the time it was fetched.
+    models_cache: RwLock<Option<(Instant, ListModelsResponse)>>,
+
+    /// How requests that fail with a transient error are
+    /// retried.
+    retry: RetryPolicy,
+
+    /// Generates the idempotency keys for retried requests,
+    /// if overridden.
+    idempotency_key: Option<IdempotencyKeyFn>,
+
+    /// Should chat completion requests be validated before
+    /// they're sent?
+    validate_requests: bool,
+
+    /// Headers added to every request (e.g. for a proxy).
+    default_headers: HeaderMap,
+
+    /// Callbacks run for each request and response.
+    hooks: Hooks,
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("base_url", &self.base_url)
+            .field("api_key", &mask_key(&self.api_key))
+            .field("org_id", &self.org_id)
+            .field("project_id", &self.project_id)
+            .field("version_prefix", &self.version_prefix)
+            .field("api_type", &self.api_type)
+            .field("retry", &self.retry)
+            .field("validate_requests", &self.validate_requests)
+            .field("default_headers", &hooks::redact(&self.default_headers))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Client {
+    /// Create a new client with the given API key.
+    pub fn new(api_key: &str) -> Self {
+        Self::with_client(api_key, reqwest::blocking::Client::new())
+    }
+
+    /// Create a new client for the Azure OpenAI Service.
+    ///
+    /// See `ohairs::Client::new_azure`.
+    pub fn new_azure(endpoint: &str, api_key: &str, deployment: &str, api_version: &str) -> Self {
+        let mut client = Self::new(api_key);
+        client.base_url = endpoint.to_string();
+        client.api_type = ApiType::Azure {
+            deployment: deployment.to_string(),
+            api_version: api_version.to_string(),
+        };
+        client
+    }
+
+    /// Create a new client with the given API key that sends
+    /// requests using the given `reqwest::blocking::Client`.
+    pub fn with_client(api_key: &str, req_client: reqwest::blocking::Client) -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+            api_key: api_key.to_string(),
+            org_id: None,
+            project_id: None,
+            req_client,
+            version_prefix: VERSION_PREFIX.to_string(),
+            api_type: ApiType::OpenAi,
+            models_cache: RwLock::new(None),
+            retry: RetryPolicy::default(),
+            idempotency_key: None,
+            validate_requests: false,
+            default_headers: HeaderMap::new(),
+            hooks: Hooks::default(),
+        }
+    }
+
+    /// Create a new client configured from environment variables.
+    ///
+    /// See `ohairs::Client::from_env`.
+    pub fn from_env() -> Result<Self> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Create a new client configured from the variables
+    /// returned by `lookup`. Empty values count as unset.
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let lookup = |name| lookup(name).filter(|value: &String| !value.trim().is_empty());
+
+        // Get the API key...
+        let api_key = lookup(API_KEY_ENV_VAR)
+            .ok_or_else(|| Error::MissingEnvVar(API_KEY_ENV_VAR.to_string()))?;
+        let mut builder = Self::builder().api_key(&api_key);
+
+        // Get the optional settings...
+        if let Some(org_id) = lookup(ORG_ID_ENV_VAR) {
+            builder = builder.org_id(&org_id);
+        }
+        if let Some(project_id) = lookup(PROJECT_ID_ENV_VAR) {
+            builder = builder.project_id(&project_id);
+        }
+        if let Some(base_url) = lookup(BASE_URL_ENV_VAR) {
+            builder = builder.base_url(&base_url);
+        }
+
+        // Build the client...
+        builder.build()
+    }
+
+    /// Create a `ClientBuilder` for configuring a client
+    /// beyond just the API key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::blocking::Client;
+    /// use std::time::Duration;
+    ///
+    /// let client = Client::builder()
+    ///     .api_key("test")
+    ///     .max_retries(3)
+    ///     .timeout(Duration::from_secs(30))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    fn create_request(&self, method: Method, path: &str) -> Result<RequestBuilder> {
+        // Format the URL...
+        let url = format_url(&self.base_url, &self.version_prefix, &self.api_type, path)?;
+
+        // Create a request builder...
+        let mut req = self.req_client.request(method, url);
+
+        // Add the default headers, except any that would clash
+        // with the auth, org or project headers...
+        if !self.default_headers.is_empty() {
+            let mut headers = self.default_headers.clone();
+            match self.api_type {
+                ApiType::OpenAi => headers.remove(AUTHORIZATION),
+                ApiType::Azure { .. } => headers.remove("api-key"),
+            };
+            if self.org_id.is_some() {
+                headers.remove("OpenAI-Organization");
+            }
+            if self.project_id.is_some() {
+                headers.remove("OpenAI-Project");
+            }
+            req = req.headers(headers);
+        }
+
+        // Add the auth header...
+        req = match self.api_type {
+            ApiType::OpenAi => req.bearer_auth(self.api_key.as_str()),
+            ApiType::Azure { .. } => req.header("api-key", self.api_key.as_str()),
+        };
+
+        // If there's a org_id, add it...
+        if let Some(org_id) = &self.org_id {
+            req = req.header("OpenAI-Organization", org_id.as_str());
+        }
+
+        // If there's a project_id, add it...
+        if let Some(project_id) = &self.project_id {
+            req = req.header("OpenAI-Project", project_id.as_str());
+        }
+
+        // If it's a beta endpoint, opt in...
+        if let Some(beta) = beta_header(path) {
+            req = req.header(OPENAI_BETA_HEADER, beta);
+        }
+
+        // Return the request...
+        Ok(req)
+    }
+
+    /// Returns a request builder for the given API path (e.g.
+    /// `/v1/models`), with the client's URL, auth and default
+    /// headers already set.
+    ///
+    /// This is an escape hatch for endpoints (or options) the
+    /// typed methods don't cover. Send the request with
+    /// `send_raw` to keep the client's retries and hooks.
+    pub fn raw_request(&self, method: Method, path: &str) -> Result<RequestBuilder> {
+        self.create_request(method, path)
+    }
+
+    /// Sends a request built with `raw_request`, returning the
+    /// response without parsing it.
+    ///
+    /// Non-success statuses are returned as an `Error::Api`.
+    pub fn send_raw(&self, rb: RequestBuilder) -> Result<Response> {
+        check_status(self.send(rb)?)
+    }
+
+    /// Sends a request, retrying it according to the
+    /// client's `RetryPolicy` if it fails with a transient
+    /// error.
+    ///
+    /// See `ohairs::Client::send`.
+    fn send(&self, rb: RequestBuilder) -> Result<Response> {
+        let mut req = rb.build()?;
+
+        // Tag POSTs that may be retried, so the API can dedupe them...
+        if self.retry.max_retries > 0
+            && req.method() == Method::POST
+            && !req.headers().contains_key(IDEMPOTENCY_KEY_HEADER)
+        {
+            let key = match &self.idempotency_key {
+                Some(generate) => generate(),
+                None => new_idempotency_key(),
+            };
+            req.headers_mut().insert(
+                IDEMPOTENCY_KEY_HEADER,
+                key.parse()
+                    .map_err(|_| Error::Config(format!("invalid idempotency key: {:?}", key)))?,
+            );
+        }
+
+        let mut retries = 0;
+        loop {
+            // Keep a copy of the request in case it needs retrying...
+            let retry_req = match retries < self.retry.max_retries {
+                true => req.try_clone(),
+                false => None,
+            };
+
+            // Send the request...
+            self.hooks.blocking_request(&req);
+            let (method, url) = (req.method().clone(), req.url().clone());
+            let res = self.req_client.execute(req)?;
+            let res = self.hooks.blocking_response(&method, &url, res)?;
+
+            // Return the response, unless it should be retried...
+            let delay = match RetryPolicy::is_retryable(res.status()) {
+                true => self.retry.delay(retries, res.headers()),
+                false => None,
+            };
+            match (retry_req, delay) {
+                (Some(next), Some(delay)) => {
+                    std::thread::sleep(delay);
+                    req = next;
+                    retries += 1;
+                }
+                _ => return Ok(res),
+            }
+        }
+    }
+
+    pub fn list_models(&self) -> Result<ListModelsResponse> {
+        // Format the URL...
+        let rb = self.create_request(Method::GET, "/v1/models")?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ListModelsResponse>(res)?;
+        data.check_object()?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Retrieves a single model by its ID.
+    pub fn retrieve_model(&self, model_id: &str) -> Result<ModelObject> {
+        // Format the URL...
+        let path = format!("/v1/models/{}", escape_path_segment(model_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ModelObject>(res)?;
+        data.check_object()?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Deletes a fine-tuned model. You must have the
+    /// Owner role in your organization to delete a model.
+    pub fn delete_model(&self, model_id: &str) -> Result<DeleteModelResponse> {
+        // Format the URL...
+        let path = format!("/v1/models/{}", escape_path_segment(model_id));
+        let rb = self.create_request(Method::DELETE, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<DeleteModelResponse>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists the available models, reusing the result of a
+    /// previous call if it was fetched less than `ttl` ago.
+    ///
+    /// See `ohairs::Client::list_models_cached`.
+    pub fn list_models_cached(&self, ttl: Duration) -> Result<ListModelsResponse> {
+        // Return the cached copy if it's still fresh...
+        {
+            let cache = self
+                .models_cache
+                .read()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some((fetched_at, models)) = cache.as_ref() {
+                if fetched_at.elapsed() < ttl {
+                    return Ok(models.clone());
+                }
+            }
+        }
+
+        // Otherwise, reload it...
+        self.refresh_models()
+    }
+
+    /// Fetches the list of models and stores it in the client's
+    /// cache, regardless of how old the cached copy is.
+    pub fn refresh_models(&self) -> Result<ListModelsResponse> {
+        // Fetch the models...
+        let models = self.list_models()?;
+
+        // Update the cache...
+        let mut cache = self
+            .models_cache
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        *cache = Some((Instant::now(), models.clone()));
+
+        // Return the data...
+        Ok(models)
+    }
+
+    pub fn create_chat_completion(
+        &self,
+        req: ChatCompletionRequest,
+    ) -> Result<ChatCompletionObject> {
+        // Build the request...
+        let rb = self.chat_completion_request(&req)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ChatCompletionObject>(res)?;
+        data.check_object()?;
+
+        // Make sure there's a choice to return...
+        if data.choices.is_empty() {
+            return Err(Error::EmptyChoices(Box::new(data)));
+        }
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Sends `prompt` to `model` as a single user message,
+    /// and returns the text of the first choice.
+    ///
+    /// See `ohairs::Client::ask`.
+    pub fn ask(&self, model: impl Into<String>, prompt: &str) -> Result<String> {
+        let req = ChatCompletionRequest {
+            model: model.into(),
+            messages: vec![ChatCompletionMessage {
+                role: Role::User,
+                content: Some(prompt.into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let res = self.create_chat_completion(req)?;
+        match res.first_content() {
+            Some(content) => Ok(content.to_string()),
+            None => Err(Error::NoContent(Box::new(res))),
+        }
+    }
+
+    /// Continues a chat completion that was cut short by the
+    /// token limit, by re-sending `req` with the partial
+    /// message from `res` appended.
+    ///
+    /// The returned completion only holds the rest of the
+    /// message, so append its content to the partial one.
+    pub fn continue_completion(
+        &self,
+        req: ChatCompletionRequest,
+        res: &ChatCompletionObject,
+    ) -> Result<ChatCompletionObject> {
+        self.create_chat_completion(req.continuation(res))
+    }
+
+    /// Creates a chat completion, returning the raw response
+    /// rather than parsing it.
+    ///
+    /// The status is still checked, so an API error is
+    /// returned as an `Error::Api`.
+    pub fn create_chat_completion_raw(&self, req: ChatCompletionRequest) -> Result<Response> {
+        // Build the request...
+        let rb = self.chat_completion_request(&req)?;
+
+        // Send the request and check the status...
+        let res = self.send(rb)?;
+        check_status(res)
+    }
+
+    /// Validates (if enabled) and builds a chat completion
+    /// request, ready to send.
+    fn chat_completion_request(&self, req: &ChatCompletionRequest) -> Result<RequestBuilder> {
+        // Check the request, if enabled...
+        if self.validate_requests {
+            req.validate()?;
+        }
+
+        // Format the URL and add the body...
+        let rb = self.create_request(Method::POST, "/v1/chat/completions")?;
+        Ok(rb.json(req))
+    }
+
+    /// Creates a chat completion, streaming back the response
+    /// as an iterator of chunks.
+    ///
+    /// Each call to `next` blocks until the next chunk has
+    /// arrived, and the iterator ends at `[DONE]`. As with
+    /// the async client, the status is checked first, so an
+    /// API error is returned before any chunks are read.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::{blocking::Client, dtypes::ChatCompletionRequest};
+    ///
+    /// # fn run() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// let req = ChatCompletionRequest {
+    ///     model: "gpt-4o".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// for chunk in client.create_chat_completion_stream(req)? {
+    ///     println!("{:?}", chunk?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_chat_completion_stream(
+        &self,
+        mut req: ChatCompletionRequest,
+    ) -> Result<impl Iterator<Item = Result<ChatCompletionChunk>>> {
+        // Build the request...
+        req.stream = Some(true);
+        let rb = self.chat_completion_request(&req)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status before reading any events...
+        let res = check_status(res)?;
+
+        // Parse the body as chunks...
+        Ok(ChunkIter::new(res, ChatCompletionChunk::parse_event))
+    }
+
+    /// Creates a streamed chat completion, like
+    /// `create_chat_completion_stream`, but yields
+    /// `stream::StreamEvent`s rather than raw chunks.
+    ///
+    /// See `ohairs::Client::create_chat_completion_events`.
+    pub fn create_chat_completion_events(
+        &self,
+        req: ChatCompletionRequest,
+    ) -> Result<impl Iterator<Item = Result<StreamEvent>>> {
+        let chunks = self.create_chat_completion_stream(req)?;
+        Ok(stream::iter_events(chunks))
+    }
+
+    /// Creates a completion using the legacy completions
+    /// endpoint.
+    pub fn create_completion(&self, req: CompletionRequest) -> Result<CompletionResponse> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/completions")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<CompletionResponse>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Creates a completion using the legacy completions
+    /// endpoint, streaming back the generated text as it's
+    /// produced.
+    pub fn create_completion_stream(
+        &self,
+        mut req: CompletionRequest,
+    ) -> Result<impl Iterator<Item = Result<CompletionChunk>>> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/completions")?;
+
+        // Add the body...
+        req.stream = Some(true);
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status before reading any events...
+        let res = check_status(res)?;
+
+        // Parse the body as chunks...
+        Ok(ChunkIter::new(res, CompletionChunk::parse_event))
+    }
+
+    /// Creates embedding vectors representing the input text.
+    pub fn create_embeddings(
+        &self,
+        req: CreateEmbeddingRequest,
+    ) -> Result<CreateEmbeddingResponse> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/embeddings")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<CreateEmbeddingResponse>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Ranks `corpus` by its similarity to `query`, returning
+    /// the indices and cosine similarities of the top `k`
+    /// entries, most similar first.
+    ///
+    /// See `ohairs::Client::semantic_search`.
+    pub fn semantic_search(
+        &self,
+        model: &str,
+        query: &str,
+        corpus: &[String],
+        k: usize,
+    ) -> Result<Vec<(usize, f32)>> {
+        // Embed the query and the corpus together...
+        let mut input = Vec::with_capacity(corpus.len() + 1);
+        input.push(query.to_string());
+        input.extend(corpus.iter().cloned());
+        let res = self.create_embeddings(CreateEmbeddingRequest {
+            model: model.to_string(),
+            input: EmbeddingInput::StringArray(input),
+            ..Default::default()
+        })?;
+
+        // Put the embeddings back in input order...
+        let mut data = res.data;
+        data.sort_by_key(|d| d.index);
+        if data.len() != corpus.len() + 1 {
+            return Err(Error::Validation(format!(
+                "expected {} embeddings, got {}",
+                corpus.len() + 1,
+                data.len()
+            )));
+        }
+
+        // Score each corpus entry against the query...
+        let query = &data[0].embedding;
+        let mut scores: Vec<(usize, f32)> = data[1..]
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (i, cosine_similarity(query, &d.embedding)))
+            .collect();
+
+        // Return the top k...
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scores.truncate(k);
+        Ok(scores)
+    }
+
+    /// Creates images from a prompt.
+    pub fn create_image(&self, req: CreateImageRequest) -> Result<ImageResponse> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/images/generations")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ImageResponse>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Edits an image given a prompt and an optional mask.
+    ///
+    /// Like `upload_file`, the image is sent as a multipart
+    /// body.
+    pub fn create_image_edit(&self, req: CreateImageEditRequest) -> Result<ImageResponse> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/images/edits")?;
+
+        // Add the body...
+        let mut form = reqwest::blocking::multipart::Form::new();
+        for (name, value) in req.form_fields()? {
+            form = form.text(name, value);
+        }
+        form = form.part("image", file_part(req.image, "image.png"));
+        if let Some(mask) = req.mask {
+            form = form.part("mask", file_part(mask, "mask.png"));
+        }
+        let rb = rb.multipart(form);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ImageResponse>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Creates variations of an image.
+    ///
+    /// Like `upload_file`, the image is sent as a multipart
+    /// body.
+    pub fn create_image_variation(
+        &self,
+        req: CreateImageVariationRequest,
+    ) -> Result<ImageResponse> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/images/variations")?;
+
+        // Add the body...
+        let mut form = reqwest::blocking::multipart::Form::new();
+        for (name, value) in req.form_fields()? {
+            form = form.text(name, value);
+        }
+        form = form.part("image", file_part(req.image, "image.png"));
+        let rb = rb.multipart(form);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ImageResponse>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Classifies whether the input violates OpenAI's
+    /// usage policies.
+    pub fn create_moderation(&self, req: ModerationRequest) -> Result<ModerationResponse> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/moderations")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ModerationResponse>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Generates spoken audio from the input text, returning
+    /// the raw audio bytes (in `req.response_format`).
+    pub fn create_speech(&self, req: SpeechRequest) -> Result<Vec<u8>> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/audio/speech")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and read the audio...
+        let res = check_status(res)?;
+        let data = res.bytes()?;
+
+        // Return the data...
+        Ok(data.to_vec())
+    }
+
+    /// Translates audio into English text.
+    ///
+    /// Like `upload_file`, the audio is sent as a multipart
+    /// body. For the `text`, `srt` and `vtt` formats, the body
+    /// is returned as the response's `text`.
+    pub fn create_translation(&self, req: CreateTranslationRequest) -> Result<TranslationResponse> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/audio/translations")?;
+
+        // Add the body...
+        let mut form = reqwest::blocking::multipart::Form::new();
+        for (name, value) in req.form_fields()? {
+            form = form.text(name, value);
+        }
+        form = form.part("file", file_part(req.file, &req.filename));
+        let rb = rb.multipart(form);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response...
+        let res = check_status(res)?;
+        let body = res.text()?;
+        let data = TranslationResponse::from_body(&body, req.response_format)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Uploads a file for use with other endpoints, where
+    /// `purpose` says what it's for (e.g. "fine-tune" or
+    /// "assistants").
+    pub fn upload_file(&self, file: Vec<u8>, filename: &str, purpose: &str) -> Result<FileObject> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/files")?;
+
+        // Add the body...
+        let form = reqwest::blocking::multipart::Form::new()
+            .text("purpose", purpose.to_string())
+            .part("file", file_part(file, filename));
+        let rb = rb.multipart(form);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<FileObject>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of the uploaded files. Use
+    /// `list_all_files` to fetch every page.
+    pub fn list_files(&self, params: ListParams) -> Result<Page<FileObject>> {
+        // Format the URL...
+        let rb = self.create_request(Method::GET, "/v1/files")?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<FileObject>>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists every uploaded file, following the `after`
+    /// cursors until the last page.
+    pub fn list_all_files(&self) -> Result<Vec<FileObject>> {
+        page::list_all_blocking(ListParams::default(), |params| self.list_files(params))
+    }
+
+    /// Retrieves a single file's metadata by its ID.
+    pub fn retrieve_file(&self, file_id: &str) -> Result<FileObject> {
+        // Format the URL...
+        let path = format!("/v1/files/{}", escape_path_segment(file_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<FileObject>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Deletes a file.
+    pub fn delete_file(&self, file_id: &str) -> Result<DeleteFileResponse> {
+        // Format the URL...
+        let path = format!("/v1/files/{}", escape_path_segment(file_id));
+        let rb = self.create_request(Method::DELETE, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<DeleteFileResponse>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Downloads the contents of a file.
+    pub fn retrieve_file_content(&self, file_id: &str) -> Result<Vec<u8>> {
+        // Format the URL...
+        let path = format!("/v1/files/{}/content", escape_path_segment(file_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and read the contents...
+        let res = check_status(res)?;
+        let data = res.bytes()?;
+
+        // Return the data...
+        Ok(data.to_vec())
+    }
+
+    /// Creates a fine-tuning job, which starts training a
+    /// new model from `req.model` and the uploaded training
+    /// file.
+    pub fn create_fine_tuning_job(&self, req: CreateFineTuningJobRequest) -> Result<FineTuningJob> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/fine_tuning/jobs")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<FineTuningJob>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of your organization's fine-tuning jobs.
+    pub fn list_fine_tuning_jobs(&self, params: ListParams) -> Result<Page<FineTuningJob>> {
+        // Format the URL...
+        let rb = self.create_request(Method::GET, "/v1/fine_tuning/jobs")?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<FineTuningJob>>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Retrieves a single fine-tuning job by its ID.
+    pub fn retrieve_fine_tuning_job(&self, job_id: &str) -> Result<FineTuningJob> {
+        // Format the URL...
+        let path = format!("/v1/fine_tuning/jobs/{}", escape_path_segment(job_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<FineTuningJob>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Cancels a fine-tuning job that's still running.
+    pub fn cancel_fine_tuning_job(&self, job_id: &str) -> Result<FineTuningJob> {
+        // Format the URL...
+        let path = format!(
+            "/v1/fine_tuning/jobs/{}/cancel",
+            escape_path_segment(job_id)
+        );
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<FineTuningJob>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of a fine-tuning job's status updates.
+    pub fn list_fine_tuning_events(
+        &self,
+        job_id: &str,
+        params: ListParams,
+    ) -> Result<Page<FineTuningJobEvent>> {
+        // Format the URL...
+        let path = format!(
+            "/v1/fine_tuning/jobs/{}/events",
+            escape_path_segment(job_id)
+        );
+        let rb = self.create_request(Method::GET, &path)?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<FineTuningJobEvent>>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Creates an assistant.
+    pub fn create_assistant(&self, req: CreateAssistantRequest) -> Result<Assistant> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/assistants")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Assistant>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of assistants.
+    pub fn list_assistants(&self, params: ListParams) -> Result<Page<Assistant>> {
+        // Format the URL...
+        let rb = self.create_request(Method::GET, "/v1/assistants")?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<Assistant>>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Retrieves a single assistant by its ID.
+    pub fn retrieve_assistant(&self, assistant_id: &str) -> Result<Assistant> {
+        // Format the URL...
+        let path = format!("/v1/assistants/{}", escape_path_segment(assistant_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Assistant>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Modifies an assistant, changing only the fields set
+    /// in `req`.
+    pub fn modify_assistant(
+        &self,
+        assistant_id: &str,
+        req: ModifyAssistantRequest,
+    ) -> Result<Assistant> {
+        // Format the URL...
+        let path = format!("/v1/assistants/{}", escape_path_segment(assistant_id));
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Assistant>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Deletes an assistant.
+    pub fn delete_assistant(&self, assistant_id: &str) -> Result<DeleteAssistantResponse> {
+        // Format the URL...
+        let path = format!("/v1/assistants/{}", escape_path_segment(assistant_id));
+        let rb = self.create_request(Method::DELETE, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<DeleteAssistantResponse>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Creates a thread, optionally starting it with some
+    /// messages.
+    pub fn create_thread(&self, req: CreateThreadRequest) -> Result<Thread> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/threads")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Thread>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Retrieves a single thread by its ID.
+    pub fn retrieve_thread(&self, thread_id: &str) -> Result<Thread> {
+        // Format the URL...
+        let path = format!("/v1/threads/{}", escape_path_segment(thread_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Thread>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Deletes a thread.
+    pub fn delete_thread(&self, thread_id: &str) -> Result<DeleteThreadResponse> {
+        // Format the URL...
+        let path = format!("/v1/threads/{}", escape_path_segment(thread_id));
+        let rb = self.create_request(Method::DELETE, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<DeleteThreadResponse>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Adds a message to a thread.
+    pub fn create_message(
+        &self,
+        thread_id: &str,
+        req: CreateMessageRequest,
+    ) -> Result<ThreadMessage> {
+        // Format the URL...
+        let path = format!("/v1/threads/{}/messages", escape_path_segment(thread_id));
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ThreadMessage>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of a thread's messages.
+    pub fn list_messages(
+        &self,
+        thread_id: &str,
+        params: ListParams,
+    ) -> Result<Page<ThreadMessage>> {
+        // Format the URL...
+        let path = format!("/v1/threads/{}/messages", escape_path_segment(thread_id));
+        let rb = self.create_request(Method::GET, &path)?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<ThreadMessage>>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Retrieves a single message in a thread.
+    pub fn retrieve_message(&self, thread_id: &str, message_id: &str) -> Result<ThreadMessage> {
+        // Format the URL...
+        let path = format!(
+            "/v1/threads/{}/messages/{}",
+            escape_path_segment(thread_id),
+            escape_path_segment(message_id)
+        );
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ThreadMessage>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Starts a run of an assistant on a thread.
+    pub fn create_run(&self, thread_id: &str, req: CreateRunRequest) -> Result<Run> {
+        // Format the URL...
+        let path = format!("/v1/threads/{}/runs", escape_path_segment(thread_id));
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Run>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of a thread's runs.
+    pub fn list_runs(&self, thread_id: &str, params: ListParams) -> Result<Page<Run>> {
+        // Format the URL...
+        let path = format!("/v1/threads/{}/runs", escape_path_segment(thread_id));
+        let rb = self.create_request(Method::GET, &path)?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<Run>>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Retrieves a single run on a thread.
+    pub fn retrieve_run(&self, thread_id: &str, run_id: &str) -> Result<Run> {
+        // Format the URL...
+        let path = format!(
+            "/v1/threads/{}/runs/{}",
+            escape_path_segment(thread_id),
+            escape_path_segment(run_id)
+        );
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Run>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Cancels a run that's in progress.
+    pub fn cancel_run(&self, thread_id: &str, run_id: &str) -> Result<Run> {
+        // Format the URL...
+        let path = format!(
+            "/v1/threads/{}/runs/{}/cancel",
+            escape_path_segment(thread_id),
+            escape_path_segment(run_id)
+        );
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Run>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Sends the outputs of the tool calls a run is waiting
+    /// on (i.e. its status is `requires_action`), so it can
+    /// continue.
+    pub fn submit_tool_outputs(
+        &self,
+        thread_id: &str,
+        run_id: &str,
+        req: SubmitToolOutputsRequest,
+    ) -> Result<Run> {
+        // Format the URL...
+        let path = format!(
+            "/v1/threads/{}/runs/{}/submit_tool_outputs",
+            escape_path_segment(thread_id),
+            escape_path_segment(run_id)
+        );
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Run>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Polls a run every `poll_interval` until it stops,
+    /// and returns it.
+    ///
+    /// A run has stopped when its status is terminal (see
+    /// `RunStatus::is_terminal`) or `requires_action`, since
+    /// a run waiting on tool outputs won't progress until
+    /// they're submitted. Returns an `Error::Timeout` if the
+    /// run is still going after `max_wait`.
+    pub fn wait_for_run(
+        &self,
+        thread_id: &str,
+        run_id: &str,
+        poll_interval: Duration,
+        max_wait: Duration,
+    ) -> Result<Run> {
+        let deadline = Instant::now() + max_wait;
+        loop {
+            // Check the run's status...
+            let run = self.retrieve_run(thread_id, run_id)?;
+            if run.status.is_terminal() || run.status == RunStatus::RequiresAction {
+                return Ok(run);
+            }
+
+            // Give up if the next poll would be too late...
+            if Instant::now() + poll_interval > deadline {
+                return Err(Error::Timeout(format!(
+                    "run {} was still {:?} after {:?}",
+                    run_id, run.status, max_wait
+                )));
+            }
+            std::thread::sleep(poll_interval);
+        }
+    }
+
+    /// Creates a batch from an uploaded file of requests,
+    /// which are run asynchronously at a lower cost.
+    pub fn create_batch(&self, req: CreateBatchRequest) -> Result<Batch> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/batches")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Batch>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of the organization's batches.
+    pub fn list_batches(&self, params: ListParams) -> Result<Page<Batch>> {
+        // Format the URL...
+        let rb = self.create_request(Method::GET, "/v1/batches")?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<Batch>>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists every one of the organization's batches whose
+    /// `metadata` has all of the given key-value pairs.
+    ///
+    /// See `ohairs::Client::list_batches_filtered`.
+    pub fn list_batches_filtered(&self, metadata: &[(&str, &str)]) -> Result<Vec<Batch>> {
+        // Fetch every page...
+        let batches =
+            page::list_all_blocking(ListParams::default(), |params| self.list_batches(params))?;
+
+        // Keep the batches that match every pair...
+        Ok(batches
+            .into_iter()
+            .filter(|batch| {
+                metadata.iter().all(|(key, value)| {
+                    batch.metadata.get(*key).map(String::as_str) == Some(*value)
+                })
+            })
+            .collect())
+    }
+
+    /// Retrieves a single batch by its ID.
+    pub fn retrieve_batch(&self, batch_id: &str) -> Result<Batch> {
+        // Format the URL...
+        let path = format!("/v1/batches/{}", escape_path_segment(batch_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Batch>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Cancels a batch that's in progress. Its status will
+    /// be `cancelling` until in-flight requests finish.
+    pub fn cancel_batch(&self, batch_id: &str) -> Result<Batch> {
+        // Format the URL...
+        let path = format!("/v1/batches/{}/cancel", escape_path_segment(batch_id));
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Send the request...
+        let res = self.send(rb)?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Batch>(res)?;
+
+        // Return the data...
+        Ok(data)
+    }
+}
+
+/// A builder for configuring a blocking `Client`.
+///
+/// It has the same settings as `ohairs::ClientBuilder`, minus
+/// `max_concurrency`, since a blocking client only ever has
+/// as many requests in flight as there are threads using it.
+#[derive(Default, Clone)]
+pub struct ClientBuilder {
+    api_key: Option<String>,
+    org_id: Option<String>,
+    project_id: Option<String>,
+    base_url: Option<String>,
+    version_prefix: Option<String>,
+    api_type: ApiType,
+    timeout: Option<Duration>,
+    proxies: Vec<reqwest::Proxy>,
+    proxy_urls: Vec<String>,
+    no_proxy: Option<String>,
+    req_client: Option<reqwest::blocking::Client>,
+    retry: RetryPolicy,
+    idempotency_key: Option<IdempotencyKeyFn>,
+    validate_requests: bool,
+    default_headers: HeaderMap,
+    hooks: Hooks,
+}
+
+impl fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("api_key", &self.api_key.as_deref().map(mask_key))
+            .field("org_id", &self.org_id)
+            .field("project_id", &self.project_id)
+            .field("base_url", &self.base_url)
+            .field("version_prefix", &self.version_prefix)
+            .field("api_type", &self.api_type)
+            .field("timeout", &self.timeout)
+            .field("proxies", &(self.proxies.len() + self.proxy_urls.len()))
+            .field("no_proxy", &self.no_proxy)
+            .field("retry", &self.retry)
+            .field("validate_requests", &self.validate_requests)
+            .field("default_headers", &hooks::redact(&self.default_headers))
+            .field("hooks", &self.hooks)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ClientBuilder {
+    /// Set the API key used to authenticate requests.
+    pub fn api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    /// Set the organization ID sent with each request.
+    pub fn org_id(mut self, org_id: &str) -> Self {
+        self.org_id = Some(org_id.to_string());
+        self
+    }
+
+    /// Set the project ID sent with each request (for
+    /// project-scoped API keys).
+    pub fn project_id(mut self, project_id: &str) -> Self {
+        self.project_id = Some(project_id.to_string());
+        self
+    }
+
+    /// Set the base URL requests are sent to.
+    ///
+    /// See `ohairs::ClientBuilder::base_url`.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// Set the version prefix the API's paths are sent
+    /// under, or `""` for none.
+    ///
+    /// See `ohairs::ClientBuilder::version_prefix`.
+    pub fn version_prefix(mut self, version_prefix: &str) -> Self {
+        self.version_prefix = Some(version_prefix.to_string());
+        self
+    }
+
+    /// Talk to the Azure OpenAI Service, sending model-specific
+    /// requests to the given deployment.
+    ///
+    /// The `base_url` should be set to your resource's endpoint.
+    pub fn azure(mut self, deployment: &str, api_version: &str) -> Self {
+        self.api_type = ApiType::Azure {
+            deployment: deployment.to_string(),
+            api_version: api_version.to_string(),
+        };
+        self
+    }
+
+    /// Set a timeout for each request, from when it starts
+    /// connecting until the response body has been read.
+    ///
+    /// Defaults to `reqwest::blocking::Client`'s default of
+    /// 30 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send requests through the given proxy.
+    ///
+    /// See `ohairs::ClientBuilder::proxy`.
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Send every request (`http` and `https`) through the
+    /// proxy at the given URL, e.g. `http://proxy.internal:3128`.
+    ///
+    /// The URL is parsed when the client is built. See `proxy`.
+    pub fn proxy_url(mut self, url: &str) -> Self {
+        self.proxy_urls.push(url.to_string());
+        self
+    }
+
+    /// Bypass the proxies for the given hosts, as a
+    /// comma-separated list (in the `NO_PROXY` format, e.g.
+    /// `localhost,10.0.0.0/8,.internal`).
+    pub fn no_proxy(mut self, hosts: &str) -> Self {
+        self.no_proxy = Some(hosts.to_string());
+        self
+    }
+
+    /// Send requests using the given
+    /// `reqwest::blocking::Client`, rather than one built
+    /// from the builder's settings.
+    ///
+    /// As with `ohairs::ClientBuilder::http_client`, this
+    /// can't be combined with `timeout`, `proxy`, `proxy_url`
+    /// or `no_proxy`.
+    pub fn http_client(mut self, req_client: reqwest::blocking::Client) -> Self {
+        self.req_client = Some(req_client);
+        self
+    }
+
+    /// Retry requests that fail with a transient error
+    /// (`429` or a `5xx`) up to `max_retries` times.
+    ///
+    /// Defaults to `0` (no retries).
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.retry.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry, which doubles
+    /// with each further retry up to `max_delay`.
+    ///
+    /// A `Retry-After` header on the failed response takes
+    /// precedence over these delays.
+    ///
+    /// Defaults to 500ms and 8s.
+    pub fn retry_delay(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.retry.base_delay = base_delay;
+        self.retry.max_delay = max_delay;
+        self
+    }
+
+    /// Override how idempotency keys are generated.
+    ///
+    /// See `ohairs::ClientBuilder::idempotency_key`.
+    pub fn idempotency_key(
+        mut self,
+        generate: impl Fn() -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.idempotency_key = Some(Arc::new(generate));
+        self
+    }
+
+    /// Check chat completion requests with
+    /// `ChatCompletionRequest::validate` before sending them,
+    /// returning an `Error::Validation` without a round-trip
+    /// if they're invalid.
+    ///
+    /// Defaults to `false`.
+    pub fn validate_requests(mut self, validate_requests: bool) -> Self {
+        self.validate_requests = validate_requests;
+        self
+    }
+
+    /// Set headers to send with every request.
+    ///
+    /// See `ohairs::ClientBuilder::default_headers`.
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers = headers;
+        self
+    }
+
+    /// Set a callback to run before each request is sent
+    /// (including retries), e.g. to log it.
+    ///
+    /// See `ohairs::ClientBuilder::on_request`.
+    pub fn on_request(mut self, hook: impl Fn(&RequestLog) + Send + Sync + 'static) -> Self {
+        self.hooks.on_request = Some(Arc::new(hook));
+        self
+    }
+
+    /// Set a callback to run after each response is
+    /// received, e.g. to log it.
+    ///
+    /// See `ohairs::ClientBuilder::on_response`.
+    pub fn on_response(mut self, hook: impl Fn(&ResponseLog) + Send + Sync + 'static) -> Self {
+        self.hooks.on_response = Some(Arc::new(hook));
+        self
+    }
+
+    /// Build the client.
+    ///
+    /// Returns an `Error::MissingApiKey` if no (non-empty) API
+    /// key was set.
+    pub fn build(self) -> Result<Client> {
+        let api_key = match self.api_key {
+            Some(api_key) if !api_key.trim().is_empty() => api_key,
+            _ => return Err(Error::MissingApiKey),
+        };
+
+        // Use the given HTTP client, or build one...
+        let req_client = match self.req_client {
+            Some(_) if self.timeout.is_some() => {
+                return Err(Error::Config(
+                    "A timeout can't be set when using a custom HTTP client".to_string(),
+                ))
+            }
+            Some(_)
+                if !self.proxies.is_empty()
+                    || !self.proxy_urls.is_empty()
+                    || self.no_proxy.is_some() =>
+            {
+                return Err(Error::Config(
+                    "A proxy can't be set when using a custom HTTP client".to_string(),
+                ))
+            }
+            Some(req_client) => req_client,
+            None => {
+                let mut req_client = reqwest::blocking::Client::builder();
+                if let Some(timeout) = self.timeout {
+                    req_client = req_client.timeout(timeout);
+                }
+
+                // Add the proxies, minus any excluded hosts...
+                let mut proxies = self.proxies;
+                for url in &self.proxy_urls {
+                    let proxy = reqwest::Proxy::all(url.as_str()).map_err(|err| {
+                        Error::Config(format!("Invalid proxy URL {:?}: {}", url, err))
+                    })?;
+                    proxies.push(proxy);
+                }
+                let no_proxy = self.no_proxy.as_deref().and_then(NoProxy::from_string);
+                for proxy in proxies {
+                    req_client = req_client.proxy(proxy.no_proxy(no_proxy.clone()));
+                }
+                req_client.build()?
+            }
+        };
+
+        // Build the client...
+        let mut client = Client::with_client(api_key.as_str(), req_client);
+        client.org_id = self.org_id;
+        client.project_id = self.project_id;
+        client.api_type = self.api_type;
+        if let Some(base_url) = self.base_url {
+            client.base_url = base_url;
+        }
+        if let Some(version_prefix) = self.version_prefix {
+            client.version_prefix = version_prefix;
+        }
+        client.retry = self.retry;
+        client.idempotency_key = self.idempotency_key;
+        client.validate_requests = self.validate_requests;
+        client.default_headers = self.default_headers;
+        client.hooks = self.hooks;
+        Ok(client)
+    }
+}
+
+/// Creates the multipart part for an uploaded file.
+fn file_part(file: Vec<u8>, filename: &str) -> reqwest::blocking::multipart::Part {
+    reqwest::blocking::multipart::Part::bytes(file).file_name(filename.to_string())
+}
+
+/// Returns the response unchanged if it has a success status,
+/// otherwise reads the body and returns it as an `Error::Api`.
+fn check_status(res: Response) -> Result<Response> {
+    // Is it a success?
+    let status = res.status();
+    if status.is_success() {
+        return Ok(res);
+    }
+
+    // Read the body for the error message...
+    let body = res.text()?;
+    Err(api_error(status, &body))
+}
+
+/// Checks the status of a response and parses its
+/// body as json.
+fn handle_response<T: DeserializeOwned>(res: Response) -> Result<T> {
+    // Check the status...
+    let res = check_status(res)?;
+
+    // Parse the response as json...
+    let body = res.bytes()?;
+    let data = serde_json::from_slice::<T>(&body)?;
+    Ok(data)
+}
+
+/// The size of each read from a streamed response body.
+const READ_BUF_SIZE: usize = 8 * 1024;
+
+/// Reads a response body and yields the chunks it contains,
+/// each event parsed by `parse`.
+///
+/// See `SseDecoder` for how reads are split into events.
+struct ChunkIter<R, T> {
+    /// The response body.
+    body: R,
+
+    /// Parses each event into a chunk.
+    parse: fn(&str) -> Result<ChunkEvent<T>>,
+
+    /// Splits the body into events.
+    decoder: SseDecoder,
+
+    /// Has the body been fully read?
+    eof: bool,
+
+    /// Has the stream ended (either `[DONE]` or an error)?
+    finished: bool,
+}
+
+impl<R: Read, T> ChunkIter<R, T> {
+    fn new(body: R, parse: fn(&str) -> Result<ChunkEvent<T>>) -> Self {
+        Self {
+            body,
+            parse,
+            decoder: SseDecoder::new(),
+            eof: false,
+            finished: false,
+        }
+    }
+}
+
+impl<R: Read, T> Iterator for ChunkIter<R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = [0; READ_BUF_SIZE];
+        loop {
+            // Has the stream already ended?
+            if self.finished {
+                return None;
+            }
+
+            // Is there a complete event?
+            if let Some(event) = self.decoder.next_event() {
+                let res = event.and_then(|event| (self.parse)(&event));
+                match res {
+                    Ok(ChunkEvent::Chunk(chunk)) => return Some(Ok(chunk)),
+                    Ok(ChunkEvent::KeepAlive) => continue,
+                    Ok(ChunkEvent::Done) => {
+                        self.finished = true;
+                        return None;
+                    }
+                    Err(err) => {
+                        self.finished = true;
+                        return Some(Err(err));
+                    }
+                }
+            }
+
+            // Was that the last of the body?
+            if self.eof {
+                self.finished = true;
+                return None;
+            }
+
+            // Otherwise, read some more...
+            match self.body.read(&mut buf) {
+                Ok(0) => {
+                    self.eof = true;
+                    self.decoder.finish();
+                }
+                Ok(n) => self.decoder.feed(&buf[..n]),
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(err.into()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dtypes::{
+        AudioResponseFormat, BatchStatus, FineTuningJobStatus, FinishReason, ImageResponseFormat,
+        ImageSize,
+    };
+    use crate::Error;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread::JoinHandle;
+
+    /// Starts a server that answers each request with the next
+    /// of the given statuses and bodies, and returns its URL
+    /// along with a handle yielding the request line and body
+    /// of each request it received.
+    fn serve(responses: Vec<(u16, &'static str)>) -> (String, JoinHandle<Vec<(String, String)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (conn, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(conn);
+
+                // Read the request line and headers...
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+
+                // Read the body...
+                let mut req_body = vec![0; content_length];
+                reader.read_exact(&mut req_body).unwrap();
+
+                // Send the response...
+                let res = format!(
+                    "HTTP/1.1 {} X\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                reader.get_mut().write_all(res.as_bytes()).unwrap();
+                requests.push((
+                    request_line.trim().to_string(),
+                    String::from_utf8_lossy(&req_body).into_owned(),
+                ));
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    /// Starts a server that answers a single request with the
+    /// given status and body, and returns its URL along with a
+    /// handle yielding the request line and body it received.
+    fn serve_once(status: u16, body: &'static str) -> (String, JoinHandle<(String, String)>) {
+        let (url, handle) = serve(vec![(status, body)]);
+        let handle = std::thread::spawn(move || handle.join().unwrap().remove(0));
+        (url, handle)
+    }
+
+    #[test]
+    fn blocking_list_models_round_trip() {
+        let (url, handle) = serve_once(
+            200,
+            r#"{"object": "list", "data": [{"id": "gpt-4o", "object": "model", "created": 1686935002, "owned_by": "openai"}]}"#,
+        );
+        let mut client = Client::new("test");
+        client.base_url = url;
+
+        let models = client.list_models().unwrap();
+        assert_eq!(models.data[0].id, "gpt-4o");
+        assert_eq!(handle.join().unwrap().0, "GET /v1/models HTTP/1.1");
+
+        // A rate limited request is retried, like with the async client...
+        let (url, handle) = serve(vec![
+            (
+                429,
+                r#"{"error": {"message": "Rate limit reached", "type": "requests", "param": null, "code": "rate_limit_exceeded"}}"#,
+            ),
+            (
+                200,
+                r#"{"object": "list", "data": [{"id": "gpt-4o", "object": "model", "created": 1686935002, "owned_by": "openai"}]}"#,
+            ),
+        ]);
+        let sent = Arc::new(AtomicUsize::new(0));
+        let counter = sent.clone();
+        let client = Client::builder()
+            .api_key("test")
+            .base_url(&url)
+            .max_retries(1)
+            .retry_delay(Duration::from_millis(1), Duration::from_millis(1))
+            .on_request(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .build()
+            .unwrap();
+
+        let models = client.list_models().unwrap();
+        assert_eq!(models.data[0].id, "gpt-4o");
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+        assert_eq!(handle.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn blocking_from_lookup_reads_variables() {
+        // A missing key is an error naming the variable...
+        let err = Client::from_lookup(|_| None).unwrap_err();
+        assert!(matches!(err, Error::MissingEnvVar(ref name) if name == "OPENAI_API_KEY"));
+
+        // The optional settings are applied when set...
+        let client = Client::from_lookup(|name| match name {
+            API_KEY_ENV_VAR => Some("sk-test".to_string()),
+            ORG_ID_ENV_VAR => Some("org-123".to_string()),
+            BASE_URL_ENV_VAR => Some("http://localhost:1323".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(client.api_key, "sk-test");
+        assert_eq!(client.org_id.as_deref(), Some("org-123"));
+        assert_eq!(client.project_id, None);
+        assert_eq!(client.base_url, "http://localhost:1323");
+    }
+
+    #[test]
+    fn blocking_builder_validate_requests_checks_before_sending() {
+        // The base URL is never reached, since validation fails first...
+        let client = Client::builder()
+            .api_key("test")
+            .base_url("http://127.0.0.1:9")
+            .validate_requests(true)
+            .build()
+            .unwrap();
+        let req = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            top_p: Some(2.0),
+            ..Default::default()
+        };
+        let err = client.create_chat_completion(req.clone()).unwrap_err();
+        assert!(matches!(err, Error::Validation(ref msg) if msg.contains("top_p")));
+        let err = client
+            .create_chat_completion_stream(req)
+            .err()
+            .expect("expected validation to fail");
+        assert!(matches!(err, Error::Validation(_)));
+    }
+
+    #[test]
+    fn blocking_chat_completion_round_trip() {
+        let (url, handle) = serve_once(
+            200,
+            r#"{"id": "chatcmpl-123", "object": "chat.completion", "created": 1677652288, "model": "gpt-4o", "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi!"}, "finish_reason": "stop"}], "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}}"#,
+        );
+        let mut client = Client::new("test");
+        client.base_url = url;
+
+        let res = client
+            .create_chat_completion(ChatCompletionRequest {
+                model: "gpt-4o".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let content = res.choices[0].message.content.as_ref();
+        assert_eq!(content.and_then(|c| c.as_text()), Some("Hi!"));
+        assert_eq!(
+            handle.join().unwrap().0,
+            "POST /v1/chat/completions HTTP/1.1"
+        );
+    }
+
+    #[test]
+    fn blocking_parses_error_envelope() {
+        let (url, handle) = serve_once(
+            401,
+            r#"{"error": {"message": "Incorrect API key provided", "type": "invalid_request_error", "param": null, "code": "invalid_api_key"}}"#,
+        );
+        let mut client = Client::new("test");
+        client.base_url = url;
+
+        let err = client.list_models().unwrap_err();
+        match err {
+            Error::Api { status, error } => {
+                assert_eq!(status, 401);
+                assert_eq!(error.message, "Incorrect API key provided");
+                assert_eq!(error.code.as_deref(), Some("invalid_api_key"));
+            }
+            err => panic!("expected an API error, got {:?}", err),
+        }
+        handle.join().unwrap();
+    }
+
+    /// A reader that returns its data a few bytes at a time,
+    /// like a slow network connection.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.step.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn blocking_chunk_iter_stops_at_done() {
+        let body = "data: {\"id\": \"a\", \"object\": \"chat.completion.chunk\", \"created\": 1, \"model\": \"m\", \"choices\": []}\n\n\
+            data: {\"id\": \"b\", \"object\": \"chat.completion.chunk\", \"created\": 1, \"model\": \"m\", \"choices\": []}\n\n\
+            data: [DONE]\n\n\
+            data: {\"id\": \"c\"}\n\n";
+
+        // Define cases to test...
+        let steps = vec![1, 7, body.len()];
+
+        // Iterate over cases and test...
+        for step in steps {
+            let iter = ChunkIter::new(
+                Trickle {
+                    data: body.as_bytes(),
+                    step,
+                },
+                ChatCompletionChunk::parse_event,
+            );
+            let ids: Vec<String> = iter.map(|chunk| chunk.unwrap().id).collect();
+            assert_eq!(ids, vec!["a", "b"], "failed with step {}", step);
+        }
+    }
+
+    #[test]
+    fn blocking_chunk_iter_stops_after_error() {
+        let body = b"data: {\"id\": \"a\", \"object\": \"chat.completion.chunk\", \"created\": 1, \"model\": \"m\", \"choices\": []}\n\nnot an event\n\ndata: [DONE]\n\n";
+        let mut iter = ChunkIter::new(&body[..], ChatCompletionChunk::parse_event);
+        assert_eq!(iter.next().unwrap().unwrap().id, "a");
+        assert!(matches!(iter.next(), Some(Err(Error::InvalidChunk(_)))));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn blocking_upload_file_sends_multipart() {
+        let (url, handle) = serve_once(
+            200,
+            r#"{"id": "file-abc123", "object": "file", "bytes": 5, "created_at": 1677610602, "filename": "data.jsonl", "purpose": "fine-tune", "status": "uploaded"}"#,
+        );
+        let mut client = Client::new("test");
+        client.base_url = url;
+
+        // Upload the file...
+        let file = client
+            .upload_file(b"hello".to_vec(), "data.jsonl", "fine-tune")
+            .unwrap();
+        assert_eq!(file.id, "file-abc123");
+        assert_eq!(file.status.as_deref(), Some("uploaded"));
+
+        // Check the form had the file and its purpose...
+        let (request_line, body) = handle.join().unwrap();
+        assert_eq!(request_line, "POST /v1/files HTTP/1.1");
+        assert!(body.contains("name=\"purpose\"\r\n\r\nfine-tune"));
+        assert!(body.contains("name=\"file\"; filename=\"data.jsonl\""));
+        assert!(body.contains("hello"));
+    }
+
+    #[test]
+    fn blocking_cancel_fine_tuning_job() {
+        let (url, handle) = serve_once(
+            200,
+            r#"{"object": "fine_tuning.job", "id": "ftjob-abc123", "model": "gpt-4o-mini", "created_at": 1721764800, "status": "cancelled", "training_file": "file-abc123"}"#,
+        );
+        let mut client = Client::new("test");
+        client.base_url = url;
+
+        let job = client.cancel_fine_tuning_job("ftjob-abc123").unwrap();
+        assert_eq!(job.status, FineTuningJobStatus::Cancelled);
+        assert_eq!(
+            handle.join().unwrap().0,
+            "POST /v1/fine_tuning/jobs/ftjob-abc123/cancel HTTP/1.1"
+        );
+    }
+
+    #[test]
+    fn blocking_cancel_batch() {
+        let (url, handle) = serve_once(
+            200,
+            r#"{"id": "batch_abc123", "object": "batch", "endpoint": "/v1/chat/completions", "input_file_id": "file-abc123", "completion_window": "24h", "status": "cancelling", "created_at": 1711471533}"#,
+        );
+        let mut client = Client::new("test");
+        client.base_url = url;
+
+        let batch = client.cancel_batch("batch_abc123").unwrap();
+        assert_eq!(batch.status, BatchStatus::Cancelling);
+        assert_eq!(
+            handle.join().unwrap().0,
+            "POST /v1/batches/batch_abc123/cancel HTTP/1.1"
+        );
+    }
+
+    #[test]
+    fn blocking_create_completion_stream() {
+        let body = "data: {\"id\": \"cmpl-1\", \"object\": \"text_completion\", \"created\": 1, \"model\": \"m\", \"choices\": [{\"text\": \"Hel\", \"index\": 0, \"logprobs\": null, \"finish_reason\": null}]}\n\n\
+            data: {\"id\": \"cmpl-1\", \"object\": \"text_completion\", \"created\": 1, \"model\": \"m\", \"choices\": [{\"text\": \"lo\", \"index\": 0, \"logprobs\": null, \"finish_reason\": \"stop\"}]}\n\n\
+            data: [DONE]\n\n";
+        let (url, handle) = serve_once(200, body);
+        let mut client = Client::new("test");
+        client.base_url = url;
+
+        let req = CompletionRequest {
+            model: "m".to_string(),
+            ..Default::default()
+        };
+        let chunks = client
+            .create_completion_stream(req)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let text: String = chunks.iter().filter_map(|chunk| chunk.text()).collect();
+        assert_eq!(text, "Hello");
+
+        let (request_line, body) = handle.join().unwrap();
+        assert!(request_line.starts_with("POST /v1/completions "));
+        assert!(body.contains("\"stream\":true"), "{}", body);
+    }
+
+    #[test]
+    fn blocking_create_chat_completion_events() {
+        let body = "data: {\"id\": \"chatcmpl-1\", \"object\": \"chat.completion.chunk\", \"created\": 1, \"model\": \"m\", \"choices\": [{\"index\": 0, \"delta\": {\"role\": \"assistant\", \"content\": \"Hi\"}, \"finish_reason\": null}]}\n\n\
+            data: {\"id\": \"chatcmpl-1\", \"object\": \"chat.completion.chunk\", \"created\": 1, \"model\": \"m\", \"choices\": [{\"index\": 0, \"delta\": {}, \"finish_reason\": \"stop\"}]}\n\n\
+            data: [DONE]\n\n";
+        let (url, handle) = serve_once(200, body);
+        let mut client = Client::new("test");
+        client.base_url = url;
+
+        let req = ChatCompletionRequest {
+            model: "m".to_string(),
+            ..Default::default()
+        };
+        let events = client
+            .create_chat_completion_events(req)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Start {
+                    role: Role::Assistant
+                },
+                StreamEvent::Content("Hi".to_string()),
+                StreamEvent::Done {
+                    finish_reason: FinishReason::Stop
+                },
+            ]
+        );
+        assert!(handle.join().unwrap().1.contains("\"stream\":true"));
+    }
+
+    #[test]
+    fn blocking_create_image_edit_sends_multipart() {
+        let (url, handle) = serve_once(
+            200,
+            r#"{"created": 1589478378, "data": [{"url": "https://example.com/edited.png"}]}"#,
+        );
+        let mut client = Client::new("test");
+        client.base_url = url;
+
+        // Edit the image...
+        let res = client
+            .create_image_edit(CreateImageEditRequest {
+                image: b"<image>".to_vec(),
+                mask: Some(b"<mask>".to_vec()),
+                prompt: "Add a hat".to_string(),
+                n: Some(2),
+                size: Some(ImageSize::Size512),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(res.data.len(), 1);
+
+        // Check the form had the files and fields...
+        let (request_line, body) = handle.join().unwrap();
+        assert_eq!(request_line, "POST /v1/images/edits HTTP/1.1");
+        assert!(body.contains("name=\"prompt\"\r\n\r\nAdd a hat"));
+        assert!(body.contains("name=\"n\"\r\n\r\n2"));
+        assert!(body.contains("name=\"size\"\r\n\r\n512x512"));
+        assert!(body.contains("name=\"image\"; filename=\"image.png\""));
+        assert!(body.contains("name=\"mask\"; filename=\"mask.png\""));
+        assert!(!body.contains("name=\"model\""));
+    }
+
+    #[test]
+    fn blocking_create_image_variation_sends_multipart() {
+        let (url, handle) = serve_once(
+            200,
+            r#"{"created": 1589478378, "data": [{"b64_json": "aGVsbG8="}]}"#,
+        );
+        let mut client = Client::new("test");
+        client.base_url = url;
+
+        client
+            .create_image_variation(CreateImageVariationRequest {
+                image: b"<image>".to_vec(),
+                response_format: Some(ImageResponseFormat::B64Json),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let (request_line, body) = handle.join().unwrap();
+        assert_eq!(request_line, "POST /v1/images/variations HTTP/1.1");
+        assert!(body.contains("name=\"response_format\"\r\n\r\nb64_json"));
+        assert!(body.contains("name=\"image\"; filename=\"image.png\""));
+        assert!(!body.contains("name=\"prompt\""));
+    }
+
+    #[test]
+    fn blocking_create_translation() {
+        // Define cases to test...
+        let cases = vec![
+            (None, r#"{"text": "Hello, world."}"#, "Hello, world."),
+            (
+                Some(AudioResponseFormat::Text),
+                "Hello, world.\n",
+                "Hello, world.\n",
+            ),
+        ];
+
+        // Iterate over cases and test...
+        for (response_format, res_body, expected) in cases {
+            let (url, handle) = serve_once(200, res_body);
+            let mut client = Client::new("test");
+            client.base_url = url;
+
+            let res = client
+                .create_translation(CreateTranslationRequest {
+                    file: b"<audio>".to_vec(),
+                    filename: "speech.mp3".to_string(),
+                    model: "whisper-1".to_string(),
+                    response_format,
+                    ..Default::default()
+                })
+                .unwrap();
+            assert_eq!(res.text, expected);
+
+            let (request_line, body) = handle.join().unwrap();
+            assert_eq!(request_line, "POST /v1/audio/translations HTTP/1.1");
+            assert!(body.contains("name=\"model\"\r\n\r\nwhisper-1"));
+            assert!(body.contains("name=\"file\"; filename=\"speech.mp3\""));
+        }
+    }
+
+    #[test]
+    fn blocking_list_all_files_follows_cursors() {
+        let (url, handle) = serve(vec![
+            (
+                200,
+                r#"{"object": "list", "data": [{"id": "file-1", "object": "file", "bytes": 1, "created_at": 1, "filename": "a.jsonl", "purpose": "batch"}], "has_more": true}"#,
+            ),
+            (
+                200,
+                r#"{"object": "list", "data": [{"id": "file-2", "object": "file", "bytes": 1, "created_at": 1, "filename": "b.jsonl", "purpose": "batch"}], "has_more": false}"#,
+            ),
+        ]);
+        let mut client = Client::new("test");
+        client.base_url = url;
+
+        let files = client.list_all_files().unwrap();
+        let ids: Vec<_> = files.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, vec!["file-1", "file-2"]);
+
+        // The second page starts after the last file of the first...
+        let requests = handle.join().unwrap();
+        assert_eq!(requests[0].0, "GET /v1/files HTTP/1.1");
+        assert_eq!(requests[1].0, "GET /v1/files?after=file-1 HTTP/1.1");
+    }
+}
@@ -0,0 +1,213 @@
+use futures_core::Stream;
+
+use crate::dtypes::{
+    ChatCompletionChunk, ChatCompletionMessage, ChatCompletionObject, ChatCompletionRequest, Role,
+};
+use crate::{Client, Result};
+
+/// A fluent builder for a chat completion request, bound
+/// to the client that will send it.
+///
+/// Created with `Client::chat`.
+///
+/// # Example
+///
+/// ```no_run
+/// use ohairs::Client;
+///
+/// # async fn run() -> ohairs::Result<()> {
+/// let client = Client::new("test");
+/// let res = client
+///     .chat("gpt-4o")
+///     .system("Be terse.")
+///     .user("Hi!")
+///     .temperature(0.2)
+///     .send()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ChatBuilder<'a> {
+    client: &'a Client,
+    req: ChatCompletionRequest,
+}
+
+impl<'a> ChatBuilder<'a> {
+    pub(crate) fn new(client: &'a Client, model: impl Into<String>) -> Self {
+        Self {
+            client,
+            req: ChatCompletionRequest {
+                model: model.into(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Append a message to the conversation.
+    pub fn message(mut self, message: ChatCompletionMessage) -> Self {
+        self.req.messages.push(message);
+        self
+    }
+
+    /// Append a `system` message to the conversation.
+    pub fn system(self, content: &str) -> Self {
+        self.message_with_role(Role::System, content)
+    }
+
+    /// Append a `user` message to the conversation.
+    pub fn user(self, content: &str) -> Self {
+        self.message_with_role(Role::User, content)
+    }
+
+    /// Append an `assistant` message to the conversation.
+    pub fn assistant(self, content: &str) -> Self {
+        self.message_with_role(Role::Assistant, content)
+    }
+
+    /// Append a `function` message with the result of the
+    /// named function (for the legacy `functions` API).
+    ///
+    /// See `ChatCompletionMessage::function_result`.
+    pub fn function_result(self, name: &str, result: &str) -> Self {
+        self.message(ChatCompletionMessage::function_result(name, result))
+    }
+
+    /// Append a `tool` message with the result of the tool
+    /// call with the given ID.
+    ///
+    /// See `ChatCompletionMessage::tool_result`.
+    pub fn tool_result(self, tool_call_id: &str, content: &str) -> Self {
+        self.message(ChatCompletionMessage::tool_result(tool_call_id, content))
+    }
+
+    fn message_with_role(self, role: Role, content: &str) -> Self {
+        self.message(ChatCompletionMessage {
+            role,
+            content: Some(content.into()),
+            ..Default::default()
+        })
+    }
+
+    /// Set the sampling temperature.
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.req.temperature = Some(temperature);
+        self
+    }
+
+    /// Set the nucleus sampling probability mass.
+    pub fn top_p(mut self, top_p: f64) -> Self {
+        self.req.top_p = Some(top_p);
+        self
+    }
+
+    /// Set the number of choices to generate.
+    pub fn n(mut self, n: u64) -> Self {
+        self.req.n = Some(n);
+        self
+    }
+
+    /// Set the maximum number of tokens to generate.
+    pub fn max_tokens(mut self, max_tokens: u64) -> Self {
+        self.req.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Set the seed for best-effort deterministic sampling.
+    pub fn seed(mut self, seed: i64) -> Self {
+        self.req.seed = Some(seed);
+        self
+    }
+
+    /// Set the presence penalty.
+    pub fn presence_penalty(mut self, presence_penalty: f64) -> Self {
+        self.req.presence_penalty = Some(presence_penalty);
+        self
+    }
+
+    /// Set the frequency penalty.
+    pub fn frequency_penalty(mut self, frequency_penalty: f64) -> Self {
+        self.req.frequency_penalty = Some(frequency_penalty);
+        self
+    }
+
+    /// Set the end-user ID sent for abuse monitoring (the
+    /// request's `user` field).
+    ///
+    /// Named `user_id` since `user` appends a user message.
+    pub fn user_id(mut self, user_id: &str) -> Self {
+        self.req.user = Some(user_id.to_string());
+        self
+    }
+
+    /// Return the request that would be sent, without
+    /// sending it.
+    pub fn build(self) -> ChatCompletionRequest {
+        self.req
+    }
+
+    /// Send the request.
+    pub async fn send(self) -> Result<ChatCompletionObject> {
+        self.client.create_chat_completion(self.req).await
+    }
+
+    /// Send the request, streaming back the response.
+    ///
+    /// See `Client::create_chat_completion_stream`.
+    pub async fn stream(
+        self,
+    ) -> Result<impl Stream<Item = Result<ChatCompletionChunk>> + Unpin + Send> {
+        self.client.create_chat_completion_stream(self.req).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn chat_builder_builds_request() {
+        let client = Client::new("test");
+        let req = client
+            .chat("gpt-4o")
+            .system("Be terse.")
+            .user("Hi!")
+            .temperature(0.2)
+            .max_tokens(10)
+            .user_id("user-1234")
+            .build();
+
+        assert_eq!(req.model, "gpt-4o");
+        assert_eq!(req.temperature, Some(0.2));
+        assert_eq!(req.max_tokens, Some(10));
+        assert_eq!(req.user.as_deref(), Some("user-1234"));
+        let roles: Vec<_> = req.messages.iter().map(|m| m.role).collect();
+        assert_eq!(roles, vec![Role::System, Role::User]);
+        assert_eq!(
+            req.messages[1].content.as_ref().and_then(|c| c.as_text()),
+            Some("Hi!")
+        );
+    }
+
+    #[test]
+    fn chat_builder_appends_results() {
+        let client = Client::new("test");
+        let req = client
+            .chat("gpt-4o")
+            .user("What's the weather?")
+            .function_result("get_weather", "{\"temp\": 72}")
+            .tool_result("call_abc123", "{\"temp\": 72}")
+            .build();
+
+        // Check the results have the fields the API requires...
+        let function = &req.messages[1];
+        assert_eq!(function.role, Role::Function);
+        assert_eq!(function.name.as_deref(), Some("get_weather"));
+        let tool = &req.messages[2];
+        assert_eq!(tool.role, Role::Tool);
+        assert_eq!(tool.tool_call_id.as_deref(), Some("call_abc123"));
+        assert_eq!(
+            tool.content.as_ref().and_then(|c| c.as_text()),
+            Some("{\"temp\": 72}")
+        );
+    }
+}
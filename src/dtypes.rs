@@ -1,6 +1,205 @@
-use anyhow::{anyhow, Result};
-use serde::{Deserialize, Serialize};
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign};
+
+use crate::page::HasId;
+use crate::{Error, Result};
+
+/// Deserializes a unix timestamp that may be either a JSON
+/// number or a numeric string (e.g. `"1677652288"`), since
+/// some OpenAI-compatible backends send the latter.
+fn deserialize_timestamp<'de, D>(deserializer: D) -> std::result::Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timestamp {
+        Number(u64),
+        String(String),
+    }
+
+    match Timestamp::deserialize(deserializer)? {
+        Timestamp::Number(n) => Ok(n),
+        Timestamp::String(s) => s.trim().parse().map_err(|_| {
+            serde::de::Error::custom(format!("expected a numeric timestamp, got {:?}", s))
+        }),
+    }
+}
+
+/// Deserializes an embedding that may be either a JSON array
+/// of floats or, when requested with `EncodingFormat::Base64`,
+/// a base64 string of little-endian `f32`s.
+fn deserialize_embedding<'de, D>(deserializer: D) -> std::result::Result<Vec<f32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Embedding {
+        Float(Vec<f32>),
+        Base64(String),
+    }
+
+    match Embedding::deserialize(deserializer)? {
+        Embedding::Float(v) => Ok(v),
+        Embedding::Base64(s) => {
+            let bytes = BASE64_STANDARD.decode(s.trim()).map_err(|err| {
+                serde::de::Error::custom(format!("invalid base64 embedding: {}", err))
+            })?;
+            if bytes.len() % 4 != 0 {
+                return Err(serde::de::Error::custom(format!(
+                    "expected a multiple of 4 bytes in a base64 embedding, got {}",
+                    bytes.len()
+                )));
+            }
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect())
+        }
+    }
+}
+
+/// Converts a unix timestamp (in seconds) to a UTC datetime,
+/// saturating at chrono's maximum for out-of-range values.
+#[cfg(feature = "chrono")]
+fn timestamp_to_datetime(secs: u64) -> chrono::DateTime<chrono::Utc> {
+    i64::try_from(secs)
+        .ok()
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .unwrap_or(chrono::DateTime::<chrono::Utc>::MAX_UTC)
+}
+
+/// A model identifier, for catching typos in common model
+/// names at compile time.
+///
+/// Any other model (a fine-tune, a new release, or a local
+/// model) can still be named with `Custom`. Requests keep
+/// taking a `String`, so use `Model` wherever a model name is
+/// accepted as `impl Into<String>`, or call `to_string`.
+///
+/// # Example
+///
+/// ```
+/// use ohairs::dtypes::Model;
+///
+/// assert_eq!(Model::Gpt4oMini.to_string(), "gpt-4o-mini");
+/// assert_eq!(Model::from("gpt-4o"), Model::Gpt4o);
+/// assert_eq!(
+///     Model::from("ft:gpt-4o-mini:my-org::abc123"),
+///     Model::Custom("ft:gpt-4o-mini:my-org::abc123".to_string()),
+/// );
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum Model {
+    /// `gpt-4o`
+    Gpt4o,
+
+    /// `gpt-4o-mini`
+    Gpt4oMini,
+
+    /// `gpt-4-turbo`
+    Gpt4Turbo,
+
+    /// `gpt-4`
+    Gpt4,
+
+    /// `gpt-3.5-turbo`
+    Gpt35Turbo,
+
+    /// `gpt-3.5-turbo-instruct`, for the legacy completions
+    /// endpoint.
+    Gpt35TurboInstruct,
+
+    /// `o1`
+    O1,
+
+    /// `o1-mini`
+    O1Mini,
+
+    /// `text-embedding-3-small`
+    TextEmbedding3Small,
+
+    /// `text-embedding-3-large`
+    TextEmbedding3Large,
+
+    /// `text-embedding-ada-002`
+    TextEmbeddingAda002,
+
+    /// Any other model, by its exact name.
+    Custom(String),
+}
+
+impl Model {
+    /// Returns the model's name, as sent to the API.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Model::Gpt4o => "gpt-4o",
+            Model::Gpt4oMini => "gpt-4o-mini",
+            Model::Gpt4Turbo => "gpt-4-turbo",
+            Model::Gpt4 => "gpt-4",
+            Model::Gpt35Turbo => "gpt-3.5-turbo",
+            Model::Gpt35TurboInstruct => "gpt-3.5-turbo-instruct",
+            Model::O1 => "o1",
+            Model::O1Mini => "o1-mini",
+            Model::TextEmbedding3Small => "text-embedding-3-small",
+            Model::TextEmbedding3Large => "text-embedding-3-large",
+            Model::TextEmbeddingAda002 => "text-embedding-ada-002",
+            Model::Custom(name) => name,
+        }
+    }
+}
+
+impl std::fmt::Display for Model {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for Model {
+    /// Parses a model name, falling back to `Custom` for
+    /// names that aren't known.
+    fn from(name: &str) -> Self {
+        match name {
+            "gpt-4o" => Model::Gpt4o,
+            "gpt-4o-mini" => Model::Gpt4oMini,
+            "gpt-4-turbo" => Model::Gpt4Turbo,
+            "gpt-4" => Model::Gpt4,
+            "gpt-3.5-turbo" => Model::Gpt35Turbo,
+            "gpt-3.5-turbo-instruct" => Model::Gpt35TurboInstruct,
+            "o1" => Model::O1,
+            "o1-mini" => Model::O1Mini,
+            "text-embedding-3-small" => Model::TextEmbedding3Small,
+            "text-embedding-3-large" => Model::TextEmbedding3Large,
+            "text-embedding-ada-002" => Model::TextEmbeddingAda002,
+            name => Model::Custom(name.to_string()),
+        }
+    }
+}
+
+impl From<String> for Model {
+    fn from(name: String) -> Self {
+        match Model::from(name.as_str()) {
+            Model::Custom(_) => Model::Custom(name),
+            model => model,
+        }
+    }
+}
+
+impl From<Model> for String {
+    fn from(model: Model) -> Self {
+        match model {
+            Model::Custom(name) => name,
+            model => model.as_str().to_string(),
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 pub struct ChatCompletionRequest {
@@ -17,6 +216,7 @@ pub struct ChatCompletionRequest {
 
     /// A list of functions the model may generate
     // JSON inputs for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub functions: Vec<Function>,
 
     /// Controls how the model responds to function calls.
@@ -30,6 +230,26 @@ pub struct ChatCompletionRequest {
     /// default if functions are present.
     pub function_call: Option<FunctionCallType>,
 
+    /// A list of tools the model may call. Currently, only
+    /// functions are supported as tools.
+    ///
+    /// Supersedes `functions`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+
+    /// Controls which (if any) tool is called by the model.
+    ///
+    /// Supersedes `function_call`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+
+    /// Whether the model may call several tools at once.
+    /// Set to `false` to make it call at most one per turn.
+    ///
+    /// Defaults to `true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+
     /// What sampling temperature to use, between 0 and 2.
     /// Higher values like 0.8 will make the output more random,
     /// while lower values like 0.2 will make it more focused
@@ -65,6 +285,11 @@ pub struct ChatCompletionRequest {
     /// Defaults to `false`.
     pub stream: Option<bool>,
 
+    /// Options for streamed responses. Only set this when
+    /// `stream` is `true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+
     /// Up to 4 sequences where the API will stop generating further tokens.
     ///
     /// Defaults to `null`.
@@ -92,6 +317,35 @@ pub struct ChatCompletionRequest {
     /// Defaults to `0`.
     pub frequency_penalty: Option<f64>,
 
+    /// If specified, the system will make a best effort to sample
+    /// deterministically, such that repeated requests with the same
+    /// `seed` and parameters should return the same result.
+    ///
+    /// Determinism is not guaranteed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+
+    /// Whether to return the log probabilities of the output
+    /// tokens in each choice's `logprobs`.
+    ///
+    /// Defaults to `false`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<bool>,
+
+    /// The number of most likely tokens (between 0 and 20) to
+    /// return at each position, along with their log
+    /// probabilities. `logprobs` must be `true` if set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_logprobs: Option<u8>,
+
+    /// The format the model must output.
+    ///
+    /// Use `ResponseFormat::JsonObject` to enable JSON mode,
+    /// or `ResponseFormat::JsonSchema` for structured outputs
+    /// that match a given schema.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+
     /// Modify the likelihood of specified tokens appearing in the completion.
     ///
     /// Accepts a json object that maps tokens (specified by their token
@@ -107,9 +361,215 @@ pub struct ChatCompletionRequest {
 
     /// A unique identifier representing your end-user, which can help
     /// OpenAI to monitor and detect abuse.
+    ///
+    /// At most `MAX_USER_LENGTH` characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
 }
 
+impl ChatCompletionRequest {
+    /// Checks the request's parameters are within the ranges
+    /// the API accepts, without sending it.
+    ///
+    /// Returns an `Error::Validation` naming the first field
+    /// that's out of range.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::dtypes::ChatCompletionRequest;
+    ///
+    /// let req = ChatCompletionRequest {
+    ///     model: "gpt-4o".to_string(),
+    ///     temperature: Some(2.5),
+    ///     ..Default::default()
+    /// };
+    /// let err = req.validate().unwrap_err();
+    /// assert!(err.to_string().contains("temperature"));
+    /// ```
+    pub fn validate(&self) -> Result<()> {
+        check_range("temperature", self.temperature, 0.0, 2.0)?;
+        check_range("top_p", self.top_p, 0.0, 1.0)?;
+        check_range("presence_penalty", self.presence_penalty, -2.0, 2.0)?;
+        check_range("frequency_penalty", self.frequency_penalty, -2.0, 2.0)?;
+        if self.n == Some(0) {
+            return Err(Error::Validation("n must be at least 1".to_string()));
+        }
+        if let Some(top_logprobs) = self.top_logprobs {
+            if top_logprobs > 20 {
+                return Err(Error::Validation(format!(
+                    "top_logprobs must be at most 20, got {}",
+                    top_logprobs
+                )));
+            }
+        }
+        if let Some(logit_bias) = &self.logit_bias {
+            check_logit_bias(logit_bias)?;
+        }
+        if let Some(user) = &self.user {
+            let len = user.chars().count();
+            if len > MAX_USER_LENGTH {
+                return Err(Error::Validation(format!(
+                    "user must be at most {} characters, got {}",
+                    MAX_USER_LENGTH, len
+                )));
+            }
+        }
+        if let Some(StopToken::MultipleTokens(tokens)) = &self.stop {
+            if tokens.len() > MAX_STOP_SEQUENCES {
+                return Err(Error::Validation(format!(
+                    "stop can have at most {} sequences, got {}",
+                    MAX_STOP_SEQUENCES,
+                    tokens.len()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a copy of the request with the first choice's
+    /// (partial) message from `res` appended, to ask the
+    /// model to keep going after a truncated response.
+    ///
+    /// See `ChatCompletionObject::is_truncated`.
+    pub fn continuation(&self, res: &ChatCompletionObject) -> ChatCompletionRequest {
+        let mut req = self.clone();
+        req.messages.extend(res.first_message().cloned());
+        req
+    }
+}
+
+/// Checks an optional parameter is within `min..=max`.
+fn check_range(name: &str, value: Option<f64>, min: f64, max: f64) -> Result<()> {
+    match value {
+        Some(value) if !(min..=max).contains(&value) => Err(Error::Validation(format!(
+            "{} must be between {} and {}, got {}",
+            name, min, max, value
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Checks every `logit_bias` key is a token ID and every
+/// bias is within `-100..=100`.
+pub(crate) fn check_logit_bias(logit_bias: &HashMap<String, f64>) -> Result<()> {
+    for (token, bias) in logit_bias {
+        if token.parse::<u32>().is_err() {
+            return Err(Error::Validation(format!(
+                "logit_bias keys must be token IDs, got {:?}",
+                token
+            )));
+        }
+        check_range(
+            &format!("logit_bias[{:?}]", token),
+            Some(*bias),
+            -100.0,
+            100.0,
+        )?;
+    }
+    Ok(())
+}
+
+/// The most `metadata` key-value pairs the API accepts on
+/// an object (e.g. a thread or run).
+pub const MAX_METADATA_PAIRS: usize = 16;
+
+/// The longest `metadata` key the API accepts, in characters.
+pub const MAX_METADATA_KEY_LENGTH: usize = 64;
+
+/// The longest `metadata` value the API accepts, in
+/// characters.
+pub const MAX_METADATA_VALUE_LENGTH: usize = 512;
+
+/// Checks `metadata` is within the API's limits of
+/// `MAX_METADATA_PAIRS` pairs, `MAX_METADATA_KEY_LENGTH`
+/// characters per key and `MAX_METADATA_VALUE_LENGTH`
+/// characters per value.
+///
+/// Returns an `Error::Validation` naming the limit that was
+/// exceeded, since the API's own error doesn't.
+pub fn check_metadata(metadata: &HashMap<String, String>) -> Result<()> {
+    if metadata.len() > MAX_METADATA_PAIRS {
+        return Err(Error::Validation(format!(
+            "metadata can have at most {} pairs, got {}",
+            MAX_METADATA_PAIRS,
+            metadata.len()
+        )));
+    }
+    for (key, value) in metadata {
+        let len = key.chars().count();
+        if len > MAX_METADATA_KEY_LENGTH {
+            return Err(Error::Validation(format!(
+                "metadata keys can be at most {} characters, got {} for {:?}",
+                MAX_METADATA_KEY_LENGTH, len, key
+            )));
+        }
+        let len = value.chars().count();
+        if len > MAX_METADATA_VALUE_LENGTH {
+            return Err(Error::Validation(format!(
+                "metadata values can be at most {} characters, got {} for {:?}",
+                MAX_METADATA_VALUE_LENGTH, len, key
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A request that can attach `metadata` to the object it
+/// creates or modifies (e.g. a thread or run).
+pub trait WithMetadata: Sized {
+    /// The request's `metadata` field.
+    fn metadata_mut(&mut self) -> &mut Option<HashMap<String, String>>;
+
+    /// Sets the request's metadata, after checking it's
+    /// within the API's limits (see `check_metadata`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use ohairs::dtypes::{CreateThreadRequest, WithMetadata};
+    ///
+    /// let metadata = HashMap::from([("user".to_string(), "u-123".to_string())]);
+    /// let req = CreateThreadRequest::default().with_metadata(metadata).unwrap();
+    /// assert_eq!(req.metadata.unwrap()["user"], "u-123");
+    ///
+    /// let too_many = (0..17).map(|i| (i.to_string(), String::new())).collect();
+    /// assert!(CreateThreadRequest::default().with_metadata(too_many).is_err());
+    /// ```
+    fn with_metadata(mut self, metadata: HashMap<String, String>) -> Result<Self> {
+        check_metadata(&metadata)?;
+        *self.metadata_mut() = Some(metadata);
+        Ok(self)
+    }
+}
+
+/// A response type tagged with a fixed `object` field
+/// (e.g. `"chat.completion"`).
+///
+/// Checking the tag catches responses that were routed to
+/// the wrong endpoint (e.g. by a misconfigured proxy) but
+/// happened to deserialize anyway.
+pub trait ApiObject {
+    /// The value the `object` field is expected to have.
+    const OBJECT: &'static str;
+
+    /// The value of the `object` field.
+    fn object(&self) -> &str;
+
+    /// Returns an `Error::UnexpectedObject` if the `object`
+    /// field isn't `OBJECT`.
+    fn check_object(&self) -> Result<()> {
+        match self.object() {
+            object if object == Self::OBJECT => Ok(()),
+            object => Err(Error::UnexpectedObject {
+                expected: Self::OBJECT,
+                found: object.to_string(),
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 pub struct ModelObject {
     /// The model identifier, which can be referenced
@@ -119,12 +579,29 @@ pub struct ModelObject {
     /// The object type, which is always "model".
     pub object: String,
     /// The date and time when the model was created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
     pub created: u64,
 
     /// The organization that owns the model.
     pub owned_by: String,
 }
 
+impl ApiObject for ModelObject {
+    const OBJECT: &'static str = "model";
+
+    fn object(&self) -> &str {
+        &self.object
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ModelObject {
+    /// The time the model was created, as a UTC datetime.
+    pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
+        timestamp_to_datetime(self.created)
+    }
+}
+
 /// Represents a chat completion response returned
 /// by model, based on the provided input.
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
@@ -137,6 +614,7 @@ pub struct ChatCompletionObject {
 
     /// A unix timestamp of when the chat completion
     /// was created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
     pub created: u64,
 
     /// The model used for the chat completion.
@@ -148,6 +626,108 @@ pub struct ChatCompletionObject {
 
     /// Usage statistics for the completion request.
     pub usage: ChatCompletionUsage,
+
+    /// A fingerprint of the backend configuration the model
+    /// ran with.
+    ///
+    /// Used alongside `seed` to detect backend changes that
+    /// might affect determinism.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+}
+
+impl ApiObject for ChatCompletionObject {
+    const OBJECT: &'static str = "chat.completion";
+
+    fn object(&self) -> &str {
+        &self.object
+    }
+}
+
+impl ChatCompletionObject {
+    /// Returns the message of the first choice, if there
+    /// is one.
+    pub fn first_message(&self) -> Option<&ChatCompletionMessage> {
+        self.choices.first().map(|choice| &choice.message)
+    }
+
+    /// Returns the text content of the first choice's
+    /// message, if there is one.
+    ///
+    /// Returns `None` if the message has no content (e.g.
+    /// it's a tool call) or its content isn't plain text.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::Client;
+    ///
+    /// # async fn run() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// let res = client.chat("gpt-4o").user("Hi!").send().await?;
+    /// println!("{}", res.first_content().unwrap_or_default());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn first_content(&self) -> Option<&str> {
+        self.first_message()?.content.as_ref()?.as_text()
+    }
+
+    /// Returns an iterator over every choice's message (e.g.
+    /// when the request's `n` is greater than `1`).
+    pub fn messages(&self) -> impl Iterator<Item = &ChatCompletionMessage> {
+        self.choices.iter().map(|choice| &choice.message)
+    }
+
+    /// Returns an iterator over every choice's text content,
+    /// skipping choices without any (e.g. function calls).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::Client;
+    ///
+    /// # async fn run() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// let res = client.chat("gpt-4o").user("Name a color.").n(3).send().await?;
+    /// for content in res.contents() {
+    ///     println!("{}", content);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn contents(&self) -> impl Iterator<Item = &str> {
+        self.messages()
+            .filter_map(|msg| msg.content.as_ref()?.as_text())
+    }
+
+    /// Was any choice cut short by the content filter (i.e.
+    /// its `finish_reason` is `content_filter`)?
+    pub fn was_filtered(&self) -> bool {
+        self.choices
+            .iter()
+            .any(|choice| choice.finish_reason == FinishReason::ContentFilter)
+    }
+
+    /// Was any choice cut short by the token limit (i.e. its
+    /// `finish_reason` is `length`)?
+    ///
+    /// If so, the generation can be continued with
+    /// `Client::continue_completion`.
+    pub fn is_truncated(&self) -> bool {
+        self.choices
+            .iter()
+            .any(|choice| choice.finish_reason == FinishReason::Length)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ChatCompletionObject {
+    /// The time the chat completion was created, as a UTC
+    /// datetime.
+    pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
+        timestamp_to_datetime(self.created)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
@@ -159,37 +739,293 @@ pub struct ChatCompletionChoice {
     pub message: ChatCompletionMessage,
 
     /// The reason the model stopped generating tokens.
-    ///
-    /// This will be `stop` if the model hit a natural stop
-    /// point or a provided stop sequence, `length` if the
-    /// maximum number of tokens specified in the request
-    /// was reached, or `function_call` if the model called
-    /// a function.
-    pub finish_reason: String,
+    pub finish_reason: FinishReason,
+
+    /// The log probabilities of the output tokens, if they
+    /// were requested with `logprobs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<LogProbs>,
+}
+
+/// The log probabilities of a choice's output tokens.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct LogProbs {
+    /// The log probability of each content token.
+    pub content: Option<Vec<LogProbContent>>,
+}
+
+/// A token and its log probability.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct LogProbContent {
+    /// The token.
+    pub token: String,
+
+    /// The log probability of the token.
+    pub logprob: f64,
+
+    /// The UTF-8 bytes of the token, useful when a character
+    /// is split across several tokens. `None` if the token
+    /// has no bytes representation.
+    pub bytes: Option<Vec<u8>>,
+
+    /// The most likely tokens at this position, if
+    /// `top_logprobs` was requested. Empty for the entries
+    /// of this list.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top_logprobs: Vec<LogProbContent>,
+}
+
+/// The reason the model stopped generating tokens.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FinishReason {
+    /// The model hit a natural stop point or a provided
+    /// stop sequence.
+    #[default]
+    #[serde(rename = "stop")]
+    Stop,
+
+    /// The maximum number of tokens specified in the
+    /// request was reached.
+    #[serde(rename = "length")]
+    Length,
+
+    /// The model called a function.
+    #[serde(rename = "function_call")]
+    FunctionCall,
+
+    /// The model called one or more tools.
+    #[serde(rename = "tool_calls")]
+    ToolCalls,
+
+    /// Content was omitted by the content filter.
+    #[serde(rename = "content_filter")]
+    ContentFilter,
+
+    /// A reason this version of the crate doesn't know
+    /// about, so that responses using newer reasons still
+    /// parse.
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 pub struct ChatCompletionMessage {
     /// The role of the author of this message.
-    pub role: String,
+    pub role: Role,
 
     /// The contents of the message.
     ///
     /// content is required for all messages, and may
     /// be null for assistant messages with function calls.
-    pub content: Option<String>,
+    /// User messages may also be a list of parts, to mix
+    /// text and images.
+    pub content: Option<MessageContent>,
+
+    /// The name of the author of this message.
+    ///
+    /// `name` is required if role is `function`, and
+    /// it should be the name of the function whose
+    /// response is in the `content`. May contain a-z,
+    /// A-Z, 0-9, and underscores, with a maximum length
+    /// of 64 characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 
-    // /// The name of the author of this message.
-    // ///
-    // /// `name` is required if role is `function`, and
-    // /// it should be the name of the function whose
-    // /// response is in the `content`. May contain a-z,
-    // /// A-Z, 0-9, and underscores, with a maximum length
-    // /// of 64 characters.
-    // pub name: Option<String>,
     /// The name and arguments of a function that should
     // be called, as generated by the model.
     pub function_call: Option<FunctionCall>,
+
+    /// The tool calls generated by the model.
+    ///
+    /// Supersedes `function_call`, and may contain several
+    /// calls to be run in parallel.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+
+    /// The ID of the tool call this message is responding to.
+    ///
+    /// Required if role is `tool`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatCompletionMessage {
+    /// Creates a `tool` message carrying the output of
+    /// the tool call with the given ID.
+    ///
+    /// The `tool_call_id` must match the `id` of the tool
+    /// call the model made, or the API will reject the
+    /// request.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::dtypes::{ChatCompletionMessage, Role};
+    ///
+    /// let msg = ChatCompletionMessage::tool_result("call_abc123", "{\"temp\": 72}");
+    /// assert_eq!(msg.role, Role::Tool);
+    /// assert_eq!(msg.tool_call_id.as_deref(), Some("call_abc123"));
+    /// ```
+    pub fn tool_result(tool_call_id: &str, content: &str) -> Self {
+        Self {
+            role: Role::Tool,
+            content: Some(content.into()),
+            tool_call_id: Some(tool_call_id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a `function` message carrying the output of
+    /// the function with the given name.
+    ///
+    /// This is for the legacy `functions` API. Prefer
+    /// `tool_result` when using tools.
+    pub fn function_result(name: &str, content: &str) -> Self {
+        Self {
+            role: Role::Function,
+            content: Some(content.into()),
+            name: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// The contents of a message.
+///
+/// Serializes to either a plain string or an array of
+/// content parts.
+///
+/// # Example
+///
+/// ```
+/// use ohairs::dtypes::{ContentPart, MessageContent};
+///
+/// let content = MessageContent::Parts(vec![
+///     ContentPart::text("What's in this image?"),
+///     ContentPart::image_url("https://example.com/cat.png"),
+/// ]);
+/// let json = serde_json::to_value(&content).unwrap();
+/// assert_eq!(json[1]["type"], "image_url");
+/// assert_eq!(json[1]["image_url"]["url"], "https://example.com/cat.png");
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl Default for MessageContent {
+    fn default() -> Self {
+        MessageContent::Text(String::new())
+    }
+}
+
+impl MessageContent {
+    /// Returns the text, if the content is a plain string.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            MessageContent::Text(text) => Some(text),
+            MessageContent::Parts(_) => None,
+        }
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        MessageContent::Text(text)
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        MessageContent::Text(text.to_string())
+    }
+}
+
+/// A single part of a message's contents.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum ContentPart {
+    #[serde(rename = "text")]
+    Text { text: String },
+
+    #[serde(rename = "image_url")]
+    ImageUrl { image_url: ImageUrl },
+}
+
+impl ContentPart {
+    /// Creates a text part.
+    pub fn text(text: &str) -> Self {
+        ContentPart::Text {
+            text: text.to_string(),
+        }
+    }
+
+    /// Creates an image part from a URL (or a base64
+    /// encoded `data:` URL), using the default detail.
+    pub fn image_url(url: &str) -> Self {
+        ContentPart::ImageUrl {
+            image_url: ImageUrl {
+                url: url.to_string(),
+                detail: None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ImageUrl {
+    /// The URL of the image, or the base64 encoded image
+    /// data as a `data:` URL.
+    pub url: String,
+
+    /// The detail level the model should process the
+    /// image at.
+    ///
+    /// Defaults to `auto`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<ImageDetail>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub enum ImageDetail {
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+
+    #[serde(rename = "low")]
+    Low,
+
+    #[serde(rename = "high")]
+    High,
+}
+
+/// The role of a message's author.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    #[serde(rename = "system")]
+    System,
+
+    #[default]
+    #[serde(rename = "user")]
+    User,
+
+    #[serde(rename = "assistant")]
+    Assistant,
+
+    /// The result of a (legacy) function call.
+    #[serde(rename = "function")]
+    Function,
+
+    /// The result of a tool call.
+    #[serde(rename = "tool")]
+    Tool,
+
+    /// A role this version of the crate doesn't know about,
+    /// so that responses using newer roles still parse.
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
@@ -218,18 +1054,146 @@ pub struct ChatCompletionUsage {
     /// Total number of tokens used in the request
     /// (prompt + completion).
     pub total_tokens: u64,
+
+    /// A breakdown of the prompt tokens, if the API sent one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
+
+    /// A breakdown of the completion tokens, if the API sent
+    /// one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_tokens_details: Option<CompletionTokensDetails>,
 }
 
-/// Represents a streamed chunk of a chat completion response
-/// returned by model, based on the provided input.
-#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
-pub struct ChatCompletionChunk {
-    /// A unique identifier for the chat completion chunk.
-    pub id: String,
-    pub object: String,
-    pub created: u64,
-    pub model: String,
-    pub choices: Vec<ChatCompletionChunkChoice>,
+/// A breakdown of a request's prompt tokens.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PromptTokensDetails {
+    /// Prompt tokens that were read from the prompt cache
+    /// (and billed at a discount).
+    #[serde(default)]
+    pub cached_tokens: u64,
+
+    /// Prompt tokens from audio input.
+    #[serde(default)]
+    pub audio_tokens: u64,
+}
+
+impl Add for PromptTokensDetails {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            cached_tokens: self.cached_tokens + rhs.cached_tokens,
+            audio_tokens: self.audio_tokens + rhs.audio_tokens,
+        }
+    }
+}
+
+/// A breakdown of a request's completion tokens.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompletionTokensDetails {
+    /// Tokens the model used for reasoning, which are
+    /// billed as completion tokens but not returned.
+    #[serde(default)]
+    pub reasoning_tokens: u64,
+
+    /// Completion tokens from audio output.
+    #[serde(default)]
+    pub audio_tokens: u64,
+}
+
+impl Add for CompletionTokensDetails {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            reasoning_tokens: self.reasoning_tokens + rhs.reasoning_tokens,
+            audio_tokens: self.audio_tokens + rhs.audio_tokens,
+        }
+    }
+}
+
+/// Adds two optional breakdowns, keeping whichever is set
+/// if only one is.
+fn add_details<T: Add<Output = T>>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
+
+impl Add for ChatCompletionUsage {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self += rhs;
+        self
+    }
+}
+
+impl AddAssign for ChatCompletionUsage {
+    fn add_assign(&mut self, rhs: Self) {
+        self.prompt_tokens += rhs.prompt_tokens;
+        self.completion_tokens += rhs.completion_tokens;
+        self.total_tokens += rhs.total_tokens;
+        self.prompt_tokens_details =
+            add_details(self.prompt_tokens_details, rhs.prompt_tokens_details);
+        self.completion_tokens_details = add_details(
+            self.completion_tokens_details,
+            rhs.completion_tokens_details,
+        );
+    }
+}
+
+impl Sum for ChatCompletionUsage {
+    /// Adds up the usage from multiple requests.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::dtypes::ChatCompletionUsage;
+    ///
+    /// let usage = |prompt_tokens, completion_tokens| ChatCompletionUsage {
+    ///     prompt_tokens,
+    ///     completion_tokens,
+    ///     total_tokens: prompt_tokens + completion_tokens,
+    ///     ..Default::default()
+    /// };
+    /// let usages = vec![usage(10, 5), usage(3, 2)];
+    /// let total: ChatCompletionUsage = usages.into_iter().sum();
+    /// assert_eq!(total.total_tokens, 20);
+    /// ```
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+/// Represents a streamed chunk of a chat completion response
+/// returned by model, based on the provided input.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ChatCompletionChunk {
+    /// A unique identifier for the chat completion chunk.
+    pub id: String,
+    pub object: String,
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created: u64,
+    pub model: String,
+    pub choices: Vec<ChatCompletionChunkChoice>,
+
+    /// Usage statistics for the whole request, sent in a
+    /// final chunk (with no choices) when
+    /// `stream_options.include_usage` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ChatCompletionUsage>,
+}
+
+#[cfg(feature = "chrono")]
+impl ChatCompletionChunk {
+    /// The time the chat completion was created, as a UTC
+    /// datetime.
+    pub fn created_at(&self) -> chrono::DateTime<chrono::Utc> {
+        timestamp_to_datetime(self.created)
+    }
 }
 
 impl ChatCompletionChunk {
@@ -247,6 +1211,12 @@ impl ChatCompletionChunk {
     /// and `Ok(Some(ChatCompletionChunk))` if the chunk was
     /// a valid JSON object.
     ///
+    /// This is a wrapper around `parse_event`, kept for
+    /// compatibility. Keep-alive comments and blank lines are
+    /// returned as errors, since `Ok(None)` already means
+    /// `[DONE]`; use `parse_event` (and its `ChunkEvent`) to
+    /// tell the two apart and skip them instead.
+    ///
     /// # Examples
     ///
     /// Checking that `data: {...}` is parsed correctly:
@@ -261,7 +1231,7 @@ impl ChatCompletionChunk {
     ///   \"choices\": []
     /// }";
     /// let data = ChatCompletionChunk::from_chunk(chunk);
-    /// assert!(matches!(data, Ok(Some(ChatCompletionChunk(_)))));
+    /// assert!(matches!(data, Ok(Some(_))));
     /// ```
     ///
     /// Checking that `data: [DONE]` is parsed correctly:
@@ -276,7 +1246,7 @@ impl ChatCompletionChunk {
     /// Checking that just JSON is parsed as an error:
     ///
     /// ```
-    /// se ohairs::dtypes::ChatCompletionChunk;
+    /// use ohairs::dtypes::ChatCompletionChunk;
     /// let chunk = "{
     ///   \"id\": \"chatcmpl-123\",
     ///   \"object\": \"chat.completion.chunk\",
@@ -288,36 +1258,170 @@ impl ChatCompletionChunk {
     /// assert!(data.is_err());
     /// ```
     pub fn from_chunk(chunk: &str) -> Result<Option<Self>> {
-        // Strip any leading or trailing whitespace...
-        let chunk = chunk.trim();
-
-        // Does it start with `data:`?
-        if !chunk.starts_with("data:") {
-            return Err(anyhow!("Expected chunk to start with 'data:'"));
+        match Self::parse_event(chunk)? {
+            ChunkEvent::Chunk(chunk) => Ok(Some(chunk)),
+            ChunkEvent::Done => Ok(None),
+            ChunkEvent::KeepAlive => Err(Error::InvalidChunk(
+                "Expected chunk to start with 'data:'".to_string(),
+            )),
         }
+    }
+
+    /// Parses a single event streamed from the API, like
+    /// `from_chunk`, but treats comment lines (starting with
+    /// `:`) and blank lines as `ChunkEvent::KeepAlive` rather
+    /// than as errors.
+    ///
+    /// The API may send these as heartbeats during long
+    /// streams, and they should just be skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::dtypes::{ChatCompletionChunk, ChunkEvent};
+    ///
+    /// let event = ChatCompletionChunk::parse_event(": keep-alive").unwrap();
+    /// assert_eq!(event, ChunkEvent::KeepAlive);
+    ///
+    /// let event = ChatCompletionChunk::parse_event("data: [DONE]").unwrap();
+    /// assert_eq!(event, ChunkEvent::Done);
+    /// ```
+    pub fn parse_event(chunk: &str) -> Result<ChunkEvent> {
+        parse_sse_event(chunk)
+    }
 
-        // Strip the `data:` prefix...
-        let chunk = chunk
-            .strip_prefix("data:")
-            .ok_or(anyhow!("Expected chunk to have 'data:' prefix"))?
-            .trim();
+    /// Returns the delta of the first choice, if there is
+    /// one.
+    pub fn first_delta(&self) -> Option<&ChatCompletionDelta> {
+        self.choices.first().map(|choice| &choice.delta)
+    }
+}
 
-        // Is it `[DONE]`?
-        if chunk == "[DONE]" {
-            return Ok(None);
-        }
+/// A single event parsed from a streamed completion.
+///
+/// Chat completions stream `ChatCompletionChunk`s (the
+/// default) and legacy completions stream `CompletionChunk`s.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChunkEvent<T = ChatCompletionChunk> {
+    /// The next chunk of the completion.
+    Chunk(T),
 
-        // Parse the chunk as JSON...
-        let chunk: Self = serde_json::from_str(chunk)?;
-        Ok(Some(chunk))
+    /// The stream is finished (`data: [DONE]`).
+    Done,
+
+    /// A comment or blank line with no data, which can be
+    /// ignored.
+    KeepAlive,
+}
+
+/// Parses a single server-sent event from a completion
+/// stream. See `ChatCompletionChunk::parse_event`.
+pub(crate) fn parse_sse_event<T: DeserializeOwned>(chunk: &str) -> Result<ChunkEvent<T>> {
+    // Strip any leading or trailing whitespace...
+    let chunk = chunk.trim();
+
+    // Is it a comment or a blank line?
+    if chunk.is_empty() || chunk.starts_with(':') {
+        return Ok(ChunkEvent::KeepAlive);
+    }
+
+    // Does it start with `data:`?
+    if !chunk.starts_with("data:") {
+        return Err(Error::InvalidChunk(
+            "Expected chunk to start with 'data:'".to_string(),
+        ));
+    }
+
+    // Strip the `data:` prefix...
+    let chunk = chunk
+        .strip_prefix("data:")
+        .ok_or(Error::InvalidChunk(
+            "Expected chunk to have 'data:' prefix".to_string(),
+        ))?
+        .trim();
+
+    // Is it `[DONE]`?
+    if chunk == "[DONE]" {
+        return Ok(ChunkEvent::Done);
     }
+
+    // Parse the chunk as JSON...
+    let chunk: T = serde_json::from_str(chunk)?;
+    Ok(ChunkEvent::Chunk(chunk))
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ChatCompletionChunkChoice {
     pub index: u64,
-    pub delta: ChatCompletionMessage,
-    pub finish_reason: Option<String>,
+    pub delta: ChatCompletionDelta,
+    pub finish_reason: Option<FinishReason>,
+}
+
+/// The partial message carried by a streamed chunk.
+///
+/// Every field is optional, since each chunk only contains
+/// the part of the message that changed. Typically `role`
+/// is only present in the first chunk, and `content` (or
+/// the call arguments) arrive in fragments afterwards.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ChatCompletionDelta {
+    /// The role of the author of this message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<Role>,
+
+    /// The next fragment of the message contents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    /// The next fragment of a function call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_call: Option<FunctionCallDelta>,
+
+    /// The next fragments of any tool calls.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+
+    /// The next fragment of a refusal message, if the
+    /// model refused the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refusal: Option<String>,
+}
+
+/// A fragment of a streamed function call.
+///
+/// Unlike `FunctionCall`, the `name` is optional since
+/// it's only sent in the first fragment.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct FunctionCallDelta {
+    /// The name of the function to call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The next fragment of the JSON arguments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+}
+
+/// A fragment of a streamed tool call.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ToolCallDelta {
+    /// The position of the tool call this fragment
+    /// belongs to, used to stitch fragments together.
+    pub index: u64,
+
+    /// The ID of the tool call. Only sent in the first
+    /// fragment.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// The type of the tool. Only sent in the first
+    /// fragment.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+
+    /// The next fragment of the function call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<FunctionCallDelta>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
@@ -342,6 +1446,169 @@ pub enum FunctionCallType {
     Name(String),
 }
 
+/// A tool the model may call.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Tool {
+    /// The type of the tool.
+    #[serde(rename = "type")]
+    pub type_: ToolType,
+
+    /// The function the model may call.
+    pub function: Function,
+}
+
+impl Tool {
+    /// Creates a tool wrapping the given function.
+    pub fn function(function: Function) -> Self {
+        Self {
+            type_: ToolType::Function,
+            function,
+        }
+    }
+}
+
+/// The type of a tool. Currently, only functions are
+/// supported.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub enum ToolType {
+    #[default]
+    #[serde(rename = "function")]
+    Function,
+}
+
+/// Controls which (if any) tool is called by the model.
+///
+/// Serializes to either `"none"`, `"auto"`, `"required"`, or
+/// `{"type": "function", "function": {"name": "..."}}`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ToolChoice {
+    /// Let the model decide (or forbid, or require) calling
+    /// tools.
+    Mode(ToolChoiceMode),
+
+    /// Force the model to call a specific tool.
+    Named(NamedToolChoice),
+}
+
+impl Default for ToolChoice {
+    fn default() -> Self {
+        ToolChoice::Mode(ToolChoiceMode::default())
+    }
+}
+
+impl ToolChoice {
+    /// Forces the model to call the function with the
+    /// given name.
+    pub fn function(name: &str) -> Self {
+        ToolChoice::Named(NamedToolChoice {
+            type_: ToolType::Function,
+            function: NamedFunction {
+                name: name.to_string(),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub enum ToolChoiceMode {
+    /// The model won't call any tools.
+    #[serde(rename = "none")]
+    None,
+
+    /// The model can pick between responding and calling
+    /// one or more tools.
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+
+    /// The model must call one or more tools.
+    #[serde(rename = "required")]
+    Required,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct NamedToolChoice {
+    /// The type of the tool.
+    #[serde(rename = "type")]
+    pub type_: ToolType,
+
+    /// The function the model must call.
+    pub function: NamedFunction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct NamedFunction {
+    /// The name of the function to call.
+    pub name: String,
+}
+
+/// A tool call generated by the model.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ToolCall {
+    /// The ID of the tool call, to be passed back as the
+    /// `tool_call_id` of the message with its result.
+    pub id: String,
+
+    /// The type of the tool.
+    #[serde(rename = "type")]
+    pub type_: ToolType,
+
+    /// The name and arguments of the function to call.
+    pub function: FunctionCall,
+}
+
+/// Options for a streamed chat completion.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct StreamOptions {
+    /// If set, an extra chunk is streamed before
+    /// `data: [DONE]`, carrying the usage statistics for the
+    /// whole request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_usage: Option<bool>,
+}
+
+/// The format the model must output.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum ResponseFormat {
+    /// Plain text.
+    #[default]
+    #[serde(rename = "text")]
+    Text,
+
+    /// JSON mode, which guarantees the message is valid
+    /// JSON. The prompt should still ask for JSON.
+    #[serde(rename = "json_object")]
+    JsonObject,
+
+    /// Structured outputs, which guarantee the message
+    /// matches the given JSON schema.
+    #[serde(rename = "json_schema")]
+    JsonSchema { json_schema: JsonSchema },
+}
+
+/// A JSON schema for structured outputs.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct JsonSchema {
+    /// The name of the response format. May contain a-z,
+    /// A-Z, 0-9, underscores and dashes, with a maximum
+    /// length of 64 characters.
+    pub name: String,
+
+    /// A description of what the response format is for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The schema, as a JSON schema object.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>,
+
+    /// Whether to enable strict schema adherence.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum StopToken {
@@ -349,99 +1616,2053 @@ pub enum StopToken {
     MultipleTokens(Vec<String>),
 }
 
+/// The most stop sequences the API accepts.
+pub const MAX_STOP_SEQUENCES: usize = 4;
+
+/// The longest `user` identifier `validate` accepts. The API
+/// doesn't document a limit, but the ID is meant to be short
+/// (e.g. a hashed username or email).
+pub const MAX_USER_LENGTH: usize = 256;
+
+impl StopToken {
+    /// Creates a `StopToken` from a list of sequences,
+    /// checking the API's limit of `MAX_STOP_SEQUENCES`.
+    ///
+    /// A single sequence is sent as a plain string. Returns
+    /// an `Error::Validation` if `tokens` is empty or has
+    /// too many sequences.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::dtypes::StopToken;
+    ///
+    /// let stop = StopToken::new(vec!["\n".to_string()]).unwrap();
+    /// assert_eq!(stop, StopToken::SingleToken("\n".to_string()));
+    ///
+    /// assert!(StopToken::new(vec!["x".to_string(); 5]).is_err());
+    /// ```
+    pub fn new(mut tokens: Vec<String>) -> Result<Self> {
+        match tokens.len() {
+            0 => Err(Error::Validation(
+                "stop needs at least 1 sequence".to_string(),
+            )),
+            1 => Ok(StopToken::SingleToken(tokens.remove(0))),
+            n if n > MAX_STOP_SEQUENCES => Err(Error::Validation(format!(
+                "stop can have at most {} sequences, got {}",
+                MAX_STOP_SEQUENCES, n
+            ))),
+            _ => Ok(StopToken::MultipleTokens(tokens)),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ListModelsResponse {
     pub object: String,
     pub data: Vec<ModelObject>,
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+impl ApiObject for ListModelsResponse {
+    const OBJECT: &'static str = "list";
 
-    #[test]
-    fn chat_completion_chunk_from_chunk() {
-        // Define cases to test...
-        enum Res {
-            IsErr,
-            IsDone,
-            IsChunk(ChatCompletionChunk),
-        }
-        let cases = vec![
-            ("", Res::IsErr),
-            ("{{", Res::IsErr),
-            ("d a t a :", Res::IsErr),
-            ("data: [DONE]", Res::IsDone),
-            ("data: [DONE]\n\n", Res::IsDone),
-            (
-                "data: {
-                    \"id\": \"\",
-                    \"object\": \"\",
-                    \"created\": 0,
-                    \"model\": \"\",
-                    \"choices\": []
-                }",
-                Res::IsChunk(ChatCompletionChunk {
-                    ..Default::default()
-                }),
-            ),
-            (
-                "data: {
-                    \"id\": \"chatcmpl-123\",
-                    \"object\": \"chat.completion.chunk\",
-                    \"created\": 1677652288,
-                    \"model\": \"gpt-3.5-turbo\",
-                    \"choices\": []
-                }",
-                Res::IsChunk(ChatCompletionChunk {
-                    id: "chatcmpl-123".to_string(),
-                    object: "chat.completion.chunk".to_string(),
-                    created: 1677652288,
-                    model: "gpt-3.5-turbo".to_string(),
-                    ..Default::default()
-                }),
-            ),
-            (
-                "data: {
-                    \"id\": \"chatcmpl-123\",
-                    \"object\": \"chat.completion.chunk\",
-                    \"created\": 1677652288,
-                    \"model\": \"gpt-3.5-turbo\",
-                    \"choices\": [
-                        {
-                            \"index\": 0,
-                            \"finish_reason\": \"stop\",
-                            \"delta\": {
-                                \"role\": \"system\",
-                                \"content\": \"You are a helpful assistant.\"
-                            }
-                        }
-                    ]
-                }",
-                Res::IsChunk(ChatCompletionChunk {
-                    id: "chatcmpl-123".to_string(),
-                    object: "chat.completion.chunk".to_string(),
-                    created: 1677652288,
-                    model: "gpt-3.5-turbo".to_string(),
-                    choices: vec![ChatCompletionChunkChoice {
-                        index: 0,
-                        finish_reason: Some("stop".to_string()),
-                        delta: ChatCompletionMessage {
-                            role: "system".to_string(),
-                            content: Some("You are a helpful assistant.".to_string()),
-                            ..Default::default()
-                        },
-                    }],
-                }),
-            ),
-            (
-                "data: {
-                    \"id\": \"chatcmpl-123\",
-                    \"object\": \"chat.completion.chunk\",
-                    \"created\": 1677652288,
-                    \"model\": \"gpt-3.5-turbo\",
-                    \"choices\": [
-                        {
+    fn object(&self) -> &str {
+        &self.object
+    }
+}
+
+/// An error returned by the API.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ApiError {
+    /// A human-readable description of the error.
+    pub message: String,
+
+    /// The type of error (e.g. `invalid_request_error`).
+    #[serde(rename = "type")]
+    pub type_: Option<String>,
+
+    /// The request parameter that caused the error, if any.
+    pub param: Option<String>,
+
+    /// A machine-readable error code (e.g. `insufficient_quota`).
+    pub code: Option<String>,
+}
+
+/// The envelope the API wraps errors in, i.e.
+/// `{"error": {...}}`.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ApiErrorEnvelope {
+    pub error: ApiError,
+}
+
+/// A request to the legacy completions endpoint.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CompletionRequest {
+    /// ID of the model to use.
+    pub model: String,
+
+    /// The prompt(s) to generate completions for.
+    pub prompt: CompletionPrompt,
+
+    /// The suffix that comes after a completion of
+    /// inserted text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+
+    /// The maximum number of tokens to generate in the completion.
+    ///
+    /// Defaults to `16`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+
+    /// What sampling temperature to use, between 0 and 2.
+    ///
+    /// Defaults to `1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+
+    /// The nucleus sampling probability mass.
+    ///
+    /// Defaults to `1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+
+    /// How many completions to generate for each prompt.
+    ///
+    /// Defaults to `1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<u64>,
+
+    /// Include the log probabilities of this many of the
+    /// most likely tokens at each position (max 5).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<u8>,
+
+    /// Echo back the prompt in addition to the completion.
+    ///
+    /// Defaults to `false`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub echo: Option<bool>,
+
+    /// Up to 4 sequences where the API will stop generating further tokens.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<StopToken>,
+
+    /// If set, the completion is streamed back as
+    /// `CompletionChunk`s, terminated by `data: [DONE]`.
+    ///
+    /// Set by `create_completion_stream`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+
+    /// Options for streamed responses. Only set this when
+    /// `stream` is `true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+
+    /// Number between -2.0 and 2.0, penalizing tokens that
+    /// have already appeared.
+    ///
+    /// Defaults to `0`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f64>,
+
+    /// Number between -2.0 and 2.0, penalizing tokens based
+    /// on how often they've appeared.
+    ///
+    /// Defaults to `0`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f64>,
+
+    /// Generates this many completions server-side and
+    /// returns the best one. Must be greater than `n`.
+    ///
+    /// Defaults to `1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub best_of: Option<u64>,
+
+    /// Modify the likelihood of specified tokens appearing in the completion.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logit_bias: Option<HashMap<String, f64>>,
+
+    /// The seed for best-effort deterministic sampling.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+
+    /// A unique identifier representing your end-user, which can help
+    /// OpenAI to monitor and detect abuse.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+/// The prompt(s) for a completion request.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum CompletionPrompt {
+    /// A single prompt.
+    String(String),
+
+    /// Several prompts, each completed separately.
+    StringArray(Vec<String>),
+}
+
+impl Default for CompletionPrompt {
+    fn default() -> Self {
+        CompletionPrompt::String(String::new())
+    }
+}
+
+/// The response from the legacy completions endpoint.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CompletionResponse {
+    /// A unique identifier for the completion.
+    pub id: String,
+
+    /// The object type, which is always `text_completion`.
+    pub object: String,
+
+    /// A unix timestamp of when the completion was created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created: u64,
+
+    /// The model used for the completion.
+    pub model: String,
+
+    /// The list of completion choices.
+    pub choices: Vec<CompletionChoice>,
+
+    /// Usage statistics for the completion request.
+    pub usage: ChatCompletionUsage,
+
+    /// A fingerprint of the backend configuration the model
+    /// ran with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CompletionChoice {
+    /// The generated text.
+    pub text: String,
+
+    /// The index of the choice in the list of choices.
+    pub index: u64,
+
+    /// The log probabilities of the generated tokens, if
+    /// they were requested.
+    pub logprobs: Option<CompletionLogProbs>,
+
+    /// The reason the model stopped generating tokens.
+    pub finish_reason: FinishReason,
+}
+
+/// A streamed chunk of a legacy completion.
+///
+/// Unlike a `ChatCompletionChunk`, each choice carries the
+/// next fragment of its `text` directly rather than a delta.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CompletionChunk {
+    /// A unique identifier for the completion.
+    pub id: String,
+
+    /// The object type, which is always `text_completion`.
+    pub object: String,
+
+    /// A unix timestamp of when the completion was created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created: u64,
+
+    /// The model used for the completion.
+    pub model: String,
+
+    /// The choices that changed in this chunk.
+    pub choices: Vec<CompletionChunkChoice>,
+
+    /// Usage statistics for the whole request, sent in a
+    /// final chunk (with no choices) when
+    /// `stream_options.include_usage` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ChatCompletionUsage>,
+
+    /// A fingerprint of the backend configuration the model
+    /// ran with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+}
+
+impl CompletionChunk {
+    /// Tries to create a new `CompletionChunk` from a string
+    /// streamed from the API.
+    ///
+    /// Returns `Ok(None)` for `data: [DONE]`, like
+    /// `ChatCompletionChunk::from_chunk`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::dtypes::CompletionChunk;
+    /// let chunk = "data: {
+    ///   \"id\": \"cmpl-123\",
+    ///   \"object\": \"text_completion\",
+    ///   \"created\": 1677652288,
+    ///   \"model\": \"gpt-3.5-turbo-instruct\",
+    ///   \"choices\": [{\"text\": \"Hello\", \"index\": 0, \"logprobs\": null, \"finish_reason\": null}]
+    /// }";
+    /// let data = CompletionChunk::from_chunk(chunk).unwrap().unwrap();
+    /// assert_eq!(data.text(), Some("Hello"));
+    /// ```
+    pub fn from_chunk(chunk: &str) -> Result<Option<Self>> {
+        match Self::parse_event(chunk)? {
+            ChunkEvent::Chunk(chunk) => Ok(Some(chunk)),
+            ChunkEvent::Done => Ok(None),
+            ChunkEvent::KeepAlive => Err(Error::InvalidChunk(
+                "Expected chunk to start with 'data:'".to_string(),
+            )),
+        }
+    }
+
+    /// Parses a single event streamed from the API, treating
+    /// comments and blank lines as `ChunkEvent::KeepAlive`.
+    pub fn parse_event(chunk: &str) -> Result<ChunkEvent<Self>> {
+        parse_sse_event(chunk)
+    }
+
+    /// Returns the text fragment of the first choice, if
+    /// there is one.
+    pub fn text(&self) -> Option<&str> {
+        self.choices.first().map(|choice| choice.text.as_str())
+    }
+}
+
+/// A choice in a streamed legacy completion chunk.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CompletionChunkChoice {
+    /// The next fragment of the generated text.
+    #[serde(default)]
+    pub text: String,
+
+    /// The index of the choice in the list of choices.
+    pub index: u64,
+
+    /// The log probabilities of this fragment's tokens, if
+    /// they were requested.
+    #[serde(default)]
+    pub logprobs: Option<CompletionLogProbs>,
+
+    /// The reason the model stopped generating tokens, set
+    /// on the choice's last chunk.
+    #[serde(default)]
+    pub finish_reason: Option<FinishReason>,
+}
+
+/// The log probabilities of a completion's tokens, as
+/// parallel lists.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CompletionLogProbs {
+    /// The generated tokens.
+    #[serde(default)]
+    pub tokens: Vec<String>,
+
+    /// The log probability of each token.
+    #[serde(default)]
+    pub token_logprobs: Vec<Option<f64>>,
+
+    /// The most likely tokens at each position, mapped to
+    /// their log probabilities.
+    #[serde(default)]
+    pub top_logprobs: Vec<Option<HashMap<String, f64>>>,
+
+    /// The character offset of each token in the text.
+    #[serde(default)]
+    pub text_offset: Vec<u64>,
+}
+
+/// A request to create embeddings of the given input.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CreateEmbeddingRequest {
+    /// ID of the model to use (e.g. `text-embedding-3-small`).
+    pub model: String,
+
+    /// The text (or tokens) to embed.
+    ///
+    /// To embed multiple inputs in a single request, pass
+    /// an array of strings or an array of token arrays.
+    pub input: EmbeddingInput,
+
+    /// A unique identifier representing your end-user, which can help
+    /// OpenAI to monitor and detect abuse.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+
+    /// The number of dimensions the resulting embeddings
+    /// should have. Only supported by `text-embedding-3`
+    /// and later models.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u64>,
+
+    /// The format to return the embeddings in. Either way,
+    /// they're parsed into `EmbeddingData::embedding`.
+    ///
+    /// Defaults to `float`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding_format: Option<EncodingFormat>,
+}
+
+/// The input to embed, either as text or as tokens.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum EmbeddingInput {
+    /// A single string.
+    String(String),
+
+    /// Multiple strings, each embedded separately.
+    StringArray(Vec<String>),
+
+    /// A single array of token IDs.
+    Tokens(Vec<u64>),
+
+    /// Multiple arrays of token IDs, each embedded separately.
+    TokenArrays(Vec<Vec<u64>>),
+}
+
+impl Default for EmbeddingInput {
+    fn default() -> Self {
+        EmbeddingInput::String(String::new())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum EncodingFormat {
+    #[serde(rename = "float")]
+    Float,
+
+    #[serde(rename = "base64")]
+    Base64,
+}
+
+/// The embeddings created for a request.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CreateEmbeddingResponse {
+    /// The object type, which is always "list".
+    pub object: String,
+
+    /// The embeddings, one per input.
+    pub data: Vec<EmbeddingData>,
+
+    /// The model used to create the embeddings.
+    pub model: String,
+
+    /// Usage statistics for the request.
+    pub usage: EmbeddingUsage,
+}
+
+/// A single embedding vector.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct EmbeddingData {
+    /// The index of the input this embedding is for.
+    pub index: u64,
+
+    /// The embedding vector, decoded from base64 if it was
+    /// requested in that format.
+    #[serde(deserialize_with = "deserialize_embedding")]
+    pub embedding: Vec<f32>,
+
+    /// The object type, which is always "embedding".
+    pub object: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct EmbeddingUsage {
+    /// Number of tokens in the input.
+    pub prompt_tokens: u64,
+
+    /// Total number of tokens used in the request.
+    pub total_tokens: u64,
+}
+
+/// A request to generate images from a prompt.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CreateImageRequest {
+    /// A text description of the desired image(s).
+    pub prompt: String,
+
+    /// The model to use for image generation (e.g. `dall-e-3`).
+    ///
+    /// Defaults to `dall-e-2`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// The number of images to generate. Must be between 1
+    /// and 10. For `dall-e-3`, only `1` is supported.
+    ///
+    /// Defaults to `1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<u64>,
+
+    /// The size of the generated images.
+    ///
+    /// Defaults to `1024x1024`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<ImageSize>,
+
+    /// The quality of the generated images. Only supported
+    /// by `dall-e-3`.
+    ///
+    /// Defaults to `standard`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<ImageQuality>,
+
+    /// The style of the generated images. Only supported
+    /// by `dall-e-3`.
+    ///
+    /// Defaults to `vivid`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<ImageStyle>,
+
+    /// The format the generated images are returned in.
+    ///
+    /// Defaults to `url`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ImageResponseFormat>,
+
+    /// A unique identifier representing your end-user, which can help
+    /// OpenAI to monitor and detect abuse.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ImageSize {
+    /// `256x256`. Only supported by `dall-e-2`.
+    #[serde(rename = "256x256")]
+    Size256,
+
+    /// `512x512`. Only supported by `dall-e-2`.
+    #[serde(rename = "512x512")]
+    Size512,
+
+    /// `1024x1024`.
+    #[serde(rename = "1024x1024")]
+    Size1024,
+
+    /// `1792x1024`. Only supported by `dall-e-3`.
+    #[serde(rename = "1792x1024")]
+    Size1792x1024,
+
+    /// `1024x1792`. Only supported by `dall-e-3`.
+    #[serde(rename = "1024x1792")]
+    Size1024x1792,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ImageQuality {
+    #[serde(rename = "standard")]
+    Standard,
+
+    #[serde(rename = "hd")]
+    Hd,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ImageStyle {
+    #[serde(rename = "vivid")]
+    Vivid,
+
+    #[serde(rename = "natural")]
+    Natural,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ImageResponseFormat {
+    #[serde(rename = "url")]
+    Url,
+
+    #[serde(rename = "b64_json")]
+    B64Json,
+}
+
+/// A request to edit an image, given a prompt and an
+/// optional mask.
+///
+/// This is sent as a multipart form rather than as JSON.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CreateImageEditRequest {
+    /// The PNG image to edit. Must be square and less than
+    /// 4MB. If there's no `mask`, its transparent areas are
+    /// edited.
+    pub image: Vec<u8>,
+
+    /// A PNG image, the same size as `image`, whose fully
+    /// transparent areas show where `image` should be edited.
+    pub mask: Option<Vec<u8>>,
+
+    /// A text description of the desired image(s).
+    pub prompt: String,
+
+    /// The model to use. Only `dall-e-2` is supported.
+    pub model: Option<String>,
+
+    /// The number of images to generate. Must be between 1
+    /// and 10.
+    ///
+    /// Defaults to `1`.
+    pub n: Option<u64>,
+
+    /// The size of the generated images.
+    ///
+    /// Defaults to `1024x1024`.
+    pub size: Option<ImageSize>,
+
+    /// The format the generated images are returned in.
+    ///
+    /// Defaults to `url`.
+    pub response_format: Option<ImageResponseFormat>,
+
+    /// A unique identifier representing your end-user, which can help
+    /// OpenAI to monitor and detect abuse.
+    pub user: Option<String>,
+}
+
+impl CreateImageEditRequest {
+    /// Returns the request's text fields, as sent in its
+    /// multipart form.
+    pub(crate) fn form_fields(&self) -> Result<Vec<(&'static str, String)>> {
+        let mut fields = vec![("prompt", self.prompt.clone())];
+        push_form_field(&mut fields, "model", &self.model)?;
+        push_form_field(&mut fields, "n", &self.n)?;
+        push_form_field(&mut fields, "size", &self.size)?;
+        push_form_field(&mut fields, "response_format", &self.response_format)?;
+        push_form_field(&mut fields, "user", &self.user)?;
+        Ok(fields)
+    }
+}
+
+/// A request to create variations of an image.
+///
+/// This is sent as a multipart form rather than as JSON.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CreateImageVariationRequest {
+    /// The PNG image to vary. Must be square and less than
+    /// 4MB.
+    pub image: Vec<u8>,
+
+    /// The model to use. Only `dall-e-2` is supported.
+    pub model: Option<String>,
+
+    /// The number of images to generate. Must be between 1
+    /// and 10.
+    ///
+    /// Defaults to `1`.
+    pub n: Option<u64>,
+
+    /// The size of the generated images.
+    ///
+    /// Defaults to `1024x1024`.
+    pub size: Option<ImageSize>,
+
+    /// The format the generated images are returned in.
+    ///
+    /// Defaults to `url`.
+    pub response_format: Option<ImageResponseFormat>,
+
+    /// A unique identifier representing your end-user, which can help
+    /// OpenAI to monitor and detect abuse.
+    pub user: Option<String>,
+}
+
+impl CreateImageVariationRequest {
+    /// Returns the request's text fields, as sent in its
+    /// multipart form.
+    pub(crate) fn form_fields(&self) -> Result<Vec<(&'static str, String)>> {
+        let mut fields = Vec::new();
+        push_form_field(&mut fields, "model", &self.model)?;
+        push_form_field(&mut fields, "n", &self.n)?;
+        push_form_field(&mut fields, "size", &self.size)?;
+        push_form_field(&mut fields, "response_format", &self.response_format)?;
+        push_form_field(&mut fields, "user", &self.user)?;
+        Ok(fields)
+    }
+}
+
+/// Adds an optional value to a multipart form's text fields,
+/// formatted as its JSON value (without quotes around
+/// strings).
+fn push_form_field<T: Serialize>(
+    fields: &mut Vec<(&'static str, String)>,
+    name: &'static str,
+    value: &Option<T>,
+) -> Result<()> {
+    if let Some(value) = value {
+        let value = match serde_json::to_value(value)? {
+            serde_json::Value::String(value) => value,
+            value => value.to_string(),
+        };
+        fields.push((name, value));
+    }
+    Ok(())
+}
+
+/// The images returned by an image request.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ImageResponse {
+    /// A unix timestamp of when the images were created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created: u64,
+
+    /// The generated images.
+    pub data: Vec<ImageData>,
+}
+
+/// A single generated image.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ImageData {
+    /// The URL of the image, if `response_format` was `url`.
+    pub url: Option<String>,
+
+    /// The base64-encoded image, if `response_format`
+    /// was `b64_json`.
+    pub b64_json: Option<String>,
+
+    /// The prompt that was used to generate the image, if
+    /// the model revised it.
+    pub revised_prompt: Option<String>,
+}
+
+/// A request to classify whether text violates
+/// OpenAI's usage policies.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ModerationRequest {
+    /// The text to classify.
+    pub input: ModerationInput,
+
+    /// The moderation model to use (e.g. `text-moderation-latest`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// The text to classify, either a single string or
+/// multiple strings classified separately.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ModerationInput {
+    String(String),
+    StringArray(Vec<String>),
+}
+
+impl Default for ModerationInput {
+    fn default() -> Self {
+        ModerationInput::String(String::new())
+    }
+}
+
+/// The moderation results for a request.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ModerationResponse {
+    /// A unique identifier for the moderation request.
+    pub id: String,
+
+    /// The model used to classify the input.
+    pub model: String,
+
+    /// The results, one per input.
+    pub results: Vec<ModerationResult>,
+}
+
+/// The moderation result for a single input.
+///
+/// Categories are kept as maps (rather than struct fields)
+/// so that categories OpenAI adds later are still available.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ModerationResult {
+    /// Whether any of the categories were flagged.
+    pub flagged: bool,
+
+    /// Whether each category (e.g. `hate`, `self-harm`)
+    /// was flagged.
+    pub categories: HashMap<String, bool>,
+
+    /// The model's confidence score for each category.
+    pub category_scores: HashMap<String, f64>,
+}
+
+/// A request to generate spoken audio from text.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct SpeechRequest {
+    /// The TTS model to use (e.g. `tts-1` or `tts-1-hd`).
+    pub model: String,
+
+    /// The text to generate audio for. The maximum length
+    /// is 4096 characters.
+    pub input: String,
+
+    /// The voice to use when generating the audio.
+    pub voice: Voice,
+
+    /// The format of the generated audio.
+    ///
+    /// Defaults to `mp3`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<SpeechResponseFormat>,
+
+    /// The speed of the generated audio, between `0.25`
+    /// and `4.0`.
+    ///
+    /// Defaults to `1`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub enum Voice {
+    #[default]
+    #[serde(rename = "alloy")]
+    Alloy,
+
+    #[serde(rename = "echo")]
+    Echo,
+
+    #[serde(rename = "fable")]
+    Fable,
+
+    #[serde(rename = "onyx")]
+    Onyx,
+
+    #[serde(rename = "nova")]
+    Nova,
+
+    #[serde(rename = "shimmer")]
+    Shimmer,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum SpeechResponseFormat {
+    #[serde(rename = "mp3")]
+    Mp3,
+
+    #[serde(rename = "opus")]
+    Opus,
+
+    #[serde(rename = "aac")]
+    Aac,
+
+    #[serde(rename = "flac")]
+    Flac,
+
+    #[serde(rename = "wav")]
+    Wav,
+
+    #[serde(rename = "pcm")]
+    Pcm,
+}
+
+/// A request to translate audio into English text.
+///
+/// This is sent as a multipart form rather than as JSON.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CreateTranslationRequest {
+    /// The audio file to translate.
+    pub file: Vec<u8>,
+
+    /// The name of the audio file. Its extension tells the
+    /// API the audio's format (e.g. `speech.mp3`).
+    pub filename: String,
+
+    /// The model to use (e.g. `whisper-1`).
+    pub model: String,
+
+    /// Text (in English) to guide the model's style, or to
+    /// continue a previous segment.
+    pub prompt: Option<String>,
+
+    /// The format of the translation.
+    ///
+    /// Defaults to `json`.
+    pub response_format: Option<AudioResponseFormat>,
+
+    /// The sampling temperature, between 0 and 1.
+    ///
+    /// Defaults to `0`.
+    pub temperature: Option<f64>,
+}
+
+impl CreateTranslationRequest {
+    /// Returns the request's text fields, as sent in its
+    /// multipart form.
+    pub(crate) fn form_fields(&self) -> Result<Vec<(&'static str, String)>> {
+        let mut fields = vec![("model", self.model.clone())];
+        push_form_field(&mut fields, "prompt", &self.prompt)?;
+        push_form_field(&mut fields, "response_format", &self.response_format)?;
+        push_form_field(&mut fields, "temperature", &self.temperature)?;
+        Ok(fields)
+    }
+}
+
+/// The format of a transcription or translation.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+pub enum AudioResponseFormat {
+    /// A JSON object with the text.
+    #[default]
+    #[serde(rename = "json")]
+    Json,
+
+    /// Plain text.
+    #[serde(rename = "text")]
+    Text,
+
+    /// SubRip subtitles.
+    #[serde(rename = "srt")]
+    Srt,
+
+    /// JSON with the detected language and duration.
+    #[serde(rename = "verbose_json")]
+    VerboseJson,
+
+    /// WebVTT subtitles.
+    #[serde(rename = "vtt")]
+    Vtt,
+}
+
+/// The response from the audio translations endpoint.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct TranslationResponse {
+    /// The translated text (or subtitles, for `srt` and
+    /// `vtt`).
+    pub text: String,
+
+    /// The language of the input audio, for `verbose_json`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// The duration of the input audio in seconds, for
+    /// `verbose_json`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+}
+
+impl TranslationResponse {
+    /// Parses a response body in the given format. The
+    /// non-JSON formats are returned as the `text`.
+    pub(crate) fn from_body(body: &str, format: Option<AudioResponseFormat>) -> Result<Self> {
+        match format.unwrap_or_default() {
+            AudioResponseFormat::Json | AudioResponseFormat::VerboseJson => {
+                Ok(serde_json::from_str(body)?)
+            }
+            AudioResponseFormat::Text | AudioResponseFormat::Srt | AudioResponseFormat::Vtt => {
+                Ok(Self {
+                    text: body.to_string(),
+                    ..Default::default()
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct DeleteModelResponse {
+    /// The ID of the deleted model.
+    pub id: String,
+
+    /// The object type, which is always "model".
+    pub object: String,
+
+    /// Whether the model was deleted.
+    pub deleted: bool,
+}
+
+/// A file uploaded to the API (e.g. training data for
+/// fine-tuning).
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct FileObject {
+    /// The file identifier, which can be referenced in the
+    /// API endpoints.
+    pub id: String,
+
+    /// The object type, which is always "file".
+    pub object: String,
+
+    /// The size of the file, in bytes.
+    pub bytes: u64,
+
+    /// The unix timestamp (in seconds) of when the file was
+    /// created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created_at: u64,
+
+    /// The name of the file.
+    pub filename: String,
+
+    /// The intended purpose of the file (e.g. "fine-tune"
+    /// or "assistants").
+    pub purpose: String,
+
+    /// The processing status of the file (e.g. "uploaded",
+    /// "processed" or "error"). Deprecated by the API, so it
+    /// may be missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+impl HasId for FileObject {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct DeleteFileResponse {
+    /// The ID of the deleted file.
+    pub id: String,
+
+    /// The object type, which is always "file".
+    pub object: String,
+
+    /// Whether the file was deleted.
+    pub deleted: bool,
+}
+
+/// A hyperparameter for a fine-tuning job, which is either
+/// chosen automatically or set to a specific value.
+///
+/// Serializes to either `"auto"` or the value.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Hyperparameter<T> {
+    /// Let the API choose the value.
+    Mode(HyperparameterMode),
+
+    /// Use a specific value.
+    Value(T),
+}
+
+impl<T> Default for Hyperparameter<T> {
+    fn default() -> Self {
+        Hyperparameter::Mode(HyperparameterMode::default())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub enum HyperparameterMode {
+    /// The API picks the value based on the dataset.
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+}
+
+/// The hyperparameters used for a fine-tuning job.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Hyperparameters {
+    /// The number of epochs to train the model for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n_epochs: Option<Hyperparameter<u32>>,
+
+    /// The number of examples in each batch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<Hyperparameter<u32>>,
+
+    /// The scaling factor for the learning rate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub learning_rate_multiplier: Option<Hyperparameter<f64>>,
+}
+
+/// A request to create a fine-tuning job.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CreateFineTuningJobRequest {
+    /// The name of the model to fine-tune.
+    pub model: String,
+
+    /// The ID of an uploaded file (with the purpose
+    /// "fine-tune") that contains the training data.
+    pub training_file: String,
+
+    /// The ID of an uploaded file that contains the
+    /// validation data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_file: Option<String>,
+
+    /// The hyperparameters to use for the job.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hyperparameters: Option<Hyperparameters>,
+
+    /// A string of up to 18 characters that will be added
+    /// to the fine-tuned model's name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+
+    /// The seed used to make the job reproducible.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FineTuningJobStatus {
+    #[serde(rename = "validating_files")]
+    ValidatingFiles,
+
+    #[default]
+    #[serde(rename = "queued")]
+    Queued,
+
+    #[serde(rename = "running")]
+    Running,
+
+    #[serde(rename = "succeeded")]
+    Succeeded,
+
+    #[serde(rename = "failed")]
+    Failed,
+
+    #[serde(rename = "cancelled")]
+    Cancelled,
+
+    /// A status this version of the crate doesn't know about.
+    #[serde(other)]
+    Unknown,
+}
+
+/// A fine-tuning job, which creates a new model from a
+/// base model and a training file.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct FineTuningJob {
+    /// The job identifier, which can be referenced in the
+    /// API endpoints.
+    pub id: String,
+
+    /// The object type, which is always "fine_tuning.job".
+    pub object: String,
+
+    /// The unix timestamp (in seconds) of when the job was
+    /// created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created_at: u64,
+
+    /// The unix timestamp (in seconds) of when the job
+    /// finished, if it has.
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+
+    /// The base model being fine-tuned.
+    pub model: String,
+
+    /// The name of the fine-tuned model, once the job has
+    /// succeeded.
+    #[serde(default)]
+    pub fine_tuned_model: Option<String>,
+
+    /// The organization that owns the job.
+    #[serde(default)]
+    pub organization_id: String,
+
+    /// The current status of the job.
+    pub status: FineTuningJobStatus,
+
+    /// The hyperparameters used for the job.
+    #[serde(default)]
+    pub hyperparameters: Hyperparameters,
+
+    /// The ID of the file used for training.
+    pub training_file: String,
+
+    /// The ID of the file used for validation, if any.
+    #[serde(default)]
+    pub validation_file: Option<String>,
+
+    /// The IDs of the job's result files.
+    #[serde(default)]
+    pub result_files: Vec<String>,
+
+    /// The total number of billable tokens processed by the
+    /// job, once it has succeeded.
+    #[serde(default)]
+    pub trained_tokens: Option<u64>,
+
+    /// The reason the job failed, if it did.
+    #[serde(default)]
+    pub error: Option<FineTuningJobError>,
+
+    /// The seed used for the job.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+}
+
+impl HasId for FineTuningJob {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Describes why a fine-tuning job failed.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct FineTuningJobError {
+    /// A machine-readable error code.
+    pub code: String,
+
+    /// A human-readable error message.
+    pub message: String,
+
+    /// The parameter that was invalid (e.g. `training_file`),
+    /// if any.
+    #[serde(default)]
+    pub param: Option<String>,
+}
+
+/// A status update (or log message) for a fine-tuning job.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct FineTuningJobEvent {
+    /// The event identifier.
+    pub id: String,
+
+    /// The object type, which is always
+    /// "fine_tuning.job.event".
+    pub object: String,
+
+    /// The unix timestamp (in seconds) of when the event was
+    /// created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created_at: u64,
+
+    /// The log level of the event (e.g. "info" or "warn").
+    pub level: String,
+
+    /// The event's message.
+    pub message: String,
+}
+
+impl HasId for FineTuningJobEvent {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// A tool an assistant can use.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum AssistantTool {
+    /// Lets the assistant write and run Python code.
+    #[serde(rename = "code_interpreter")]
+    CodeInterpreter,
+
+    /// Lets the assistant search the files attached to it.
+    #[serde(rename = "file_search")]
+    FileSearch,
+
+    /// A function the assistant may call.
+    #[serde(rename = "function")]
+    Function { function: Function },
+}
+
+impl AssistantTool {
+    /// Creates a tool wrapping the given function.
+    pub fn function(function: Function) -> Self {
+        AssistantTool::Function { function }
+    }
+}
+
+/// An assistant, i.e. a model with instructions and tools
+/// that can run against threads.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Assistant {
+    /// The assistant identifier, which can be referenced in
+    /// the API endpoints.
+    pub id: String,
+
+    /// The object type, which is always "assistant".
+    pub object: String,
+
+    /// The unix timestamp (in seconds) of when the assistant
+    /// was created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created_at: u64,
+
+    /// The name of the assistant.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// The description of the assistant.
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// The model the assistant uses.
+    pub model: String,
+
+    /// The system instructions the assistant uses.
+    #[serde(default)]
+    pub instructions: Option<String>,
+
+    /// The tools enabled on the assistant.
+    #[serde(default)]
+    pub tools: Vec<AssistantTool>,
+
+    /// Up to 16 key-value pairs of extra information about
+    /// the assistant.
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl HasId for Assistant {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// A request to create an assistant.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CreateAssistantRequest {
+    /// The model the assistant should use.
+    pub model: String,
+
+    /// The name of the assistant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The description of the assistant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The system instructions the assistant should use.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+
+    /// The tools to enable on the assistant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<AssistantTool>>,
+
+    /// Up to 16 key-value pairs of extra information about
+    /// the assistant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+
+    /// The sampling temperature, between 0 and 2.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+
+    /// The nucleus sampling probability mass, between 0 and 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+}
+
+impl WithMetadata for CreateAssistantRequest {
+    fn metadata_mut(&mut self) -> &mut Option<HashMap<String, String>> {
+        &mut self.metadata
+    }
+}
+
+/// A request to modify an assistant. Only the fields that
+/// are set are changed.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ModifyAssistantRequest {
+    /// The model the assistant should use.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// The name of the assistant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The description of the assistant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The system instructions the assistant should use.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+
+    /// The tools to enable on the assistant, replacing the
+    /// current ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<AssistantTool>>,
+
+    /// Up to 16 key-value pairs of extra information about
+    /// the assistant, replacing the current ones.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+
+    /// The sampling temperature, between 0 and 2.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+
+    /// The nucleus sampling probability mass, between 0 and 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+}
+
+impl WithMetadata for ModifyAssistantRequest {
+    fn metadata_mut(&mut self) -> &mut Option<HashMap<String, String>> {
+        &mut self.metadata
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct DeleteAssistantResponse {
+    /// The ID of the deleted assistant.
+    pub id: String,
+
+    /// The object type, which is always "assistant.deleted".
+    pub object: String,
+
+    /// Whether the assistant was deleted.
+    pub deleted: bool,
+}
+
+/// A conversation between a user and an assistant.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Thread {
+    /// The thread identifier, which can be referenced in the
+    /// API endpoints.
+    pub id: String,
+
+    /// The object type, which is always "thread".
+    pub object: String,
+
+    /// The unix timestamp (in seconds) of when the thread
+    /// was created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created_at: u64,
+
+    /// Up to 16 key-value pairs of extra information about
+    /// the thread.
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+/// A request to create a thread.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CreateThreadRequest {
+    /// Messages to start the thread with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub messages: Option<Vec<CreateMessageRequest>>,
+
+    /// Up to 16 key-value pairs of extra information about
+    /// the thread.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+impl WithMetadata for CreateThreadRequest {
+    fn metadata_mut(&mut self) -> &mut Option<HashMap<String, String>> {
+        &mut self.metadata
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct DeleteThreadResponse {
+    /// The ID of the deleted thread.
+    pub id: String,
+
+    /// The object type, which is always "thread.deleted".
+    pub object: String,
+
+    /// Whether the thread was deleted.
+    pub deleted: bool,
+}
+
+/// A request to add a message to a thread.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CreateMessageRequest {
+    /// The role of the message's author, either `user` or
+    /// `assistant`.
+    pub role: Role,
+
+    /// The message's contents, either text or a list of
+    /// (text and image) parts.
+    pub content: MessageContent,
+
+    /// Up to 16 key-value pairs of extra information about
+    /// the message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+impl WithMetadata for CreateMessageRequest {
+    fn metadata_mut(&mut self) -> &mut Option<HashMap<String, String>> {
+        &mut self.metadata
+    }
+}
+
+impl CreateMessageRequest {
+    /// Creates a user message with the given content.
+    pub fn user(content: impl Into<MessageContent>) -> Self {
+        Self {
+            role: Role::User,
+            content: content.into(),
+            metadata: None,
+        }
+    }
+}
+
+/// A message in a thread.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ThreadMessage {
+    /// The message identifier, which can be referenced in
+    /// the API endpoints.
+    pub id: String,
+
+    /// The object type, which is always "thread.message".
+    pub object: String,
+
+    /// The unix timestamp (in seconds) of when the message
+    /// was created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created_at: u64,
+
+    /// The thread the message belongs to.
+    pub thread_id: String,
+
+    /// The role of the message's author.
+    pub role: Role,
+
+    /// The message's contents.
+    #[serde(default)]
+    pub content: Vec<ThreadMessageContent>,
+
+    /// The assistant that wrote the message, if any.
+    #[serde(default)]
+    pub assistant_id: Option<String>,
+
+    /// The run that created the message, if any.
+    #[serde(default)]
+    pub run_id: Option<String>,
+
+    /// Up to 16 key-value pairs of extra information about
+    /// the message.
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl HasId for ThreadMessage {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl ThreadMessage {
+    /// Returns the message's text parts, joined together.
+    pub fn text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|part| match part {
+                ThreadMessageContent::Text { text } => Some(text.value.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// A single part of a thread message's contents.
+///
+/// Unlike `ContentPart`, text parts returned by the API
+/// carry their annotations (e.g. file citations) alongside
+/// the text.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum ThreadMessageContent {
+    #[serde(rename = "text")]
+    Text { text: MessageText },
+
+    #[serde(rename = "image_url")]
+    ImageUrl { image_url: ImageUrl },
+
+    #[serde(rename = "image_file")]
+    ImageFile { image_file: ImageFile },
+
+    /// A content type this version of the crate doesn't
+    /// know about.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct MessageText {
+    /// The text.
+    pub value: String,
+
+    /// Annotations on spans of the text (e.g. file
+    /// citations), as raw JSON.
+    #[serde(default)]
+    pub annotations: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ImageFile {
+    /// The ID of the uploaded image file.
+    pub file_id: String,
+
+    /// The detail level the model should process the
+    /// image at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<ImageDetail>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    #[default]
+    #[serde(rename = "queued")]
+    Queued,
+
+    #[serde(rename = "in_progress")]
+    InProgress,
+
+    #[serde(rename = "requires_action")]
+    RequiresAction,
+
+    #[serde(rename = "cancelling")]
+    Cancelling,
+
+    #[serde(rename = "cancelled")]
+    Cancelled,
+
+    #[serde(rename = "failed")]
+    Failed,
+
+    #[serde(rename = "completed")]
+    Completed,
+
+    #[serde(rename = "incomplete")]
+    Incomplete,
+
+    #[serde(rename = "expired")]
+    Expired,
+
+    /// A status this version of the crate doesn't know about.
+    #[serde(other)]
+    Unknown,
+}
+
+impl RunStatus {
+    /// Has the run stopped for good (i.e. its status will
+    /// never change again)?
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            RunStatus::Completed
+                | RunStatus::Failed
+                | RunStatus::Cancelled
+                | RunStatus::Expired
+                | RunStatus::Incomplete
+        )
+    }
+}
+
+/// An execution of an assistant on a thread.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Run {
+    /// The run identifier, which can be referenced in the
+    /// API endpoints.
+    pub id: String,
+
+    /// The object type, which is always "thread.run".
+    pub object: String,
+
+    /// The unix timestamp (in seconds) of when the run was
+    /// created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created_at: u64,
+
+    /// The thread the run is executing on.
+    pub thread_id: String,
+
+    /// The assistant used for the run.
+    pub assistant_id: String,
+
+    /// The run's status.
+    pub status: RunStatus,
+
+    /// What the caller needs to do for the run to continue,
+    /// if its status is `requires_action`.
+    #[serde(default)]
+    pub required_action: Option<RequiredAction>,
+
+    /// Why the run failed, if it did.
+    #[serde(default)]
+    pub last_error: Option<RunError>,
+
+    /// The unix timestamp (in seconds) of when the run will
+    /// expire.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+
+    /// The unix timestamp (in seconds) of when the run was
+    /// started.
+    #[serde(default)]
+    pub started_at: Option<u64>,
+
+    /// The unix timestamp (in seconds) of when the run was
+    /// cancelled.
+    #[serde(default)]
+    pub cancelled_at: Option<u64>,
+
+    /// The unix timestamp (in seconds) of when the run
+    /// failed.
+    #[serde(default)]
+    pub failed_at: Option<u64>,
+
+    /// The unix timestamp (in seconds) of when the run was
+    /// completed.
+    #[serde(default)]
+    pub completed_at: Option<u64>,
+
+    /// The model the assistant used for the run.
+    #[serde(default)]
+    pub model: String,
+
+    /// The instructions the assistant used for the run.
+    #[serde(default)]
+    pub instructions: Option<String>,
+
+    /// The tools the assistant could use for the run.
+    #[serde(default)]
+    pub tools: Vec<AssistantTool>,
+
+    /// The token usage of the run, once it's finished.
+    #[serde(default)]
+    pub usage: Option<ChatCompletionUsage>,
+
+    /// Up to 16 key-value pairs of extra information about
+    /// the run.
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl HasId for Run {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// An action a run needs the caller to take before it can
+/// continue.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct RequiredAction {
+    /// The type of action, which is currently always
+    /// "submit_tool_outputs".
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// The tool calls whose outputs are needed.
+    pub submit_tool_outputs: SubmitToolOutputsAction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct SubmitToolOutputsAction {
+    /// The tool calls to run, whose outputs should be sent
+    /// with `submit_tool_outputs`.
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// Describes why a run failed.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct RunError {
+    /// A machine-readable error code (e.g. `rate_limit_exceeded`).
+    pub code: String,
+
+    /// A human-readable error message.
+    pub message: String,
+}
+
+/// A request to run an assistant on a thread.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CreateRunRequest {
+    /// The ID of the assistant to run.
+    pub assistant_id: String,
+
+    /// Overrides the assistant's model.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Overrides the assistant's instructions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+
+    /// Instructions appended to the assistant's own, for
+    /// this run only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_instructions: Option<String>,
+
+    /// Overrides the assistant's tools.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<AssistantTool>>,
+
+    /// Up to 16 key-value pairs of extra information about
+    /// the run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+
+    /// The sampling temperature, between 0 and 2.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+
+    /// The nucleus sampling probability mass.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+}
+
+impl WithMetadata for CreateRunRequest {
+    fn metadata_mut(&mut self) -> &mut Option<HashMap<String, String>> {
+        &mut self.metadata
+    }
+}
+
+/// The outputs of the tool calls a run is waiting on.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct SubmitToolOutputsRequest {
+    pub tool_outputs: Vec<ToolOutput>,
+}
+
+/// The output of a single tool call.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ToolOutput {
+    /// The ID of the tool call (from the run's
+    /// `required_action`) this is the output of.
+    pub tool_call_id: String,
+
+    /// The output of the tool call.
+    pub output: String,
+}
+
+/// A request to create a batch of API requests, which are
+/// run asynchronously at a lower cost.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct CreateBatchRequest {
+    /// The ID of an uploaded JSONL file (with purpose
+    /// `batch`) containing the requests.
+    pub input_file_id: String,
+
+    /// The endpoint the requests are sent to (e.g.
+    /// `/v1/chat/completions`).
+    pub endpoint: String,
+
+    /// The time frame the batch should be processed in.
+    /// Currently only `24h` is supported.
+    pub completion_window: String,
+
+    /// Up to 16 key-value pairs of extra information about
+    /// the batch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+impl WithMetadata for CreateBatchRequest {
+    fn metadata_mut(&mut self) -> &mut Option<HashMap<String, String>> {
+        &mut self.metadata
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BatchStatus {
+    #[default]
+    #[serde(rename = "validating")]
+    Validating,
+
+    #[serde(rename = "failed")]
+    Failed,
+
+    #[serde(rename = "in_progress")]
+    InProgress,
+
+    #[serde(rename = "finalizing")]
+    Finalizing,
+
+    #[serde(rename = "completed")]
+    Completed,
+
+    #[serde(rename = "expired")]
+    Expired,
+
+    #[serde(rename = "cancelling")]
+    Cancelling,
+
+    #[serde(rename = "cancelled")]
+    Cancelled,
+
+    /// A status this version of the crate doesn't know about.
+    #[serde(other)]
+    Unknown,
+}
+
+/// A batch of API requests.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Batch {
+    /// The batch identifier, which can be referenced in the
+    /// API endpoints.
+    pub id: String,
+
+    /// The object type, which is always "batch".
+    pub object: String,
+
+    /// The endpoint the batch's requests are sent to.
+    pub endpoint: String,
+
+    /// The ID of the input file.
+    pub input_file_id: String,
+
+    /// The time frame the batch should be processed in.
+    pub completion_window: String,
+
+    /// The batch's status.
+    pub status: BatchStatus,
+
+    /// How many of the batch's requests have finished.
+    #[serde(default)]
+    pub request_counts: BatchRequestCounts,
+
+    /// The ID of the file with the successful requests'
+    /// outputs, once there is one.
+    #[serde(default)]
+    pub output_file_id: Option<String>,
+
+    /// The ID of the file with the failed requests' errors,
+    /// once there is one.
+    #[serde(default)]
+    pub error_file_id: Option<String>,
+
+    /// The unix timestamp (in seconds) of when the batch was
+    /// created.
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub created_at: u64,
+
+    /// The unix timestamp (in seconds) of when the batch
+    /// started processing.
+    #[serde(default)]
+    pub in_progress_at: Option<u64>,
+
+    /// The unix timestamp (in seconds) of when the batch
+    /// will expire.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+
+    /// The unix timestamp (in seconds) of when the batch was
+    /// completed.
+    #[serde(default)]
+    pub completed_at: Option<u64>,
+
+    /// The unix timestamp (in seconds) of when the batch
+    /// failed.
+    #[serde(default)]
+    pub failed_at: Option<u64>,
+
+    /// The unix timestamp (in seconds) of when the batch
+    /// expired.
+    #[serde(default)]
+    pub expired_at: Option<u64>,
+
+    /// The unix timestamp (in seconds) of when the batch was
+    /// cancelled.
+    #[serde(default)]
+    pub cancelled_at: Option<u64>,
+
+    /// Up to 16 key-value pairs of extra information about
+    /// the batch.
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl HasId for Batch {
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// The number of requests in a batch, by outcome.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BatchRequestCounts {
+    /// The total number of requests in the batch.
+    pub total: u64,
+
+    /// The number of requests that completed successfully.
+    pub completed: u64,
+
+    /// The number of requests that failed.
+    pub failed: u64,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn chat_completion_chunk_from_chunk() {
+        // Define cases to test...
+        #[allow(clippy::enum_variant_names)]
+        enum Res {
+            IsErr,
+            IsDone,
+            IsChunk(ChatCompletionChunk),
+        }
+        let cases = vec![
+            ("", Res::IsErr),
+            ("{{", Res::IsErr),
+            ("d a t a :", Res::IsErr),
+            ("data: [DONE]", Res::IsDone),
+            ("data: [DONE]\n\n", Res::IsDone),
+            (
+                "data: {
+                    \"id\": \"\",
+                    \"object\": \"\",
+                    \"created\": 0,
+                    \"model\": \"\",
+                    \"choices\": []
+                }",
+                Res::IsChunk(ChatCompletionChunk {
+                    ..Default::default()
+                }),
+            ),
+            (
+                "data: {
+                    \"id\": \"chatcmpl-123\",
+                    \"object\": \"chat.completion.chunk\",
+                    \"created\": 1677652288,
+                    \"model\": \"gpt-3.5-turbo\",
+                    \"choices\": []
+                }",
+                Res::IsChunk(ChatCompletionChunk {
+                    id: "chatcmpl-123".to_string(),
+                    object: "chat.completion.chunk".to_string(),
+                    created: 1677652288,
+                    model: "gpt-3.5-turbo".to_string(),
+                    ..Default::default()
+                }),
+            ),
+            (
+                "data: {
+                    \"id\": \"chatcmpl-123\",
+                    \"object\": \"chat.completion.chunk\",
+                    \"created\": 1677652288,
+                    \"model\": \"gpt-3.5-turbo\",
+                    \"choices\": [
+                        {
+                            \"index\": 0,
+                            \"finish_reason\": \"stop\",
+                            \"delta\": {
+                                \"role\": \"system\",
+                                \"content\": \"You are a helpful assistant.\"
+                            }
+                        }
+                    ]
+                }",
+                Res::IsChunk(ChatCompletionChunk {
+                    id: "chatcmpl-123".to_string(),
+                    object: "chat.completion.chunk".to_string(),
+                    created: 1677652288,
+                    model: "gpt-3.5-turbo".to_string(),
+                    choices: vec![ChatCompletionChunkChoice {
+                        index: 0,
+                        finish_reason: Some(FinishReason::Stop),
+                        delta: ChatCompletionDelta {
+                            role: Some(Role::System),
+                            content: Some("You are a helpful assistant.".to_string()),
+                            ..Default::default()
+                        },
+                    }],
+                    usage: None,
+                }),
+            ),
+            (
+                "data: {
+                    \"id\": \"chatcmpl-123\",
+                    \"object\": \"chat.completion.chunk\",
+                    \"created\": 1677652288,
+                    \"model\": \"gpt-3.5-turbo\",
+                    \"choices\": [
+                        {
                             \"index\": 0,
                             \"finish_reason\": \"stop\",
                             \"delta\": {
@@ -463,7 +3684,7 @@ mod test {
                             \"delta\": {
                                 \"role\": \"assistant\",
                                 \"function_call\": {
-                                    \"role\": \"get_weather\",
+                                    \"name\": \"get_weather\",
                                     \"arguments\": \"{\\\"loc\\\": \\\"Los Angeles\\\"}\"
                                 }
                             }
@@ -478,64 +3699,1631 @@ mod test {
                     choices: vec![
                         ChatCompletionChunkChoice {
                             index: 0,
-                            finish_reason: Some("stop".to_string()),
-                            delta: ChatCompletionMessage {
-                                role: "assistant".to_string(),
+                            finish_reason: Some(FinishReason::Stop),
+                            delta: ChatCompletionDelta {
+                                role: Some(Role::Assistant),
                                 content: Some("You are a helpful assistant.".to_string()),
                                 ..Default::default()
                             },
                         },
                         ChatCompletionChunkChoice {
                             index: 1,
-                            finish_reason: Some("length".to_string()),
-                            delta: ChatCompletionMessage {
-                                role: "assistant".to_string(),
+                            finish_reason: Some(FinishReason::Length),
+                            delta: ChatCompletionDelta {
+                                role: Some(Role::Assistant),
                                 content: Some("You are a helpful assistant.".to_string()),
                                 ..Default::default()
                             },
                         },
                         ChatCompletionChunkChoice {
                             index: 2,
-                            finish_reason: Some("function_call".to_string()),
-                            delta: ChatCompletionMessage {
-                                role: "assistant".to_string(),
-                                function_call: Some(FunctionCall {
-                                    name: "get_weather".to_string(),
-                                    arguments: "{\"loc\": \"Los Angeles\"}".to_string(),
+                            finish_reason: Some(FinishReason::FunctionCall),
+                            delta: ChatCompletionDelta {
+                                role: Some(Role::Assistant),
+                                function_call: Some(FunctionCallDelta {
+                                    name: Some("get_weather".to_string()),
+                                    arguments: Some("{\"loc\": \"Los Angeles\"}".to_string()),
                                 }),
                                 ..Default::default()
                             },
                         },
-                    ],
-                }),
+                    ],
+                    usage: None,
+                }),
+            ),
+        ];
+
+        // Iterate over cases and test...
+        for (i, (chunk, res)) in cases.into_iter().enumerate() {
+            let data = ChatCompletionChunk::from_chunk(chunk);
+            match res {
+                Res::IsErr => assert!(data.is_err(), "expected case {} to be an error", i),
+                Res::IsDone => {
+                    assert!(
+                        data.is_ok(),
+                        "expected case {} to not be an error. Err: {}",
+                        i,
+                        data.unwrap_err()
+                    );
+                    assert!(matches!(data, Ok(None)), "expected case {} to be [DONE]", i)
+                }
+                Res::IsChunk(chunk) => {
+                    assert!(
+                        data.is_ok(),
+                        "expected case {} to not be an error. Err: {}",
+                        i,
+                        data.unwrap_err(),
+                    );
+                    let data = data.unwrap();
+                    assert!(data.is_some(), "expected case {} to not be none", i);
+                    assert_eq!(data, Some(chunk), "expected case {} to be a chunk", i);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chat_completion_usage_add() {
+        let a = ChatCompletionUsage {
+            prompt_tokens: 10,
+            completion_tokens: 5,
+            total_tokens: 15,
+            prompt_tokens_details: Some(PromptTokensDetails {
+                cached_tokens: 8,
+                audio_tokens: 0,
+            }),
+            completion_tokens_details: None,
+        };
+        let b = ChatCompletionUsage {
+            prompt_tokens: 1,
+            completion_tokens: 2,
+            total_tokens: 3,
+            prompt_tokens_details: Some(PromptTokensDetails {
+                cached_tokens: 1,
+                audio_tokens: 0,
+            }),
+            completion_tokens_details: Some(CompletionTokensDetails {
+                reasoning_tokens: 2,
+                audio_tokens: 0,
+            }),
+        };
+        let expected = ChatCompletionUsage {
+            prompt_tokens: 11,
+            completion_tokens: 7,
+            total_tokens: 18,
+            prompt_tokens_details: Some(PromptTokensDetails {
+                cached_tokens: 9,
+                audio_tokens: 0,
+            }),
+            completion_tokens_details: Some(CompletionTokensDetails {
+                reasoning_tokens: 2,
+                audio_tokens: 0,
+            }),
+        };
+
+        // Check the operators...
+        assert_eq!(a.clone() + b.clone(), expected);
+        let mut c = a.clone();
+        c += b.clone();
+        assert_eq!(c, expected);
+
+        // Check summing an iterator (including an empty one)...
+        let total: ChatCompletionUsage = vec![a, b].into_iter().sum();
+        assert_eq!(total, expected);
+        let empty: ChatCompletionUsage = Vec::new().into_iter().sum();
+        assert_eq!(empty, ChatCompletionUsage::default());
+    }
+
+    #[test]
+    fn chat_completion_message_result_constructors() {
+        // Check the tool message...
+        let msg = ChatCompletionMessage::tool_result("call_abc123", "72");
+        let value = serde_json::to_value(&msg).unwrap();
+        assert_eq!(value["role"], "tool");
+        assert_eq!(value["tool_call_id"], "call_abc123");
+        assert_eq!(value["content"], "72");
+        assert!(value.get("name").is_none());
+
+        // Check the function message...
+        let msg = ChatCompletionMessage::function_result("get_weather", "72");
+        let value = serde_json::to_value(&msg).unwrap();
+        assert_eq!(value["role"], "function");
+        assert_eq!(value["name"], "get_weather");
+        assert_eq!(value["content"], "72");
+        assert!(value.get("tool_call_id").is_none());
+    }
+
+    #[test]
+    fn chat_completion_delta_fragments() {
+        // A later function call fragment has no name...
+        let delta: ChatCompletionDelta =
+            serde_json::from_str(r#"{"function_call": {"arguments": "{\"lo"}}"#).unwrap();
+        assert_eq!(
+            delta,
+            ChatCompletionDelta {
+                function_call: Some(FunctionCallDelta {
+                    name: None,
+                    arguments: Some("{\"lo".to_string()),
+                }),
+                ..Default::default()
+            }
+        );
+
+        // Tool call fragments are keyed by index...
+        let delta: ChatCompletionDelta = serde_json::from_str(
+            r#"{"tool_calls": [{"index": 1, "id": "call_1", "type": "function", "function": {"name": "f", "arguments": ""}}]}"#,
+        )
+        .unwrap();
+        let tool_calls = delta.tool_calls.unwrap();
+        assert_eq!(tool_calls[0].index, 1);
+        assert_eq!(tool_calls[0].id.as_deref(), Some("call_1"));
+        assert_eq!(tool_calls[0].type_.as_deref(), Some("function"));
+    }
+
+    #[test]
+    fn api_error_envelope_from_json() {
+        let body = r#"{
+            "error": {
+                "message": "You exceeded your current quota, please check your plan and billing details.",
+                "type": "insufficient_quota",
+                "param": null,
+                "code": "insufficient_quota"
+            }
+        }"#;
+        let envelope: ApiErrorEnvelope = serde_json::from_str(body).unwrap();
+        assert_eq!(
+            envelope.error,
+            ApiError {
+                message:
+                    "You exceeded your current quota, please check your plan and billing details."
+                        .to_string(),
+                type_: Some("insufficient_quota".to_string()),
+                param: None,
+                code: Some("insufficient_quota".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn created_accepts_numbers_and_strings() {
+        // Define cases to test...
+        let cases = vec![
+            (r#"1677652288"#, Some(1677652288)),
+            (r#""1677652288""#, Some(1677652288)),
+            (r#"" 42 ""#, Some(42)),
+            (r#""yesterday""#, None),
+            (r#"-1"#, None),
+            (r#"null"#, None),
+        ];
+
+        // Iterate over cases and test...
+        for (i, (created, expected)) in cases.into_iter().enumerate() {
+            let body = format!(
+                r#"{{"id": "gpt-4", "object": "model", "created": {}, "owned_by": "openai"}}"#,
+                created
+            );
+            let model = serde_json::from_str::<ModelObject>(&body);
+            match expected {
+                Some(expected) => assert_eq!(
+                    model.expect("expected case to parse").created,
+                    expected,
+                    "case {} didn't match",
+                    i
+                ),
+                None => assert!(model.is_err(), "expected case {} to be an error", i),
+            }
+        }
+
+        // Check the other types with a `created` field...
+        let chunk = ChatCompletionChunk::from_chunk(
+            r#"data: {"id": "", "object": "", "created": "7", "model": "", "choices": []}"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(chunk.created, 7);
+        let obj: ChatCompletionObject = serde_json::from_str(
+            r#"{"id": "", "object": "", "created": "7", "model": "", "choices": [],
+                "usage": {"prompt_tokens": 0, "completion_tokens": 0, "total_tokens": 0}}"#,
+        )
+        .unwrap();
+        assert_eq!(obj.created, 7);
+    }
+
+    #[test]
+    fn embedding_input_serialization() {
+        // Define cases to test...
+        let cases = vec![
+            (EmbeddingInput::String("hi".to_string()), r#""hi""#),
+            (
+                EmbeddingInput::StringArray(vec!["a".to_string(), "b".to_string()]),
+                r#"["a","b"]"#,
+            ),
+            (EmbeddingInput::Tokens(vec![1, 2]), r#"[1,2]"#),
+            (
+                EmbeddingInput::TokenArrays(vec![vec![1], vec![2, 3]]),
+                r#"[[1],[2,3]]"#,
+            ),
+        ];
+
+        // Iterate over cases and test both directions...
+        for (i, (input, json)) in cases.into_iter().enumerate() {
+            assert_eq!(serde_json::to_string(&input).unwrap(), json, "case {}", i);
+            let parsed: EmbeddingInput = serde_json::from_str(json).unwrap();
+            assert_eq!(parsed, input, "case {}", i);
+        }
+
+        // Unset options are omitted from the request...
+        let req = CreateEmbeddingRequest {
+            model: "text-embedding-3-small".to_string(),
+            input: EmbeddingInput::String("hi".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&req).unwrap(),
+            serde_json::json!({"model": "text-embedding-3-small", "input": "hi"})
+        );
+    }
+
+    #[test]
+    fn create_embedding_response_from_json() {
+        let body = r#"{
+            "object": "list",
+            "data": [
+                {"object": "embedding", "embedding": [0.0023064255, -0.009327292], "index": 0}
+            ],
+            "model": "text-embedding-3-small",
+            "usage": {"prompt_tokens": 8, "total_tokens": 8}
+        }"#;
+        let res: CreateEmbeddingResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(res.data.len(), 1);
+        assert_eq!(res.data[0].embedding, vec![0.0023064255, -0.009327292]);
+        assert_eq!(res.usage.total_tokens, 8);
+    }
+
+    #[test]
+    fn embedding_data_decodes_base64() {
+        // Define cases to test...
+        let cases = vec![
+            (r#""AACAPwAAIMA=""#, Some(vec![1.0, -2.5])),
+            (r#""""#, Some(vec![])),
+            (r#""AACA""#, None),
+            (r#""not base64!""#, None),
+        ];
+
+        // Iterate over cases and test...
+        for (embedding, expected) in cases {
+            let json = format!(
+                r#"{{"object": "embedding", "embedding": {}, "index": 0}}"#,
+                embedding
+            );
+            let res = serde_json::from_str::<EmbeddingData>(&json).ok();
+            assert_eq!(res.map(|data| data.embedding), expected, "{}", embedding);
+        }
+    }
+
+    #[test]
+    fn create_image_request_serialization() {
+        let req = CreateImageRequest {
+            prompt: "A cute baby sea otter".to_string(),
+            model: Some("dall-e-3".to_string()),
+            size: Some(ImageSize::Size1792x1024),
+            quality: Some(ImageQuality::Hd),
+            response_format: Some(ImageResponseFormat::B64Json),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&req).unwrap(),
+            serde_json::json!({
+                "prompt": "A cute baby sea otter",
+                "model": "dall-e-3",
+                "size": "1792x1024",
+                "quality": "hd",
+                "response_format": "b64_json",
+            })
+        );
+    }
+
+    #[test]
+    fn image_response_from_json() {
+        let body = r#"{
+            "created": 1589478378,
+            "data": [
+                {"url": "https://example.com/a.png", "revised_prompt": "A sea otter"},
+                {"b64_json": "aGVsbG8="}
+            ]
+        }"#;
+        let res: ImageResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(res.created, 1589478378);
+        assert_eq!(
+            res.data[0].url.as_deref(),
+            Some("https://example.com/a.png")
+        );
+        assert_eq!(res.data[0].revised_prompt.as_deref(), Some("A sea otter"));
+        assert_eq!(res.data[1].b64_json.as_deref(), Some("aGVsbG8="));
+    }
+
+    #[test]
+    fn moderation_response_from_json() {
+        let body = r#"{
+            "id": "modr-XXXXX",
+            "model": "text-moderation-007",
+            "results": [
+                {
+                    "flagged": true,
+                    "categories": {"hate": false, "violence": true, "some-new-category": false},
+                    "category_scores": {"hate": 0.01, "violence": 0.97, "some-new-category": 0.0}
+                }
+            ]
+        }"#;
+        let res: ModerationResponse = serde_json::from_str(body).unwrap();
+        let result = &res.results[0];
+        assert!(result.flagged);
+        assert_eq!(result.categories.get("violence"), Some(&true));
+        assert_eq!(result.categories.get("some-new-category"), Some(&false));
+        assert_eq!(result.category_scores.get("violence"), Some(&0.97));
+
+        // Both input forms serialize as expected...
+        let req = ModerationRequest {
+            input: ModerationInput::StringArray(vec!["a".to_string(), "b".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&req).unwrap(),
+            serde_json::json!({"input": ["a", "b"]})
+        );
+    }
+
+    #[test]
+    fn speech_request_serialization() {
+        let req = SpeechRequest {
+            model: "tts-1".to_string(),
+            input: "Hello!".to_string(),
+            voice: Voice::Shimmer,
+            response_format: Some(SpeechResponseFormat::Opus),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&req).unwrap(),
+            serde_json::json!({
+                "model": "tts-1",
+                "input": "Hello!",
+                "voice": "shimmer",
+                "response_format": "opus",
+            })
+        );
+    }
+
+    #[test]
+    fn chat_completion_request_skips_empty_functions() {
+        // No functions, no key...
+        let req = ChatCompletionRequest {
+            model: "gpt-3.5-turbo".to_string(),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&req).unwrap();
+        assert!(value.get("functions").is_none());
+
+        // ...and it can still be read back...
+        let parsed: ChatCompletionRequest = serde_json::from_value(value).unwrap();
+        assert!(parsed.functions.is_empty());
+
+        // With functions, the key is present...
+        let req = ChatCompletionRequest {
+            functions: vec![Function {
+                name: "get_weather".to_string(),
+                ..Default::default()
+            }],
+            ..req
+        };
+        let value = serde_json::to_value(&req).unwrap();
+        assert_eq!(value["functions"][0]["name"], "get_weather");
+    }
+
+    #[test]
+    fn role_serialization() {
+        // Define cases to test...
+        let cases = vec![
+            (Role::System, "system"),
+            (Role::User, "user"),
+            (Role::Assistant, "assistant"),
+            (Role::Function, "function"),
+            (Role::Tool, "tool"),
+        ];
+
+        // Iterate over cases and test both directions...
+        for (role, name) in cases {
+            let json = format!("\"{}\"", name);
+            assert_eq!(serde_json::to_string(&role).unwrap(), json);
+            assert_eq!(serde_json::from_str::<Role>(&json).unwrap(), role);
+        }
+
+        // Unknown roles still parse...
+        let role: Role = serde_json::from_str("\"developer\"").unwrap();
+        assert_eq!(role, Role::Unknown);
+    }
+
+    #[test]
+    fn finish_reason_deserialization() {
+        // Define cases to test...
+        let cases = vec![
+            ("\"stop\"", FinishReason::Stop),
+            ("\"length\"", FinishReason::Length),
+            ("\"function_call\"", FinishReason::FunctionCall),
+            ("\"tool_calls\"", FinishReason::ToolCalls),
+            ("\"content_filter\"", FinishReason::ContentFilter),
+            ("\"something_new\"", FinishReason::Unknown),
+        ];
+
+        // Iterate over cases and test...
+        for (json, expected) in cases {
+            let reason: FinishReason = serde_json::from_str(json).unwrap();
+            assert_eq!(reason, expected, "failed to parse {}", json);
+        }
+    }
+
+    #[test]
+    fn chat_completion_message_name_round_trips() {
+        // Define cases to test...
+        let cases = vec![
+            ChatCompletionMessage::function_result("get_weather", "72"),
+            ChatCompletionMessage {
+                role: Role::User,
+                content: Some("Hi!".into()),
+                name: Some("alice".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        // Iterate over cases and test...
+        for msg in cases {
+            let json = serde_json::to_string(&msg).unwrap();
+            assert!(json.contains("\"name\":"), "name missing from {}", json);
+            let parsed: ChatCompletionMessage = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, msg);
+        }
+    }
+
+    #[test]
+    fn chat_completion_request_tools() {
+        let req = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            tools: Some(vec![Tool::function(Function {
+                name: "get_weather".to_string(),
+                description: None,
+                parameters: serde_json::json!({"type": "object"}),
+            })]),
+            tool_choice: Some(ToolChoice::function("get_weather")),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&req).unwrap();
+        assert_eq!(value["tools"][0]["type"], "function");
+        assert_eq!(value["tools"][0]["function"]["name"], "get_weather");
+        assert_eq!(
+            value["tool_choice"],
+            serde_json::json!({"type": "function", "function": {"name": "get_weather"}})
+        );
+
+        // Check the modes serialize as plain strings...
+        let cases = vec![
+            (ToolChoiceMode::None, "\"none\""),
+            (ToolChoiceMode::Auto, "\"auto\""),
+            (ToolChoiceMode::Required, "\"required\""),
+        ];
+        for (mode, expected) in cases {
+            let json = serde_json::to_string(&ToolChoice::Mode(mode)).unwrap();
+            assert_eq!(json, expected);
+        }
+    }
+
+    #[test]
+    fn chat_completion_message_tool_calls() {
+        let msg: ChatCompletionMessage = serde_json::from_str(
+            r#"{
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [
+                    {
+                        "id": "call_a",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"loc\": \"LA\"}"}
+                    },
+                    {
+                        "id": "call_b",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"loc\": \"SF\"}"}
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let calls = msg.tool_calls.expect("expected tool calls");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_a");
+        assert_eq!(calls[1].function.arguments, "{\"loc\": \"SF\"}");
+    }
+
+    #[test]
+    fn response_format_serialization() {
+        // Define cases to test...
+        let cases = vec![
+            (ResponseFormat::Text, serde_json::json!({"type": "text"})),
+            (
+                ResponseFormat::JsonObject,
+                serde_json::json!({"type": "json_object"}),
+            ),
+            (
+                ResponseFormat::JsonSchema {
+                    json_schema: JsonSchema {
+                        name: "weather".to_string(),
+                        schema: Some(serde_json::json!({"type": "object"})),
+                        strict: Some(true),
+                        ..Default::default()
+                    },
+                },
+                serde_json::json!({
+                    "type": "json_schema",
+                    "json_schema": {
+                        "name": "weather",
+                        "schema": {"type": "object"},
+                        "strict": true,
+                    },
+                }),
+            ),
+        ];
+
+        // Iterate over cases and test...
+        for (format, expected) in cases {
+            assert_eq!(serde_json::to_value(&format).unwrap(), expected);
+            let parsed: ResponseFormat = serde_json::from_value(expected).unwrap();
+            assert_eq!(parsed, format);
+        }
+    }
+
+    #[test]
+    fn chat_completion_object_system_fingerprint() {
+        // Define cases to test...
+        let cases = vec![
+            (
+                r#","system_fingerprint":"fp_44709d6fcb""#,
+                Some("fp_44709d6fcb"),
+            ),
+            ("", None),
+        ];
+
+        // Iterate over cases and test...
+        for (field, expected) in cases {
+            let json = format!(
+                r#"{{"id":"chatcmpl-123","object":"chat.completion","created":1677652288,"model":"gpt-4o","choices":[],"usage":{{"prompt_tokens":1,"completion_tokens":1,"total_tokens":2}}{}}}"#,
+                field
+            );
+            let res: ChatCompletionObject = serde_json::from_str(&json).unwrap();
+            assert_eq!(res.system_fingerprint.as_deref(), expected);
+        }
+    }
+
+    #[test]
+    fn chat_completion_choice_logprobs() {
+        let choice: ChatCompletionChoice = serde_json::from_str(
+            r#"{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hi"},
+                "finish_reason": "stop",
+                "logprobs": {
+                    "content": [
+                        {
+                            "token": "Hi",
+                            "logprob": -0.1,
+                            "bytes": [72, 105],
+                            "top_logprobs": [
+                                {"token": "Hi", "logprob": -0.1, "bytes": [72, 105]},
+                                {"token": "Hey", "logprob": -2.5, "bytes": null}
+                            ]
+                        }
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+        let content = choice.logprobs.unwrap().content.unwrap();
+        assert_eq!(content.len(), 1);
+        assert_eq!(content[0].token, "Hi");
+        assert_eq!(content[0].bytes, Some(vec![72, 105]));
+        assert_eq!(content[0].top_logprobs.len(), 2);
+        assert_eq!(content[0].top_logprobs[1].logprob, -2.5);
+        assert_eq!(content[0].top_logprobs[1].bytes, None);
+
+        // Without logprobs, the field is absent...
+        let choice: ChatCompletionChoice = serde_json::from_str(
+            r#"{"index": 0, "message": {"role": "assistant", "content": "Hi"}, "finish_reason": "stop"}"#,
+        )
+        .unwrap();
+        assert_eq!(choice.logprobs, None);
+    }
+
+    #[test]
+    fn message_content_forms() {
+        // A plain string still parses as text...
+        let msg: ChatCompletionMessage =
+            serde_json::from_str(r#"{"role": "assistant", "content": "Hello!"}"#).unwrap();
+        assert_eq!(
+            msg.content,
+            Some(MessageContent::Text("Hello!".to_string()))
+        );
+
+        // An array parses as parts...
+        let msg: ChatCompletionMessage = serde_json::from_str(
+            r#"{
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "What's this?"},
+                    {"type": "image_url", "image_url": {"url": "https://example.com/a.png", "detail": "low"}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            msg.content,
+            Some(MessageContent::Parts(vec![
+                ContentPart::text("What's this?"),
+                ContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url: "https://example.com/a.png".to_string(),
+                        detail: Some(ImageDetail::Low),
+                    },
+                },
+            ]))
+        );
+
+        // Parts without a detail leave it out...
+        let json =
+            serde_json::to_value(ContentPart::image_url("https://example.com/a.png")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "image_url", "image_url": {"url": "https://example.com/a.png"}})
+        );
+    }
+
+    #[test]
+    fn completion_request_and_response() {
+        // Check both prompt forms serialize...
+        let req = CompletionRequest {
+            model: "gpt-3.5-turbo-instruct".to_string(),
+            prompt: CompletionPrompt::StringArray(vec!["a".to_string(), "b".to_string()]),
+            max_tokens: Some(5),
+            stop: Some(StopToken::SingleToken("\n".to_string())),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&req).unwrap(),
+            serde_json::json!({
+                "model": "gpt-3.5-turbo-instruct",
+                "prompt": ["a", "b"],
+                "max_tokens": 5,
+                "stop": "\n",
+            })
+        );
+        let prompt = serde_json::to_value(CompletionPrompt::String("a".to_string())).unwrap();
+        assert_eq!(prompt, serde_json::json!("a"));
+
+        // Check the response parses...
+        let res: CompletionResponse = serde_json::from_str(
+            r#"{
+                "id": "cmpl-123",
+                "object": "text_completion",
+                "created": 1589478378,
+                "model": "gpt-3.5-turbo-instruct",
+                "choices": [
+                    {
+                        "text": " world",
+                        "index": 0,
+                        "logprobs": {
+                            "tokens": [" world"],
+                            "token_logprobs": [-0.5],
+                            "top_logprobs": [{" world": -0.5}],
+                            "text_offset": [5]
+                        },
+                        "finish_reason": "length"
+                    }
+                ],
+                "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(res.choices[0].text, " world");
+        assert_eq!(res.choices[0].finish_reason, FinishReason::Length);
+        let logprobs = res.choices[0].logprobs.as_ref().unwrap();
+        assert_eq!(logprobs.token_logprobs, vec![Some(-0.5)]);
+        assert_eq!(res.usage.total_tokens, 2);
+    }
+
+    #[test]
+    fn chat_completion_request_validate() {
+        let base = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            ..Default::default()
+        };
+
+        // Define cases to test...
+        let cases = vec![
+            (base.clone(), None),
+            (
+                ChatCompletionRequest {
+                    temperature: Some(2.0),
+                    top_p: Some(0.0),
+                    n: Some(1),
+                    presence_penalty: Some(-2.0),
+                    frequency_penalty: Some(2.0),
+                    ..base.clone()
+                },
+                None,
+            ),
+            (
+                ChatCompletionRequest {
+                    temperature: Some(-0.1),
+                    ..base.clone()
+                },
+                Some("temperature"),
+            ),
+            (
+                ChatCompletionRequest {
+                    top_p: Some(1.5),
+                    ..base.clone()
+                },
+                Some("top_p"),
+            ),
+            (
+                ChatCompletionRequest {
+                    n: Some(0),
+                    ..base.clone()
+                },
+                Some("n must"),
+            ),
+            (
+                ChatCompletionRequest {
+                    presence_penalty: Some(3.0),
+                    ..base.clone()
+                },
+                Some("presence_penalty"),
+            ),
+            (
+                ChatCompletionRequest {
+                    frequency_penalty: Some(-2.5),
+                    ..base.clone()
+                },
+                Some("frequency_penalty"),
+            ),
+            (
+                ChatCompletionRequest {
+                    temperature: Some(f64::NAN),
+                    ..base.clone()
+                },
+                Some("temperature"),
+            ),
+            (
+                ChatCompletionRequest {
+                    stop: Some(StopToken::MultipleTokens(vec!["a".to_string(); 5])),
+                    ..base.clone()
+                },
+                Some("stop"),
             ),
         ];
 
         // Iterate over cases and test...
-        for (i, (chunk, res)) in cases.into_iter().enumerate() {
-            let data = ChatCompletionChunk::from_chunk(chunk);
-            match res {
-                Res::IsErr => assert!(data.is_err(), "expected case {} to be an error", i),
-                Res::IsDone => {
-                    assert!(
-                        data.is_ok(),
-                        "expected case {} to not be an error. Err: {}",
-                        i,
-                        data.unwrap_err()
-                    );
-                    assert!(matches!(data, Ok(None)), "expected case {} to be [DONE]", i)
+        for (i, (req, expected)) in cases.into_iter().enumerate() {
+            match (req.validate(), expected) {
+                (Ok(()), None) => {}
+                (Err(Error::Validation(msg)), Some(field)) => {
+                    assert!(msg.contains(field), "case {}: {:?}", i, msg)
                 }
-                Res::IsChunk(chunk) => {
-                    assert!(
-                        data.is_ok(),
-                        "expected case {} to not be an error. Err: {}",
-                        i,
-                        data.unwrap_err(),
-                    );
-                    let data = data.unwrap();
-                    assert!(data.is_some(), "expected case {} to not be none", i);
-                    assert_eq!(data, Some(chunk), "expected case {} to be a chunk", i);
+                (res, expected) => panic!("case {}: got {:?}, expected {:?}", i, res, expected),
+            }
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn created_at_converts_timestamps() {
+        let expected = chrono::DateTime::parse_from_rfc3339("2023-03-01T06:31:28Z").unwrap();
+        let res = ChatCompletionObject {
+            created: 1677652288,
+            ..Default::default()
+        };
+        assert_eq!(res.created_at(), expected);
+        let chunk = ChatCompletionChunk {
+            created: 1677652288,
+            ..Default::default()
+        };
+        assert_eq!(chunk.created_at(), expected);
+        let model = ModelObject {
+            created: 1677652288,
+            ..Default::default()
+        };
+        assert_eq!(model.created_at(), expected);
+
+        // Out of range timestamps saturate...
+        let model = ModelObject {
+            created: u64::MAX,
+            ..Default::default()
+        };
+        assert_eq!(model.created_at(), chrono::DateTime::<chrono::Utc>::MAX_UTC);
+    }
+
+    #[test]
+    fn file_object_deserialization() {
+        let data = r#"{
+            "id": "file-abc123",
+            "object": "file",
+            "bytes": 120000,
+            "created_at": 1677610602,
+            "filename": "mydata.jsonl",
+            "purpose": "fine-tune"
+        }"#;
+        let file: FileObject = serde_json::from_str(data).unwrap();
+        assert_eq!(file.id, "file-abc123");
+        assert_eq!(file.bytes, 120000);
+        assert_eq!(file.created_at, 1677610602);
+        assert_eq!(file.purpose, "fine-tune");
+        assert_eq!(file.status, None);
+    }
+
+    #[test]
+    fn create_fine_tuning_job_request_serialization() {
+        let req = CreateFineTuningJobRequest {
+            model: "gpt-4o-mini".to_string(),
+            training_file: "file-abc123".to_string(),
+            hyperparameters: Some(Hyperparameters {
+                n_epochs: Some(Hyperparameter::Value(3)),
+                learning_rate_multiplier: Some(Hyperparameter::default()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&req).unwrap(),
+            serde_json::json!({
+                "model": "gpt-4o-mini",
+                "training_file": "file-abc123",
+                "hyperparameters": {
+                    "n_epochs": 3,
+                    "learning_rate_multiplier": "auto"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn fine_tuning_job_deserialization() {
+        let data = r#"{
+            "object": "fine_tuning.job",
+            "id": "ftjob-abc123",
+            "model": "gpt-4o-mini-2024-07-18",
+            "created_at": 1721764800,
+            "finished_at": 1721767800,
+            "fine_tuned_model": "ft:gpt-4o-mini:my-org:custom_suffix:id",
+            "organization_id": "org-123",
+            "result_files": ["file-abc123"],
+            "status": "succeeded",
+            "validation_file": null,
+            "training_file": "file-abc123",
+            "hyperparameters": {
+                "n_epochs": 4,
+                "batch_size": 1,
+                "learning_rate_multiplier": "auto"
+            },
+            "trained_tokens": 5768,
+            "error": null,
+            "seed": 42
+        }"#;
+        let job: FineTuningJob = serde_json::from_str(data).unwrap();
+        assert_eq!(job.status, FineTuningJobStatus::Succeeded);
+        assert_eq!(job.finished_at, Some(1721767800));
+        assert_eq!(job.trained_tokens, Some(5768));
+        assert_eq!(job.error, None);
+        assert_eq!(job.hyperparameters.n_epochs, Some(Hyperparameter::Value(4)));
+        assert_eq!(
+            job.hyperparameters.learning_rate_multiplier,
+            Some(Hyperparameter::Mode(HyperparameterMode::Auto))
+        );
+
+        // Failed jobs say why...
+        let data = r#"{
+            "object": "fine_tuning.job",
+            "id": "ftjob-abc123",
+            "model": "gpt-4o-mini",
+            "created_at": 1721764800,
+            "status": "failed",
+            "training_file": "file-abc123",
+            "error": {"code": "invalid_training_file", "message": "Bad file", "param": "training_file"}
+        }"#;
+        let job: FineTuningJob = serde_json::from_str(data).unwrap();
+        assert_eq!(job.status, FineTuningJobStatus::Failed);
+        assert_eq!(job.error.unwrap().code, "invalid_training_file");
+    }
+
+    #[test]
+    fn first_choice_accessors() {
+        let mut res = ChatCompletionObject::default();
+        assert_eq!(res.first_message(), None);
+        assert_eq!(res.first_content(), None);
+
+        // The first choice's text is returned...
+        res.choices = vec![
+            ChatCompletionChoice {
+                message: ChatCompletionMessage {
+                    role: Role::Assistant,
+                    content: Some("Hello!".into()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ChatCompletionChoice {
+                index: 1,
+                message: ChatCompletionMessage {
+                    content: Some("Hi!".into()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ];
+        assert_eq!(res.first_message().unwrap().role, Role::Assistant);
+        assert_eq!(res.first_content(), Some("Hello!"));
+
+        // ...and the first chunk's delta...
+        let chunk = ChatCompletionChunk {
+            id: "chatcmpl-123".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: 1677652288,
+            model: "gpt-4o".to_string(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: 0,
+                delta: ChatCompletionDelta {
+                    content: Some("Hel".to_string()),
+                    ..Default::default()
+                },
+                finish_reason: None,
+            }],
+            usage: None,
+        };
+        assert_eq!(chunk.first_delta().unwrap().content.as_deref(), Some("Hel"));
+    }
+
+    #[test]
+    fn chat_completion_chunk_parse_event_keep_alive() {
+        // Define cases to test...
+        let cases = vec![": keep-alive", ":", "", "  \n", "data: [DONE]"];
+
+        // Iterate over cases and test...
+        for chunk in cases {
+            let event = ChatCompletionChunk::parse_event(chunk).unwrap();
+            match chunk {
+                "data: [DONE]" => assert_eq!(event, ChunkEvent::Done),
+                _ => assert_eq!(event, ChunkEvent::KeepAlive, "{:?}", chunk),
+            }
+        }
+
+        // Anything else without a `data:` prefix is still an error...
+        assert!(ChatCompletionChunk::parse_event("event: ping").is_err());
+    }
+
+    #[test]
+    fn from_chunk_wraps_parse_event() {
+        let data = r#"data: {"id": "a", "object": "", "created": 1, "model": "", "choices": []}"#;
+
+        // Define cases to test...
+        let cases = vec![data, "data: [DONE]", ": keep-alive", "", "data: {"];
+
+        // Iterate over cases and test...
+        for chunk in cases {
+            match (
+                ChatCompletionChunk::parse_event(chunk),
+                ChatCompletionChunk::from_chunk(chunk),
+            ) {
+                (Ok(ChunkEvent::Chunk(a)), Ok(Some(b))) => assert_eq!(a, b),
+                (Ok(ChunkEvent::Done), Ok(None)) => {}
+                (Ok(ChunkEvent::KeepAlive), Err(Error::InvalidChunk(_))) => {}
+                (Err(_), Err(_)) => {}
+                (event, res) => panic!("{:?}: {:?} vs {:?}", chunk, event, res),
+            }
+        }
+    }
+
+    #[test]
+    fn stop_token_new() {
+        let tokens = |n: usize| (0..n).map(|i| i.to_string()).collect::<Vec<_>>();
+
+        // Define cases to test...
+        let cases = vec![
+            (0, None),
+            (1, Some(StopToken::SingleToken("0".to_string()))),
+            (2, Some(StopToken::MultipleTokens(tokens(2)))),
+            (4, Some(StopToken::MultipleTokens(tokens(4)))),
+            (5, None),
+        ];
+
+        // Iterate over cases and test...
+        for (n, expected) in cases {
+            match (StopToken::new(tokens(n)), expected) {
+                (Ok(stop), Some(expected)) => assert_eq!(stop, expected),
+                (Err(Error::Validation(_)), None) => {}
+                (res, expected) => panic!("{}: {:?} vs {:?}", n, res, expected),
+            }
+        }
+
+        // A single sequence is sent as a plain string...
+        let stop = StopToken::new(vec!["\n".to_string()]).unwrap();
+        assert_eq!(serde_json::to_value(stop).unwrap(), serde_json::json!("\n"));
+    }
+
+    #[test]
+    fn stream_options_and_usage_chunk() {
+        let req = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            stream: Some(true),
+            stream_options: Some(StreamOptions {
+                include_usage: Some(true),
+            }),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&req).unwrap();
+        assert_eq!(
+            value["stream_options"],
+            serde_json::json!({"include_usage": true})
+        );
+
+        // The final chunk carries the usage, and no choices...
+        let chunk = ChatCompletionChunk::from_chunk(
+            r#"data: {"id": "chatcmpl-123", "object": "chat.completion.chunk", "created": 1, "model": "gpt-4o", "choices": [], "usage": {"prompt_tokens": 9, "completion_tokens": 12, "total_tokens": 21}}"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(chunk.choices.is_empty());
+        assert_eq!(chunk.usage.unwrap().total_tokens, 21);
+    }
+
+    #[test]
+    fn assistant_round_trip() {
+        let data = r#"{
+            "id": "asst_abc123",
+            "object": "assistant",
+            "created_at": 1698984975,
+            "name": "Math Tutor",
+            "description": null,
+            "model": "gpt-4o",
+            "instructions": "You are a personal math tutor.",
+            "tools": [
+                {"type": "code_interpreter"},
+                {"type": "function", "function": {"name": "add", "description": null, "parameters": {}}}
+            ],
+            "metadata": {"team": "edu"},
+            "top_p": 1.0,
+            "temperature": 1.0,
+            "response_format": "auto"
+        }"#;
+        let assistant: Assistant = serde_json::from_str(data).unwrap();
+        assert_eq!(assistant.name.as_deref(), Some("Math Tutor"));
+        assert_eq!(assistant.description, None);
+        assert_eq!(assistant.tools[0], AssistantTool::CodeInterpreter);
+        assert!(
+            matches!(&assistant.tools[1], AssistantTool::Function { function } if function.name == "add")
+        );
+        assert_eq!(assistant.metadata["team"], "edu");
+
+        // Unset fields aren't sent when creating one...
+        let req = CreateAssistantRequest {
+            model: "gpt-4o".to_string(),
+            tools: Some(vec![AssistantTool::FileSearch]),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&req).unwrap(),
+            serde_json::json!({"model": "gpt-4o", "tools": [{"type": "file_search"}]})
+        );
+    }
+
+    #[test]
+    fn thread_message_round_trip() {
+        let data = r#"{
+            "id": "msg_abc123",
+            "object": "thread.message",
+            "created_at": 1699017614,
+            "assistant_id": null,
+            "thread_id": "thread_abc123",
+            "run_id": null,
+            "role": "user",
+            "content": [
+                {"type": "text", "text": {"value": "How does AI work? ", "annotations": []}},
+                {"type": "image_file", "image_file": {"file_id": "file-abc123"}},
+                {"type": "text", "text": {"value": "Explain it simply.", "annotations": []}},
+                {"type": "refusal", "refusal": "No."}
+            ],
+            "attachments": [],
+            "metadata": {}
+        }"#;
+        let msg: ThreadMessage = serde_json::from_str(data).unwrap();
+        assert_eq!(msg.role, Role::User);
+        assert_eq!(msg.content.len(), 4);
+        assert_eq!(msg.content[3], ThreadMessageContent::Unknown);
+        assert_eq!(msg.text(), "How does AI work? Explain it simply.");
+
+        // Messages are created with the same content as chat messages...
+        let req = CreateMessageRequest::user(MessageContent::Parts(vec![
+            ContentPart::text("What's this?"),
+            ContentPart::image_url("https://example.com/cat.png"),
+        ]));
+        assert_eq!(
+            serde_json::to_value(&req).unwrap(),
+            serde_json::json!({
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "What's this?"},
+                    {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn run_deserialization() {
+        let data = r#"{
+            "id": "run_abc123",
+            "object": "thread.run",
+            "created_at": 1699063290,
+            "assistant_id": "asst_abc123",
+            "thread_id": "thread_abc123",
+            "status": "requires_action",
+            "required_action": {
+                "type": "submit_tool_outputs",
+                "submit_tool_outputs": {
+                    "tool_calls": [{
+                        "id": "call_abc123",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{}"}
+                    }]
+                }
+            },
+            "last_error": null,
+            "started_at": 1699063291,
+            "expires_at": 1699063890,
+            "cancelled_at": null,
+            "failed_at": null,
+            "completed_at": null,
+            "model": "gpt-4o",
+            "instructions": null,
+            "tools": [{"type": "function", "function": {"name": "get_weather", "description": null, "parameters": {}}}],
+            "metadata": {},
+            "usage": null
+        }"#;
+        let run: Run = serde_json::from_str(data).unwrap();
+        assert_eq!(run.status, RunStatus::RequiresAction);
+        assert!(!run.status.is_terminal());
+        assert_eq!(run.started_at, Some(1699063291));
+        assert_eq!(run.completed_at, None);
+        let action = run.required_action.unwrap();
+        assert_eq!(action.submit_tool_outputs.tool_calls[0].id, "call_abc123");
+
+        // Define cases to test...
+        let cases = vec![
+            ("queued", false),
+            ("in_progress", false),
+            ("cancelling", false),
+            ("completed", true),
+            ("failed", true),
+            ("cancelled", true),
+            ("expired", true),
+            ("incomplete", true),
+            ("something_new", false),
+        ];
+
+        // Iterate over cases and test...
+        for (status, terminal) in cases {
+            let status: RunStatus = serde_json::from_value(serde_json::json!(status)).unwrap();
+            assert_eq!(status.is_terminal(), terminal, "{:?}", status);
+        }
+    }
+
+    #[test]
+    fn check_object_catches_misrouted_responses() {
+        // Define cases to test...
+        let cases = vec![
+            ("chat.completion", true),
+            ("text_completion", false),
+            ("", false),
+        ];
+
+        // Iterate over cases and test...
+        for (object, ok) in cases {
+            let res = ChatCompletionObject {
+                object: object.to_string(),
+                ..Default::default()
+            };
+            match res.check_object() {
+                Ok(()) => assert!(ok, "{:?}", object),
+                Err(Error::UnexpectedObject { expected, found }) => {
+                    assert!(!ok, "{:?}", object);
+                    assert_eq!(expected, "chat.completion");
+                    assert_eq!(found, object);
+                }
+                Err(err) => panic!("unexpected error: {}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn choice_iterators() {
+        let choice = |index: u64, content: Option<&str>| ChatCompletionChoice {
+            index,
+            message: ChatCompletionMessage {
+                role: Role::Assistant,
+                content: content.map(Into::into),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let res = ChatCompletionObject {
+            choices: vec![
+                choice(0, Some("Red")),
+                choice(1, None),
+                choice(2, Some("Blue")),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(res.messages().count(), 3);
+        assert_eq!(res.contents().collect::<Vec<_>>(), vec!["Red", "Blue"]);
+        assert_eq!(ChatCompletionObject::default().contents().count(), 0);
+    }
+
+    #[test]
+    fn validate_logit_bias() {
+        // Define cases to test...
+        let cases = vec![
+            (vec![("50256", -100.0), ("1234", 100.0)], None),
+            (vec![("50256", -100.5)], Some("logit_bias[\"50256\"]")),
+            (vec![("1234", 250.0)], Some("logit_bias[\"1234\"]")),
+            (vec![("hello", 5.0)], Some("\"hello\"")),
+            (vec![("-1", 5.0)], Some("\"-1\"")),
+        ];
+
+        // Iterate over cases and test...
+        for (logit_bias, expected) in cases {
+            let req = ChatCompletionRequest {
+                model: "gpt-4o".to_string(),
+                logit_bias: Some(
+                    logit_bias
+                        .iter()
+                        .map(|(token, bias)| (token.to_string(), *bias))
+                        .collect(),
+                ),
+                ..Default::default()
+            };
+            match (req.validate(), expected) {
+                (Ok(()), None) => {}
+                (Err(Error::Validation(msg)), Some(expected)) => {
+                    assert!(msg.contains(expected), "{}", msg)
+                }
+                (res, expected) => {
+                    panic!("{:?}: got {:?}, expected {:?}", logit_bias, res, expected)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn batch_deserialization() {
+        let data = r#"{
+            "id": "batch_abc123",
+            "object": "batch",
+            "endpoint": "/v1/chat/completions",
+            "errors": null,
+            "input_file_id": "file-abc123",
+            "completion_window": "24h",
+            "status": "completed",
+            "output_file_id": "file-cvaTdG",
+            "error_file_id": "file-HOWS94",
+            "created_at": 1711471533,
+            "in_progress_at": 1711471538,
+            "expires_at": 1711557933,
+            "finalizing_at": 1711493133,
+            "completed_at": 1711493163,
+            "failed_at": null,
+            "expired_at": null,
+            "cancelling_at": null,
+            "cancelled_at": null,
+            "request_counts": {"total": 100, "completed": 95, "failed": 5},
+            "metadata": {"batch_description": "Nightly eval job"}
+        }"#;
+        let batch: Batch = serde_json::from_str(data).unwrap();
+        assert_eq!(batch.status, BatchStatus::Completed);
+        assert_eq!(
+            batch.request_counts,
+            BatchRequestCounts {
+                total: 100,
+                completed: 95,
+                failed: 5,
+            }
+        );
+        assert_eq!(batch.output_file_id.as_deref(), Some("file-cvaTdG"));
+        assert_eq!(batch.completed_at, Some(1711493163));
+        assert_eq!(batch.failed_at, None);
+    }
+
+    #[test]
+    fn parallel_tool_calls_serialization() {
+        // Define cases to test...
+        let cases = vec![
+            (None, None),
+            (Some(false), Some(serde_json::json!(false))),
+            (Some(true), Some(serde_json::json!(true))),
+        ];
+
+        // Iterate over cases and test...
+        for (parallel_tool_calls, expected) in cases {
+            let req = ChatCompletionRequest {
+                model: "gpt-4o".to_string(),
+                parallel_tool_calls,
+                ..Default::default()
+            };
+            let value = serde_json::to_value(&req).unwrap();
+            assert_eq!(value.get("parallel_tool_calls").cloned(), expected);
+        }
+    }
+
+    #[test]
+    fn was_filtered_detects_content_filter() {
+        let data = r#"{
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1677652288,
+            "model": "gpt-4o",
+            "choices": [
+                {"index": 0, "message": {"role": "assistant", "content": "Sure"}, "finish_reason": "stop"},
+                {"index": 1, "message": {"role": "assistant", "content": ""}, "finish_reason": "content_filter"}
+            ],
+            "usage": {"prompt_tokens": 9, "completion_tokens": 1, "total_tokens": 10}
+        }"#;
+        let mut res: ChatCompletionObject = serde_json::from_str(data).unwrap();
+        assert!(res.was_filtered());
+
+        res.choices.pop();
+        assert!(!res.was_filtered());
+        assert!(!ChatCompletionObject::default().was_filtered());
+    }
+
+    #[test]
+    fn completion_logprobs_shape() {
+        // Unlike chat's boolean, the legacy logprobs is a count...
+        let req = CompletionRequest {
+            model: "gpt-3.5-turbo-instruct".to_string(),
+            prompt: CompletionPrompt::String("Hello".to_string()),
+            logprobs: Some(2),
+            echo: Some(true),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&req).unwrap();
+        assert_eq!(value["logprobs"], serde_json::json!(2));
+
+        // ...and with echo, the first prompt token has no logprobs...
+        let logprobs: CompletionLogProbs = serde_json::from_str(
+            r#"{
+                "tokens": ["Hello", " world"],
+                "token_logprobs": [null, -0.25],
+                "top_logprobs": [null, {" world": -0.25, " there": -1.5}],
+                "text_offset": [0, 5]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(logprobs.token_logprobs, vec![None, Some(-0.25)]);
+        assert_eq!(logprobs.top_logprobs[0], None);
+        assert_eq!(logprobs.top_logprobs[1].as_ref().unwrap().len(), 2);
+        assert_eq!(logprobs.text_offset, vec![0, 5]);
+    }
+
+    #[test]
+    fn user_is_omitted_and_validated() {
+        let req = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            ..Default::default()
+        };
+        assert!(serde_json::to_value(&req).unwrap().get("user").is_none());
+
+        // Define cases to test...
+        let cases = vec![
+            ("user-1234".to_string(), true),
+            ("x".repeat(MAX_USER_LENGTH), true),
+            ("x".repeat(MAX_USER_LENGTH + 1), false),
+        ];
+
+        // Iterate over cases and test...
+        for (user, ok) in cases {
+            let req = ChatCompletionRequest {
+                user: Some(user.clone()),
+                ..req.clone()
+            };
+            assert_eq!(serde_json::to_value(&req).unwrap()["user"], user);
+            assert_eq!(req.validate().is_ok(), ok, "{}", user.len());
+        }
+    }
+
+    #[test]
+    fn usage_token_details() {
+        // Define cases to test...
+        let cases = vec![
+            (
+                r#"{"prompt_tokens": 9, "completion_tokens": 12, "total_tokens": 21}"#,
+                None,
+                None,
+            ),
+            (
+                r#"{
+                    "prompt_tokens": 2006,
+                    "completion_tokens": 300,
+                    "total_tokens": 2306,
+                    "prompt_tokens_details": {"cached_tokens": 1920, "audio_tokens": 0},
+                    "completion_tokens_details": {
+                        "reasoning_tokens": 256,
+                        "audio_tokens": 0,
+                        "accepted_prediction_tokens": 0,
+                        "rejected_prediction_tokens": 0
+                    }
+                }"#,
+                Some(PromptTokensDetails {
+                    cached_tokens: 1920,
+                    audio_tokens: 0,
+                }),
+                Some(CompletionTokensDetails {
+                    reasoning_tokens: 256,
+                    audio_tokens: 0,
+                }),
+            ),
+            (
+                r#"{"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2, "prompt_tokens_details": {"cached_tokens": 0}}"#,
+                Some(PromptTokensDetails::default()),
+                None,
+            ),
+        ];
+
+        // Iterate over cases and test...
+        for (data, prompt_details, completion_details) in cases {
+            let usage: ChatCompletionUsage = serde_json::from_str(data).unwrap();
+            assert_eq!(usage.prompt_tokens_details, prompt_details, "{}", data);
+            assert_eq!(
+                usage.completion_tokens_details, completion_details,
+                "{}",
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn completion_chunk_parses_text_deltas() {
+        // Define cases to test...
+        let cases = vec![
+            (
+                r#"data: {"id": "cmpl-1", "object": "text_completion", "created": 1, "model": "m", "choices": [{"text": "Hi", "index": 0, "logprobs": null, "finish_reason": null}]}"#,
+                Some(("Hi", None)),
+            ),
+            (
+                r#"data: {"id": "cmpl-1", "object": "text_completion", "created": 1, "model": "m", "choices": [{"text": "", "index": 0, "finish_reason": "length"}]}"#,
+                Some(("", Some(FinishReason::Length))),
+            ),
+            ("data: [DONE]", None),
+        ];
+
+        // Iterate over cases and test...
+        for (chunk, expected) in cases {
+            let parsed = CompletionChunk::from_chunk(chunk).unwrap();
+            let parsed = parsed.map(|chunk| {
+                let choice = chunk.choices[0].clone();
+                (choice.text, choice.finish_reason)
+            });
+            let expected = expected.map(|(text, reason)| (text.to_string(), reason));
+            assert_eq!(parsed, expected, "{}", chunk);
+        }
+
+        // ...and keep-alive comments are skipped like in chat streams...
+        let event = CompletionChunk::parse_event(": ping").unwrap();
+        assert_eq!(event, ChunkEvent::KeepAlive);
+    }
+
+    #[test]
+    fn model_round_trips_wire_names() {
+        // Define cases to test...
+        let cases = vec![
+            (Model::Gpt4o, "gpt-4o"),
+            (Model::Gpt4oMini, "gpt-4o-mini"),
+            (Model::Gpt35Turbo, "gpt-3.5-turbo"),
+            (Model::TextEmbedding3Small, "text-embedding-3-small"),
+            (Model::Custom("gpt-4-turbp".to_string()), "gpt-4-turbp"),
+        ];
+
+        // Iterate over cases and test...
+        for (model, name) in cases {
+            assert_eq!(model.to_string(), name);
+            assert_eq!(Model::from(name), model);
+            assert_eq!(Model::from(name.to_string()), model);
+            let json = serde_json::to_string(&model).unwrap();
+            assert_eq!(json, format!("{:?}", name));
+            assert_eq!(serde_json::from_str::<Model>(&json).unwrap(), model);
+        }
+    }
+
+    #[test]
+    fn is_truncated_detects_length() {
+        let data = r#"{
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1677652288,
+            "model": "gpt-4o",
+            "choices": [
+                {"index": 0, "message": {"role": "assistant", "content": "Once upon a"}, "finish_reason": "length"}
+            ],
+            "usage": {"prompt_tokens": 9, "completion_tokens": 3, "total_tokens": 12}
+        }"#;
+        let mut res: ChatCompletionObject = serde_json::from_str(data).unwrap();
+        assert!(res.is_truncated());
+        assert!(!res.was_filtered());
+
+        // The continuation ends with the partial message...
+        let req = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![ChatCompletionMessage {
+                role: Role::User,
+                content: Some("Tell me a story.".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let next = req.continuation(&res);
+        assert_eq!(next.messages.len(), 2);
+        assert_eq!(next.messages[1].role, Role::Assistant);
+        assert_eq!(
+            next.messages[1].content.as_ref().and_then(|c| c.as_text()),
+            Some("Once upon a")
+        );
+
+        res.choices[0].finish_reason = FinishReason::Stop;
+        assert!(!res.is_truncated());
+        assert!(!ChatCompletionObject::default().is_truncated());
+    }
+
+    #[test]
+    fn check_metadata_enforces_limits() {
+        let pairs = |n: usize| -> HashMap<String, String> {
+            (0..n).map(|i| (i.to_string(), "x".to_string())).collect()
+        };
+        let pair = |key: String, value: String| HashMap::from([(key, value)]);
+
+        // Define cases to test...
+        let cases = vec![
+            (HashMap::new(), None),
+            (pairs(MAX_METADATA_PAIRS), None),
+            (pairs(MAX_METADATA_PAIRS + 1), Some("at most 16 pairs")),
+            (
+                pair("k".repeat(MAX_METADATA_KEY_LENGTH), "v".to_string()),
+                None,
+            ),
+            (
+                pair("k".repeat(MAX_METADATA_KEY_LENGTH + 1), "v".to_string()),
+                Some("keys can be at most 64"),
+            ),
+            (
+                pair("k".to_string(), "é".repeat(MAX_METADATA_VALUE_LENGTH)),
+                None,
+            ),
+            (
+                pair("k".to_string(), "v".repeat(MAX_METADATA_VALUE_LENGTH + 1)),
+                Some("values can be at most 512"),
+            ),
+        ];
+
+        // Iterate over cases and test...
+        for (i, (metadata, expected)) in cases.into_iter().enumerate() {
+            let res = CreateRunRequest::default().with_metadata(metadata.clone());
+            match (res, expected) {
+                (Ok(req), None) => assert_eq!(req.metadata, Some(metadata), "case {}", i),
+                (Err(Error::Validation(msg)), Some(expected)) => {
+                    assert!(msg.contains(expected), "case {}: {}", i, msg)
                 }
+                (res, _) => panic!("case {}: unexpected result {:?}", i, res),
             }
         }
     }
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use crate::dtypes::{ApiError, ChatCompletionObject};
+
+/// A `Result` alias where the error is an `ohairs::Error`.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The errors that can be returned by this crate.
+#[derive(Debug)]
+pub enum Error {
+    /// The request couldn't be sent, or the response
+    /// couldn't be read (e.g. a connection error or
+    /// a timeout).
+    Http(reqwest::Error),
+
+    /// The API responded with a non-success status code.
+    Api {
+        /// The HTTP status code of the response.
+        status: u16,
+
+        /// The error returned by the API.
+        ///
+        /// If the body wasn't a JSON error envelope, the
+        /// `message` is the raw body and the other fields
+        /// are `None`.
+        error: ApiError,
+    },
+
+    /// The response body couldn't be parsed as the
+    /// expected JSON type.
+    Deserialize(serde_json::Error),
+
+    /// The client's base URL, or a path joined onto it,
+    /// isn't a valid URL.
+    InvalidUrl(url::ParseError),
+
+    /// A chunk streamed from the API wasn't a valid
+    /// server-sent event.
+    InvalidChunk(String),
+
+    /// The client was configured incorrectly.
+    Config(String),
+
+    /// Data failed validation before being sent.
+    Validation(String),
+
+    /// A client was built without an API key.
+    MissingApiKey,
+
+    /// A required environment variable (named here) was
+    /// missing or empty.
+    MissingEnvVar(String),
+
+    /// Reading a response body failed (e.g. while streaming
+    /// with the blocking client).
+    Io(std::io::Error),
+
+    /// The response's `object` field didn't match the type
+    /// the endpoint returns, which usually means the request
+    /// was routed to the wrong endpoint.
+    UnexpectedObject {
+        /// The expected `object` value.
+        expected: &'static str,
+
+        /// The `object` value the response had.
+        found: String,
+    },
+
+    /// A chat completion succeeded but didn't include any
+    /// choices (e.g. because they were filtered). The
+    /// response is kept so its usage can still be read.
+    EmptyChoices(Box<ChatCompletionObject>),
+
+    /// A chat completion's first choice had no text content
+    /// (e.g. the model called a tool instead), where text
+    /// was expected. The response is kept so it can still be
+    /// read.
+    NoContent(Box<ChatCompletionObject>),
+
+    /// A polling helper (e.g. `Client::wait_for_run`) gave
+    /// up waiting.
+    Timeout(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(err) => write!(f, "HTTP request failed: {}", err),
+            Error::Api { status, error } => write!(
+                f,
+                "API returned an error (status {}): {}",
+                status, error.message
+            ),
+            Error::Deserialize(err) => write!(f, "Failed to parse response as json: {}", err),
+            Error::InvalidUrl(err) => write!(f, "Invalid URL: {}", err),
+            Error::InvalidChunk(msg) => write!(f, "Invalid stream chunk: {}", msg),
+            Error::Config(msg) => write!(f, "Invalid client configuration: {}", msg),
+            Error::Validation(msg) => write!(f, "Validation failed: {}", msg),
+            Error::MissingApiKey => write!(f, "An API key is required to build a client"),
+            Error::MissingEnvVar(name) => {
+                write!(f, "Environment variable {} is missing or empty", name)
+            }
+            Error::Io(err) => write!(f, "Failed to read response: {}", err),
+            Error::UnexpectedObject { expected, found } => write!(
+                f,
+                "Expected a response with object {:?}, got {:?}",
+                expected, found
+            ),
+            Error::EmptyChoices(res) => {
+                write!(f, "Chat completion {} returned no choices", res.id)
+            }
+            Error::NoContent(res) => {
+                write!(f, "Chat completion {} returned no text content", res.id)
+            }
+            Error::Timeout(msg) => write!(f, "Timed out: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(err) => Some(err),
+            Error::Deserialize(err) => Some(err),
+            Error::InvalidUrl(err) => Some(err),
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Http(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Deserialize(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<url::ParseError> for Error {
+    fn from(err: url::ParseError) -> Self {
+        Error::InvalidUrl(err)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn error_conversions() {
+        // JSON errors are deserialization errors...
+        let err: Error = serde_json::from_str::<u64>("{").unwrap_err().into();
+        assert!(matches!(err, Error::Deserialize(_)));
+        assert!(err.source().is_some());
+
+        // URL errors are invalid URL errors...
+        let err: Error = url::Url::parse("not a url").unwrap_err().into();
+        assert!(matches!(err, Error::InvalidUrl(_)));
+        assert!(err.to_string().starts_with("Invalid URL"));
+    }
+
+    #[test]
+    fn error_converts_into_anyhow() {
+        fn fails() -> anyhow::Result<()> {
+            Err(Error::Api {
+                status: 401,
+                error: ApiError {
+                    message: "Incorrect API key provided".to_string(),
+                    type_: Some("invalid_request_error".to_string()),
+                    ..Default::default()
+                },
+            })?;
+            Ok(())
+        }
+        let err = fails().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "API returned an error (status 401): Incorrect API key provided"
+        );
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::Api { status: 401, .. })
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+use serde::Serialize;
+
+use crate::dtypes::{ChatCompletionMessage, FunctionCall, MessageContent, Role, ToolCall};
+use crate::{Error, Result};
+
+/// A single message in a fine-tuning example, containing
+/// only the fields the fine-tuning endpoint accepts.
+#[derive(Serialize)]
+struct FineTuneMessage<'a> {
+    role: Role,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<&'a MessageContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function_call: Option<&'a FunctionCall>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<&'a [ToolCall]>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<&'a str>,
+}
+
+impl<'a> From<&'a ChatCompletionMessage> for FineTuneMessage<'a> {
+    fn from(msg: &'a ChatCompletionMessage) -> Self {
+        Self {
+            role: msg.role,
+            content: msg.content.as_ref(),
+            name: msg.name.as_deref(),
+            function_call: msg.function_call.as_ref(),
+            tool_calls: msg.tool_calls.as_deref(),
+            tool_call_id: msg.tool_call_id.as_deref(),
+        }
+    }
+}
+
+/// A single fine-tuning example (i.e. one line of the file).
+#[derive(Serialize)]
+struct FineTuneExample<'a> {
+    messages: Vec<FineTuneMessage<'a>>,
+}
+
+/// Converts conversations into the JSONL format expected by
+/// the fine-tuning endpoint, with one `{"messages": [...]}`
+/// object per line.
+///
+/// Fields fine-tuning doesn't accept (and unset fields) are
+/// dropped. Returns an error if any conversation doesn't
+/// contain at least one `assistant` message, since it
+/// wouldn't have anything to train on.
+///
+/// # Example
+///
+/// ```
+/// use ohairs::dtypes::{ChatCompletionMessage, Role};
+/// use ohairs::fine_tune::to_fine_tune_jsonl;
+///
+/// let conversation = vec![
+///     ChatCompletionMessage {
+///         role: Role::User,
+///         content: Some("Hi!".into()),
+///         ..Default::default()
+///     },
+///     ChatCompletionMessage {
+///         role: Role::Assistant,
+///         content: Some("Hello!".into()),
+///         ..Default::default()
+///     },
+/// ];
+/// let jsonl = to_fine_tune_jsonl(&[conversation]).unwrap();
+/// assert_eq!(
+///     jsonl,
+///     "{\"messages\":[{\"role\":\"user\",\"content\":\"Hi!\"},{\"role\":\"assistant\",\"content\":\"Hello!\"}]}\n"
+/// );
+/// ```
+pub fn to_fine_tune_jsonl(conversations: &[Vec<ChatCompletionMessage>]) -> Result<String> {
+    let mut jsonl = String::new();
+    for (i, conversation) in conversations.iter().enumerate() {
+        // Check there's something to train on...
+        if !conversation.iter().any(|msg| msg.role == Role::Assistant) {
+            return Err(Error::Validation(format!(
+                "Conversation {} has no assistant messages",
+                i
+            )));
+        }
+
+        // Write the example as a single line...
+        let example = FineTuneExample {
+            messages: conversation.iter().map(FineTuneMessage::from).collect(),
+        };
+        jsonl.push_str(&serde_json::to_string(&example)?);
+        jsonl.push('\n');
+    }
+    Ok(jsonl)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn message(role: Role, content: &str) -> ChatCompletionMessage {
+        ChatCompletionMessage {
+            role,
+            content: Some(content.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fine_tune_jsonl_one_line_per_conversation() {
+        let conversations = vec![
+            vec![
+                message(Role::System, "Be terse."),
+                message(Role::Assistant, "Ok."),
+            ],
+            vec![
+                message(Role::User, "Weather?"),
+                ChatCompletionMessage {
+                    role: Role::Assistant,
+                    function_call: Some(FunctionCall {
+                        name: "get_weather".to_string(),
+                        arguments: "{}".to_string(),
+                    }),
+                    ..Default::default()
+                },
+                ChatCompletionMessage::function_result("get_weather", "72"),
+            ],
+        ];
+        let jsonl = to_fine_tune_jsonl(&conversations).unwrap();
+
+        // Check there's one line per conversation...
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        // Check unset fields are dropped...
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(
+            first,
+            serde_json::json!({"messages": [
+                {"role": "system", "content": "Be terse."},
+                {"role": "assistant", "content": "Ok."},
+            ]})
+        );
+
+        // Check set fields are kept...
+        let second: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(
+            second["messages"][1],
+            serde_json::json!({
+                "role": "assistant",
+                "function_call": {"name": "get_weather", "arguments": "{}"},
+            })
+        );
+        assert_eq!(second["messages"][2]["name"], "get_weather");
+    }
+
+    #[test]
+    fn fine_tune_jsonl_requires_assistant_message() {
+        let conversations = vec![
+            vec![
+                message(Role::User, "Hi!"),
+                message(Role::Assistant, "Hello!"),
+            ],
+            vec![message(Role::User, "Hi!")],
+        ];
+        let err = to_fine_tune_jsonl(&conversations).unwrap_err();
+        assert!(matches!(err, Error::Validation(ref msg) if msg.contains("Conversation 1")));
+    }
+}
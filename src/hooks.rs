@@ -0,0 +1,231 @@
+//! Hooks for observing the requests a `Client` sends and
+//! the responses it receives (e.g. for debug logging).
+//!
+//! Auth headers are redacted before a hook sees them, so
+//! hooks can log everything they're given without leaking
+//! API keys.
+
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Method, Request, Response, StatusCode};
+use std::fmt;
+use std::sync::Arc;
+use url::Url;
+
+use crate::Result;
+
+/// The value redacted headers are replaced with.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// The headers that carry credentials, and are redacted.
+const SENSITIVE_HEADERS: &[&str] = &["authorization", "api-key"];
+
+/// A callback run before each request is sent.
+pub type RequestHook = Arc<dyn Fn(&RequestLog) + Send + Sync>;
+
+/// A callback run after each response is received.
+pub type ResponseHook = Arc<dyn Fn(&ResponseLog) + Send + Sync>;
+
+/// A request about to be sent, as seen by a request hook.
+#[derive(Debug, Clone)]
+pub struct RequestLog {
+    /// The HTTP method.
+    pub method: Method,
+
+    /// The full URL, including any query string.
+    pub url: Url,
+
+    /// The request headers, with auth headers redacted.
+    pub headers: HeaderMap,
+
+    /// The request body, if it's in memory (streamed bodies,
+    /// like file uploads, are `None`).
+    pub body: Option<String>,
+}
+
+/// A response that was received, as seen by a response hook.
+#[derive(Debug, Clone)]
+pub struct ResponseLog {
+    /// The method of the request this answers.
+    pub method: Method,
+
+    /// The URL of the request this answers.
+    pub url: Url,
+
+    /// The HTTP status code.
+    pub status: StatusCode,
+
+    /// The response headers.
+    pub headers: HeaderMap,
+
+    /// The response body. Streamed responses (server-sent
+    /// events) are `None`, since reading them up front would
+    /// defeat streaming.
+    pub body: Option<String>,
+}
+
+/// The hooks set on a client.
+#[derive(Clone, Default)]
+pub(crate) struct Hooks {
+    pub(crate) on_request: Option<RequestHook>,
+    pub(crate) on_response: Option<ResponseHook>,
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("on_request", &self.on_request.is_some())
+            .field("on_response", &self.on_response.is_some())
+            .finish()
+    }
+}
+
+impl Hooks {
+    /// Runs the request hook, if set.
+    pub(crate) fn request(&self, req: &Request) {
+        let body = req.body().and_then(|body| body.as_bytes());
+        self.run_request(req.method(), req.url(), req.headers(), body);
+    }
+
+    /// Runs the request hook for a blocking request, if set.
+    pub(crate) fn blocking_request(&self, req: &reqwest::blocking::Request) {
+        let body = req.body().and_then(|body| body.as_bytes());
+        self.run_request(req.method(), req.url(), req.headers(), body);
+    }
+
+    /// Runs the request hook on the parts of a request.
+    fn run_request(&self, method: &Method, url: &Url, headers: &HeaderMap, body: Option<&[u8]>) {
+        let Some(hook) = &self.on_request else {
+            return;
+        };
+        hook(&RequestLog {
+            method: method.clone(),
+            url: url.clone(),
+            headers: redact(headers),
+            body: body.map(|bytes| String::from_utf8_lossy(bytes).into_owned()),
+        });
+    }
+
+    /// Runs the response hook, if set.
+    ///
+    /// Unless the response is a stream, its body is read so
+    /// the hook can see it, and the returned response is
+    /// rebuilt around the buffered body.
+    pub(crate) async fn response(
+        &self,
+        method: &Method,
+        url: &Url,
+        res: Response,
+    ) -> Result<Response> {
+        let Some(hook) = &self.on_response else {
+            return Ok(res);
+        };
+        let mut log = ResponseLog {
+            method: method.clone(),
+            url: url.clone(),
+            status: res.status(),
+            headers: res.headers().clone(),
+            body: None,
+        };
+
+        // Leave streams untouched...
+        if is_event_stream(res.headers()) {
+            hook(&log);
+            return Ok(res);
+        }
+
+        // Otherwise, buffer the body...
+        let version = res.version();
+        let bytes = res.bytes().await?;
+        log.body = Some(String::from_utf8_lossy(&bytes).into_owned());
+        hook(&log);
+
+        // ...and put it back...
+        let mut rebuilt = http::Response::new(bytes);
+        *rebuilt.status_mut() = log.status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = log.headers;
+        Ok(Response::from(rebuilt))
+    }
+
+    /// Runs the response hook for a blocking response, if set.
+    ///
+    /// See `response`.
+    pub(crate) fn blocking_response(
+        &self,
+        method: &Method,
+        url: &Url,
+        res: reqwest::blocking::Response,
+    ) -> Result<reqwest::blocking::Response> {
+        let Some(hook) = &self.on_response else {
+            return Ok(res);
+        };
+        let mut log = ResponseLog {
+            method: method.clone(),
+            url: url.clone(),
+            status: res.status(),
+            headers: res.headers().clone(),
+            body: None,
+        };
+
+        // Leave streams untouched...
+        if is_event_stream(res.headers()) {
+            hook(&log);
+            return Ok(res);
+        }
+
+        // Otherwise, buffer the body...
+        let version = res.version();
+        let bytes = res.bytes()?;
+        log.body = Some(String::from_utf8_lossy(&bytes).into_owned());
+        hook(&log);
+
+        // ...and put it back...
+        let mut rebuilt = http::Response::new(bytes);
+        *rebuilt.status_mut() = log.status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = log.headers;
+        Ok(reqwest::blocking::Response::from(rebuilt))
+    }
+}
+
+/// Returns a copy of the headers with any credentials
+/// replaced by `REDACTED`.
+pub fn redact(headers: &HeaderMap) -> HeaderMap {
+    let mut headers = headers.clone();
+    for name in SENSITIVE_HEADERS {
+        if headers.contains_key(*name) {
+            let mut value = HeaderValue::from_static(REDACTED);
+            value.set_sensitive(true);
+            headers.insert(*name, value);
+        }
+    }
+    headers
+}
+
+/// Is the response a server-sent event stream?
+fn is_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use reqwest::header::AUTHORIZATION;
+
+    #[test]
+    fn redact_masks_credentials() {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, "Bearer sk-secret".parse().unwrap());
+        headers.insert("api-key", "azure-secret".parse().unwrap());
+        headers.insert("x-my-org", "research".parse().unwrap());
+
+        let redacted = redact(&headers);
+        assert_eq!(redacted[AUTHORIZATION], REDACTED);
+        assert_eq!(redacted["api-key"], REDACTED);
+        assert_eq!(redacted["x-my-org"], "research");
+        assert!(!format!("{:?}", redacted).contains("secret"));
+    }
+}
@@ -1,25 +1,216 @@
-use anyhow::{anyhow, Result};
-use reqwest::{Method, Request, RequestBuilder};
-use serde::{Deserialize, Serialize};
+use futures_core::Stream;
+use futures_util::stream::StreamExt;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+use reqwest::{Method, NoProxy, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
 use url::Url;
 
+use chat::ChatBuilder;
 use dtypes::{
-    ChatCompletionChunk, ChatCompletionObject, ChatCompletionRequest, ListModelsResponse,
+    ApiError, ApiErrorEnvelope, ApiObject, Assistant, Batch, ChatCompletionChunk,
+    ChatCompletionObject, ChatCompletionRequest, ChunkEvent, CompletionChunk, CompletionRequest,
+    CompletionResponse, CreateAssistantRequest, CreateBatchRequest, CreateEmbeddingRequest,
+    CreateEmbeddingResponse, CreateFineTuningJobRequest, CreateImageEditRequest,
+    CreateImageRequest, CreateImageVariationRequest, CreateMessageRequest, CreateRunRequest,
+    CreateThreadRequest, CreateTranslationRequest, DeleteAssistantResponse, DeleteFileResponse,
+    DeleteModelResponse, DeleteThreadResponse, EmbeddingInput, FileObject, FineTuningJob,
+    FineTuningJobEvent, ImageResponse, ListModelsResponse, ModelObject, ModerationRequest,
+    ModerationResponse, ModifyAssistantRequest, Run, RunStatus, SpeechRequest,
+    SubmitToolOutputsRequest, Thread, ThreadMessage, TranslationResponse,
 };
+use hooks::{Hooks, RequestLog, ResponseLog};
+use meta::{RateLimitInfo, ResponseMeta};
+use page::{ListParams, Page};
+use retry::{new_idempotency_key, IdempotencyKeyFn, RetryPolicy, IDEMPOTENCY_KEY_HEADER};
+use sse::SseDecoder;
+
+pub use error::{Error, Result};
+pub use reqwest;
 
 pub mod blocking;
+pub mod chat;
 pub mod dtypes;
+pub mod error;
+pub mod fine_tune;
+pub mod hooks;
+pub mod meta;
+pub mod models;
+pub mod page;
+pub mod pricing;
+pub mod retry;
+pub mod sse;
+pub mod stream;
 
 #[cfg(feature = "mock")]
 pub mod mock;
 
+#[cfg(feature = "tokens")]
+pub mod tokens;
+
 pub const BASE_URL: &str = "https://api.openai.com/";
 
+/// The API version prefix that paths are sent under by
+/// default (e.g. `/v1/models`).
+pub const VERSION_PREFIX: &str = "v1";
+
+/// The environment variable `Client::from_env` reads the API key from.
+pub const API_KEY_ENV_VAR: &str = "OPENAI_API_KEY";
+
+/// The environment variable `Client::from_env` reads the
+/// (optional) organization ID from.
+pub const ORG_ID_ENV_VAR: &str = "OPENAI_ORG_ID";
+
+/// The environment variable `Client::from_env` reads the
+/// (optional) project ID from.
+pub const PROJECT_ID_ENV_VAR: &str = "OPENAI_PROJECT_ID";
+
+/// The environment variable `Client::from_env` reads the
+/// (optional) base URL from.
+pub const BASE_URL_ENV_VAR: &str = "OPENAI_BASE_URL";
+
+/// The characters escaped when a value (e.g. a model ID) is
+/// used as a single path segment.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'\\')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// The flavor of API a `Client` talks to.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum ApiType {
+    /// The OpenAI API (or an OpenAI-compatible server).
+    #[default]
+    OpenAi,
+
+    /// The Azure OpenAI Service.
+    ///
+    /// Requests are authenticated with an `api-key` header,
+    /// and model-specific endpoints are routed to the given
+    /// deployment, e.g.
+    /// `{base_url}/openai/deployments/{deployment}/chat/completions?api-version={api_version}`.
+    Azure {
+        /// The name of the model deployment.
+        deployment: String,
+
+        /// The API version (e.g. `2024-02-01`).
+        api_version: String,
+    },
+}
+
+/// Endpoints that Azure serves per-deployment, rather than
+/// at the resource level.
+const AZURE_DEPLOYMENT_ENDPOINTS: &[&str] = &[
+    "chat/completions",
+    "completions",
+    "embeddings",
+    "images/generations",
+    "images/edits",
+    "images/variations",
+    "audio/speech",
+    "audio/transcriptions",
+    "audio/translations",
+];
+
+/// The header used to opt in to beta endpoints.
+pub const OPENAI_BETA_HEADER: &str = "OpenAI-Beta";
+
+/// The `OpenAI-Beta` value that opts in to v2 of the
+/// Assistants API.
+pub const ASSISTANTS_BETA: &str = "assistants=v2";
+
+/// The paths (and their subpaths) of the Assistants API,
+/// which are sent with the `OpenAI-Beta` header.
+const ASSISTANTS_PATHS: &[&str] = &["/v1/assistants", "/v1/threads"];
+
+/// A client for the OpenAI API.
+///
+/// Cloning a client is cheap, and the clone shares the
+/// original's connection pool, model cache, concurrency
+/// limit, and response metadata.
+#[derive(Clone)]
 pub struct Client {
     pub base_url: String,
     pub api_key: String,
     pub org_id: Option<String>,
+    pub project_id: Option<String>,
     pub req_client: reqwest::Client,
+
+    /// The version prefix the API's paths are sent under,
+    /// in place of the `v1` the endpoints are written with.
+    /// Set it to `""` for servers that serve the API at the
+    /// root of their base URL.
+    ///
+    /// Defaults to `VERSION_PREFIX`.
+    pub version_prefix: String,
+
+    /// Which flavor of the API the client talks to, which
+    /// determines how URLs and auth headers are built.
+    pub api_type: ApiType,
+
+    /// The most recent result of `list_models`, along with
+    /// the time it was fetched.
+    models_cache: Arc<RwLock<Option<(Instant, ListModelsResponse)>>>,
+
+    /// Limits the number of requests this client has in
+    /// flight at once, if set.
+    concurrency: Option<Arc<Semaphore>>,
+
+    /// Metadata from the most recent response.
+    last_response_meta: Arc<RwLock<Option<ResponseMeta>>>,
+
+    /// How requests that fail with a transient error are
+    /// retried.
+    retry: RetryPolicy,
+
+    /// Generates the idempotency keys for retried requests,
+    /// if overridden.
+    idempotency_key: Option<IdempotencyKeyFn>,
+
+    /// Overrides the inner client's timeout for each
+    /// request, if set.
+    request_timeout: Option<Duration>,
+
+    /// Should chat completion requests be validated before
+    /// they're sent?
+    validate_requests: bool,
+
+    /// Headers added to every request (e.g. for a proxy).
+    default_headers: HeaderMap,
+
+    /// Callbacks run for each request and response.
+    hooks: Hooks,
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("base_url", &self.base_url)
+            .field("api_key", &mask_key(&self.api_key))
+            .field("org_id", &self.org_id)
+            .field("project_id", &self.project_id)
+            .field("version_prefix", &self.version_prefix)
+            .field("api_type", &self.api_type)
+            .field("retry", &self.retry)
+            .field("request_timeout", &self.request_timeout)
+            .field("validate_requests", &self.validate_requests)
+            .field("default_headers", &hooks::redact(&self.default_headers))
+            .finish_non_exhaustive()
+    }
 }
 
 impl Client {
@@ -35,21 +226,189 @@ impl Client {
     /// let client = Client::new("test");
     /// ```
     pub fn new(api_key: &str) -> Self {
+        Self::with_client(api_key, reqwest::Client::new())
+    }
+
+    /// Create a new client for the Azure OpenAI Service.
+    ///
+    /// The `endpoint` is your resource's endpoint (e.g.
+    /// `https://my-resource.openai.azure.com/`), and requests
+    /// for model-specific endpoints are sent to `deployment`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::Client;
+    ///
+    /// let client = Client::new_azure(
+    ///     "https://my-resource.openai.azure.com/",
+    ///     "test",
+    ///     "my-gpt-4o",
+    ///     "2024-02-01",
+    /// );
+    /// ```
+    pub fn new_azure(endpoint: &str, api_key: &str, deployment: &str, api_version: &str) -> Self {
+        let mut client = Self::new(api_key);
+        client.base_url = endpoint.to_string();
+        client.api_type = ApiType::Azure {
+            deployment: deployment.to_string(),
+            api_version: api_version.to_string(),
+        };
+        client
+    }
+
+    /// Create a new client with the given API key that sends
+    /// requests using the given `reqwest::Client`.
+    ///
+    /// This is useful for sharing a connection pool, or for
+    /// configuring TLS, proxies, or middleware that this crate
+    /// doesn't expose itself.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::Client;
+    /// use std::time::Duration;
+    ///
+    /// let req_client = reqwest::Client::builder()
+    ///     .pool_idle_timeout(Duration::from_secs(30))
+    ///     .build()
+    ///     .unwrap();
+    /// let client = Client::with_client("test", req_client);
+    /// ```
+    pub fn with_client(api_key: &str, req_client: reqwest::Client) -> Self {
         Self {
             base_url: BASE_URL.to_string(),
             api_key: api_key.to_string(),
             org_id: None,
-            req_client: reqwest::Client::new(),
+            project_id: None,
+            req_client,
+            version_prefix: VERSION_PREFIX.to_string(),
+            api_type: ApiType::OpenAi,
+            models_cache: Arc::new(RwLock::new(None)),
+            concurrency: None,
+            last_response_meta: Arc::new(RwLock::new(None)),
+            retry: RetryPolicy::default(),
+            idempotency_key: None,
+            request_timeout: None,
+            validate_requests: false,
+            default_headers: HeaderMap::new(),
+            hooks: Hooks::default(),
+        }
+    }
+
+    /// Create a new client configured from environment variables.
+    ///
+    /// The API key is read from `OPENAI_API_KEY`, which is required.
+    /// The organization ID, project ID and base URL are read from
+    /// `OPENAI_ORG_ID`, `OPENAI_PROJECT_ID` and `OPENAI_BASE_URL`, if
+    /// they're set.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::Client;
+    ///
+    /// let client = Client::from_env().expect("OPENAI_API_KEY should be set");
+    /// ```
+    pub fn from_env() -> Result<Self> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Create a new client configured from the variables
+    /// returned by `lookup`. Empty values count as unset.
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let lookup = |name| lookup(name).filter(|value: &String| !value.trim().is_empty());
+
+        // Get the API key...
+        let api_key = lookup(API_KEY_ENV_VAR)
+            .ok_or_else(|| Error::MissingEnvVar(API_KEY_ENV_VAR.to_string()))?;
+        let mut builder = Self::builder().api_key(&api_key);
+
+        // Get the optional settings...
+        if let Some(org_id) = lookup(ORG_ID_ENV_VAR) {
+            builder = builder.org_id(&org_id);
+        }
+        if let Some(project_id) = lookup(PROJECT_ID_ENV_VAR) {
+            builder = builder.project_id(&project_id);
+        }
+        if let Some(base_url) = lookup(BASE_URL_ENV_VAR) {
+            builder = builder.base_url(&base_url);
+        }
+
+        // Build the client...
+        builder.build()
+    }
+
+    /// Create a `ClientBuilder` for configuring a client
+    /// beyond just the API key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::Client;
+    ///
+    /// let client = Client::builder()
+    ///     .api_key("test")
+    ///     .max_concurrency(4)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// Returns a handle to this client whose requests use the
+    /// given timeout, overriding the client's default.
+    ///
+    /// The handle shares this client's connection pool and
+    /// state (see `Client`), so it's cheap to create for a
+    /// single call.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::{dtypes::CreateImageRequest, Client};
+    /// use std::time::Duration;
+    ///
+    /// # async fn run() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// let req = CreateImageRequest {
+    ///     prompt: "A cat in a hat".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let res = client
+    ///     .with_timeout(Duration::from_secs(120))
+    ///     .create_image(req)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_timeout(&self, timeout: Duration) -> Client {
+        let mut client = self.clone();
+        client.request_timeout = Some(timeout);
+        client
+    }
+
+    /// Waits for a free slot if the client has a concurrency
+    /// limit. The returned permit should be held until the
+    /// request (including any retries) has finished.
+    async fn acquire_permit(&self) -> Result<Option<OwnedSemaphorePermit>> {
+        match &self.concurrency {
+            Some(semaphore) => {
+                let permit = semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("the request semaphore is never closed");
+                Ok(Some(permit))
+            }
+            None => Ok(None),
         }
     }
 
     fn format_url(&self, path: &str) -> Result<Url> {
-        let base_url = Url::parse(self.base_url.as_str())
-            .map_err(|err| anyhow!("Failed to parse self.base_url: {}", err))?;
-        let url = base_url
-            .join(path)
-            .map_err(|err| anyhow!("Failed to add path to self.base_url: {}", err))?;
-        Ok(url)
+        format_url(&self.base_url, &self.version_prefix, &self.api_type, path)
     }
 
     fn create_request(&self, method: reqwest::Method, path: &str) -> Result<RequestBuilder> {
@@ -59,141 +418,3852 @@ impl Client {
         // Create a request builder...
         let mut req = self.req_client.request(method, url);
 
+        // Override the timeout, if set...
+        if let Some(timeout) = self.request_timeout {
+            req = req.timeout(timeout);
+        }
+
+        // Add the default headers, except any that would clash
+        // with the auth, org or project headers...
+        if !self.default_headers.is_empty() {
+            let mut headers = self.default_headers.clone();
+            match self.api_type {
+                ApiType::OpenAi => headers.remove(AUTHORIZATION),
+                ApiType::Azure { .. } => headers.remove("api-key"),
+            };
+            if self.org_id.is_some() {
+                headers.remove("OpenAI-Organization");
+            }
+            if self.project_id.is_some() {
+                headers.remove("OpenAI-Project");
+            }
+            req = req.headers(headers);
+        }
+
         // Add the auth header...
-        req = req.bearer_auth(self.api_key.as_str());
+        req = match self.api_type {
+            ApiType::OpenAi => req.bearer_auth(self.api_key.as_str()),
+            ApiType::Azure { .. } => req.header("api-key", self.api_key.as_str()),
+        };
 
         // If there's a org_id, add it...
         if let Some(org_id) = &self.org_id {
             req = req.header("OpenAI-Organization", org_id.as_str());
         }
 
+        // If there's a project_id, add it...
+        if let Some(project_id) = &self.project_id {
+            req = req.header("OpenAI-Project", project_id.as_str());
+        }
+
+        // If it's a beta endpoint, opt in...
+        if let Some(beta) = beta_header(path) {
+            req = req.header(OPENAI_BETA_HEADER, beta);
+        }
+
         // Return the request...
         Ok(req)
     }
 
+    /// Returns a request builder for the given API path (e.g.
+    /// `/v1/models`), with the client's URL, auth and default
+    /// headers already set.
+    ///
+    /// This is an escape hatch for endpoints (or options) the
+    /// typed methods don't cover. Send the request with
+    /// `send_raw` to keep the client's retries, hooks and
+    /// concurrency limit.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::{reqwest::Method, Client};
+    ///
+    /// # async fn run() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// let rb = client.raw_request(Method::GET, "/v1/models")?;
+    /// let res = client.send_raw(rb).await?;
+    /// println!("{:?}", res.headers());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn raw_request(&self, method: Method, path: &str) -> Result<RequestBuilder> {
+        self.create_request(method, path)
+    }
+
+    /// Sends a request built with `raw_request`, returning the
+    /// response without parsing it.
+    ///
+    /// Non-success statuses are returned as an `Error::Api`.
+    pub async fn send_raw(&self, rb: RequestBuilder) -> Result<Response> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Send the request and check the status...
+        let res = self.send(rb).await?;
+        check_status(res).await
+    }
+
+    /// Sends a request, retrying it according to the
+    /// client's `RetryPolicy` if it fails with a transient
+    /// error.
+    async fn send(&self, rb: RequestBuilder) -> Result<Response> {
+        let mut req = rb.build()?;
+
+        // Tag POSTs that may be retried, so the API can dedupe them...
+        if self.retry.max_retries > 0
+            && req.method() == Method::POST
+            && !req.headers().contains_key(IDEMPOTENCY_KEY_HEADER)
+        {
+            let key = match &self.idempotency_key {
+                Some(generate) => generate(),
+                None => new_idempotency_key(),
+            };
+            req.headers_mut().insert(
+                IDEMPOTENCY_KEY_HEADER,
+                key.parse()
+                    .map_err(|_| Error::Config(format!("invalid idempotency key: {:?}", key)))?,
+            );
+        }
+
+        let mut retries = 0;
+        loop {
+            // Keep a copy of the request in case it needs retrying...
+            let retry_req = match retries < self.retry.max_retries {
+                true => req.try_clone(),
+                false => None,
+            };
+
+            // Send the request...
+            self.hooks.request(&req);
+            let (method, url) = (req.method().clone(), req.url().clone());
+            let res = self.req_client.execute(req).await?;
+            let res = self.hooks.response(&method, &url, res).await?;
+
+            // Record the response's metadata...
+            let meta = ResponseMeta::from_headers(res.headers());
+            *self
+                .last_response_meta
+                .write()
+                .unwrap_or_else(PoisonError::into_inner) = Some(meta);
+
+            // Return the response, unless it should be retried...
+            let delay = match RetryPolicy::is_retryable(res.status()) {
+                true => self.retry.delay(retries, res.headers()),
+                false => None,
+            };
+            match (retry_req, delay) {
+                (Some(next), Some(delay)) => {
+                    tokio::time::sleep(delay).await;
+                    req = next;
+                    retries += 1;
+                }
+                _ => return Ok(res),
+            }
+        }
+    }
+
+    /// Returns the metadata (e.g. server processing time)
+    /// parsed from the headers of the most recent response
+    /// this client received, if any.
+    pub fn last_response_meta(&self) -> Option<ResponseMeta> {
+        self.last_response_meta
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Returns the rate limit info parsed from the headers
+    /// of the most recent response this client received,
+    /// if any.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::Client;
+    ///
+    /// # async fn run() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// client.list_models().await?;
+    /// if let Some(info) = client.last_rate_limit() {
+    ///     println!("{:?} requests left", info.remaining_requests);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn last_rate_limit(&self) -> Option<RateLimitInfo> {
+        self.last_response_meta().map(|meta| meta.rate_limit)
+    }
+
     pub async fn list_models(&self) -> Result<ListModelsResponse> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
         // Format the URL...
         let rb = self.create_request(Method::GET, "/v1/models")?;
 
         // Send the request...
-        let res = rb
-            .send()
-            .await
-            .map_err(|err| anyhow!("Failed to send request: {}", err))?;
+        let res = self.send(rb).await?;
 
-        // TODO - Check status code and handle other possible states...
+        // Check the status and parse the response as json...
+        let data = handle_response::<ListModelsResponse>(res).await?;
+        data.check_object()?;
 
-        // Parse the response as json...
-        let data = res
-            .json::<ListModelsResponse>()
-            .await
-            .map_err(|err| anyhow!("Failed to parse response as json: {}", err))?;
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Retrieves a single model by its ID.
+    pub async fn retrieve_model(&self, model_id: &str) -> Result<ModelObject> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/models/{}", escape_path_segment(model_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ModelObject>(res).await?;
+        data.check_object()?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Deletes a fine-tuned model. You must have the
+    /// Owner role in your organization to delete a model.
+    pub async fn delete_model(&self, model_id: &str) -> Result<DeleteModelResponse> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/models/{}", escape_path_segment(model_id));
+        let rb = self.create_request(Method::DELETE, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<DeleteModelResponse>(res).await?;
 
         // Return the data...
         Ok(data)
     }
 
+    /// Lists the available models, reusing the result of a
+    /// previous call if it was fetched less than `ttl` ago.
+    ///
+    /// The cached list is stored on the client, so it's shared
+    /// by everything holding a reference to it. Use
+    /// `refresh_models` to force a reload.
+    pub async fn list_models_cached(&self, ttl: Duration) -> Result<ListModelsResponse> {
+        // Return the cached copy if it's still fresh...
+        {
+            let cache = self
+                .models_cache
+                .read()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some((fetched_at, models)) = cache.as_ref() {
+                if fetched_at.elapsed() < ttl {
+                    return Ok(models.clone());
+                }
+            }
+        }
+
+        // Otherwise, reload it...
+        self.refresh_models().await
+    }
+
+    /// Fetches the list of models and stores it in the client's
+    /// cache, regardless of how old the cached copy is.
+    pub async fn refresh_models(&self) -> Result<ListModelsResponse> {
+        // Fetch the models...
+        let models = self.list_models().await?;
+
+        // Update the cache...
+        let mut cache = self
+            .models_cache
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        *cache = Some((Instant::now(), models.clone()));
+
+        // Return the data...
+        Ok(models)
+    }
+
     pub async fn create_chat_completion(
         &self,
         req: ChatCompletionRequest,
     ) -> Result<ChatCompletionObject> {
-        // Format the URL...
-        let rb = self.create_request(Method::POST, "/v1/chat/completions")?;
+        // Build the request...
+        let rb = self.chat_completion_request(&req)?;
 
-        // Add the body...
-        let rb = rb.json(&req);
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
 
         // Send the request...
-        let res = rb
-            .send()
-            .await
-            .map_err(|err| anyhow!("Failed to send request: {}", err))?;
+        let res = self.send(rb).await?;
 
-        // TODO - Check status code and handle other possible states...
+        // Check the status and parse the response as json...
+        let data = handle_response::<ChatCompletionObject>(res).await?;
+        data.check_object()?;
 
-        // Parse the response as json...
-        let data = res
-            .json::<ChatCompletionObject>()
-            .await
-            .map_err(|err| anyhow!("Failed to parse response as json: {}", err))?;
+        // Make sure there's a choice to return...
+        if data.choices.is_empty() {
+            return Err(Error::EmptyChoices(Box::new(data)));
+        }
 
         // Return the data...
         Ok(data)
     }
 
-    // pub async fn create_chat_completion_stream(
-    //     &self,
-    //     req: ChatCompletionRequest,
-    // ) -> Result<impl futures_core::Stream<Item = anyhow::Result<ChatCompletionChunk>>> {
-    //     // Format the URL...
-    //     let rb = self.create_request(Method::POST, "/v1/chat/completions")?;
+    /// Continues a chat completion that was cut short by the
+    /// token limit, by re-sending `req` with the partial
+    /// message from `res` appended.
+    ///
+    /// The returned completion only holds the rest of the
+    /// message, so append its content to the partial one.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::Client;
+    ///
+    /// # async fn run() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// let req = client.chat("gpt-4o").user("Tell me a story.").max_tokens(100).build();
+    /// let mut res = client.create_chat_completion(req.clone()).await?;
+    /// let mut story = res.first_content().unwrap_or_default().to_string();
+    /// while res.is_truncated() {
+    ///     res = client.continue_completion(req.clone(), &res).await?;
+    ///     story.push_str(res.first_content().unwrap_or_default());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn continue_completion(
+        &self,
+        req: ChatCompletionRequest,
+        res: &ChatCompletionObject,
+    ) -> Result<ChatCompletionObject> {
+        self.create_chat_completion(req.continuation(res)).await
+    }
+
+    /// Creates a chat completion, returning the raw response
+    /// rather than parsing it.
+    ///
+    /// The request is validated (if enabled), authenticated,
+    /// retried and status-checked just like with
+    /// `create_chat_completion`, so an API error is still
+    /// returned as an `Error::Api`. Use this to read response
+    /// headers or handle the body yourself.
+    pub async fn create_chat_completion_raw(&self, req: ChatCompletionRequest) -> Result<Response> {
+        // Build the request...
+        let rb = self.chat_completion_request(&req)?;
+
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Send the request and check the status...
+        let res = self.send(rb).await?;
+        check_status(res).await
+    }
+
+    /// Returns the JSON body `create_chat_completion` would
+    /// send for `req`, without sending anything.
+    ///
+    /// The request is validated (if enabled) and serialized
+    /// the same way as when it's sent, so this is useful for
+    /// checking which optional fields are omitted, or for
+    /// generating test fixtures.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::{dtypes::ChatCompletionRequest, Client};
+    ///
+    /// # fn main() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// let req = ChatCompletionRequest {
+    ///     model: "gpt-4o".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let body = client.build_chat_completion_body(&req)?;
+    /// assert!(!body.contains("\"seed\""));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn build_chat_completion_body(&self, req: &ChatCompletionRequest) -> Result<String> {
+        let req = self.chat_completion_request(req)?.build()?;
+        let body = req
+            .body()
+            .and_then(|body| body.as_bytes())
+            .unwrap_or_default();
+        Ok(String::from_utf8_lossy(body).into_owned())
+    }
 
-    //     // Add the body...
-    //     let rb = rb.json(&req);
+    /// Validates (if enabled) and builds a chat completion
+    /// request, ready to send.
+    fn chat_completion_request(&self, req: &ChatCompletionRequest) -> Result<RequestBuilder> {
+        // Check the request, if enabled...
+        if self.validate_requests {
+            req.validate()?;
+        }
 
-    //     // Send the request...
-    //     let res = rb
-    //         .send()
-    //         .await
-    //         .map_err(|err| anyhow!("Failed to send request: {}", err))?;
+        // Format the URL and add the body...
+        let rb = self.create_request(Method::POST, "/v1/chat/completions")?;
+        Ok(rb.json(req))
+    }
 
-    //     // TODO - Check status code and handle other possible states...
+    /// Creates a completion using the legacy completions
+    /// endpoint.
+    ///
+    /// Prefer `create_chat_completion` where it's available,
+    /// but some older models and OpenAI-compatible servers
+    /// only support this endpoint.
+    pub async fn create_completion(&self, req: CompletionRequest) -> Result<CompletionResponse> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
 
-    //     // Parse the response as json...
-    //     let data = res
-    //         .json::<ChatCompletionObject>()
-    //         .await
-    //         .map_err(|err| anyhow!("Failed to parse response as json: {}", err))?;
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/completions")?;
 
-    //     // Return the data...
-    //     Ok(data)
-    // }
-}
+        // Add the body...
+        let rb = rb.json(&req);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use anyhow::Result;
+        // Send the request...
+        let res = self.send(rb).await?;
 
-    #[tokio::test]
-    async fn it_works() -> Result<()> {
-        let _ = Client::new("test");
-        Ok(())
+        // Check the status and parse the response as json...
+        let data = handle_response::<CompletionResponse>(res).await?;
+
+        // Return the data...
+        Ok(data)
     }
 
-    /// NOTE - This test requires a running mock server.
-    #[tokio::test]
-    async fn create_chat_completion_sync() -> Result<()> {
-        // Create a client...
-        let mut client = Client::new("test");
-        client.base_url = "http://localhost:1323".to_string();
+    /// Creates a completion using the legacy completions
+    /// endpoint, streaming back the generated text as it's
+    /// produced.
+    ///
+    /// Many local inference servers only support this
+    /// streaming shape.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    /// use ohairs::{
+    ///     dtypes::{CompletionPrompt, CompletionRequest},
+    ///     Client,
+    /// };
+    ///
+    /// # async fn run() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// let req = CompletionRequest {
+    ///     model: "gpt-3.5-turbo-instruct".to_string(),
+    ///     prompt: CompletionPrompt::String("Say hello".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// let mut stream = client.create_completion_stream(req).await?;
+    /// while let Some(chunk) = stream.next().await {
+    ///     print!("{}", chunk?.text().unwrap_or_default());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_completion_stream(
+        &self,
+        mut req: CompletionRequest,
+    ) -> Result<impl Stream<Item = Result<CompletionChunk>> + Unpin + Send> {
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/completions")?;
 
-        let req = ChatCompletionRequest {
-            model: "gpt-3.5-turbo".to_string(),
-            stream: Some(false),
-            messages: vec![],
-            ..Default::default()
-        };
+        // Add the body...
+        req.stream = Some(true);
+        let rb = rb.json(&req);
 
-        let _res = client.create_chat_completion(req).await?;
-        Ok(())
+        // Wait for a free slot (held until the stream is dropped)...
+        let permit = self.acquire_permit().await?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status before reading any events...
+        let res = check_status(res).await?;
+
+        // Parse the body as a stream of chunks...
+        Ok(chunk_stream(
+            res.bytes_stream(),
+            CompletionChunk::parse_event,
+            permit,
+        ))
     }
 
-    /// NOTE - This test requires a running mock server.
-    #[tokio::test]
-    async fn create_chat_completion_async() -> Result<()> {
-        // Create a client...
-        let mut client = Client::new("test");
-        client.base_url = "http://localhost:1323".to_string();
+    /// Creates embedding vectors representing the input text.
+    pub async fn create_embeddings(
+        &self,
+        req: CreateEmbeddingRequest,
+    ) -> Result<CreateEmbeddingResponse> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
 
-        let req = ChatCompletionRequest {
-            model: "gpt-3.5-turbo".to_string(),
-            stream: Some(false),
-            messages: vec![],
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/embeddings")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<CreateEmbeddingResponse>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Ranks `corpus` by its similarity to `query`, returning
+    /// the indices and cosine similarities of the top `k`
+    /// entries, most similar first.
+    ///
+    /// The query and the whole corpus are embedded with
+    /// `model` in a single batched `create_embeddings` call,
+    /// so the corpus must fit within one request.
+    pub async fn semantic_search(
+        &self,
+        model: &str,
+        query: &str,
+        corpus: &[String],
+        k: usize,
+    ) -> Result<Vec<(usize, f32)>> {
+        // Embed the query and the corpus together...
+        let mut input = Vec::with_capacity(corpus.len() + 1);
+        input.push(query.to_string());
+        input.extend(corpus.iter().cloned());
+        let res = self
+            .create_embeddings(CreateEmbeddingRequest {
+                model: model.to_string(),
+                input: EmbeddingInput::StringArray(input),
+                ..Default::default()
+            })
+            .await?;
+
+        // Put the embeddings back in input order...
+        let mut data = res.data;
+        data.sort_by_key(|d| d.index);
+        if data.len() != corpus.len() + 1 {
+            return Err(Error::Validation(format!(
+                "expected {} embeddings, got {}",
+                corpus.len() + 1,
+                data.len()
+            )));
+        }
+
+        // Score each corpus entry against the query...
+        let query = &data[0].embedding;
+        let mut scores: Vec<(usize, f32)> = data[1..]
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (i, cosine_similarity(query, &d.embedding)))
+            .collect();
+
+        // Return the top k...
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scores.truncate(k);
+        Ok(scores)
+    }
+
+    /// Creates images from a prompt.
+    pub async fn create_image(&self, req: CreateImageRequest) -> Result<ImageResponse> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/images/generations")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ImageResponse>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Edits an image given a prompt and an optional mask.
+    ///
+    /// Like `upload_file`, the image is sent as a multipart
+    /// body.
+    pub async fn create_image_edit(&self, req: CreateImageEditRequest) -> Result<ImageResponse> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/images/edits")?;
+
+        // Add the body...
+        let mut form = reqwest::multipart::Form::new();
+        for (name, value) in req.form_fields()? {
+            form = form.text(name, value);
+        }
+        form = form.part("image", file_part(req.image, "image.png"));
+        if let Some(mask) = req.mask {
+            form = form.part("mask", file_part(mask, "mask.png"));
+        }
+        let rb = rb.multipart(form);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ImageResponse>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Creates variations of an image.
+    ///
+    /// Like `upload_file`, the image is sent as a multipart
+    /// body.
+    pub async fn create_image_variation(
+        &self,
+        req: CreateImageVariationRequest,
+    ) -> Result<ImageResponse> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/images/variations")?;
+
+        // Add the body...
+        let mut form = reqwest::multipart::Form::new();
+        for (name, value) in req.form_fields()? {
+            form = form.text(name, value);
+        }
+        form = form.part("image", file_part(req.image, "image.png"));
+        let rb = rb.multipart(form);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ImageResponse>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Classifies whether the input violates OpenAI's
+    /// usage policies.
+    pub async fn create_moderation(&self, req: ModerationRequest) -> Result<ModerationResponse> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/moderations")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ModerationResponse>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Generates spoken audio from the input text, returning
+    /// the raw audio bytes (in `req.response_format`).
+    pub async fn create_speech(&self, req: SpeechRequest) -> Result<Vec<u8>> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/audio/speech")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and read the audio...
+        let res = check_status(res).await?;
+        let data = res.bytes().await?;
+
+        // Return the data...
+        Ok(data.to_vec())
+    }
+
+    /// Translates audio into English text.
+    ///
+    /// Like `upload_file`, the audio is sent as a multipart
+    /// body. For the `text`, `srt` and `vtt` formats, the body
+    /// is returned as the response's `text`.
+    pub async fn create_translation(
+        &self,
+        req: CreateTranslationRequest,
+    ) -> Result<TranslationResponse> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/audio/translations")?;
+
+        // Add the body...
+        let mut form = reqwest::multipart::Form::new();
+        for (name, value) in req.form_fields()? {
+            form = form.text(name, value);
+        }
+        form = form.part("file", file_part(req.file, &req.filename));
+        let rb = rb.multipart(form);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response...
+        let res = check_status(res).await?;
+        let body = res.text().await?;
+        let data = TranslationResponse::from_body(&body, req.response_format)?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Uploads a file for use with other endpoints, where
+    /// `purpose` says what it's for (e.g. "fine-tune" or
+    /// "assistants").
+    ///
+    /// Note that the upload is sent as a streamed multipart
+    /// body, so it isn't retried on transient errors.
+    pub async fn upload_file(
+        &self,
+        file: Vec<u8>,
+        filename: &str,
+        purpose: &str,
+    ) -> Result<FileObject> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/files")?;
+
+        // Add the body...
+        let form = reqwest::multipart::Form::new()
+            .text("purpose", purpose.to_string())
+            .part("file", file_part(file, filename));
+        let rb = rb.multipart(form);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<FileObject>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of the uploaded files. Use
+    /// `list_all_files` or `list_files_stream` to fetch every
+    /// page.
+    pub async fn list_files(&self, params: ListParams) -> Result<Page<FileObject>> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::GET, "/v1/files")?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<FileObject>>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists every uploaded file, following the `after`
+    /// cursors until the last page.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::Client;
+    ///
+    /// # async fn run() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// let files = client.list_all_files().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list_all_files(&self) -> Result<Vec<FileObject>> {
+        page::list_all(ListParams::default(), |params| self.list_files(params)).await
+    }
+
+    /// Streams every uploaded file, fetching each page only
+    /// once the files before it have been consumed.
+    pub fn list_files_stream(&self) -> impl Stream<Item = Result<FileObject>> + Unpin + '_ {
+        page::list_stream(ListParams::default(), |params| self.list_files(params))
+    }
+
+    /// Retrieves a single file's metadata by its ID.
+    pub async fn retrieve_file(&self, file_id: &str) -> Result<FileObject> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/files/{}", escape_path_segment(file_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<FileObject>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Deletes a file.
+    pub async fn delete_file(&self, file_id: &str) -> Result<DeleteFileResponse> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/files/{}", escape_path_segment(file_id));
+        let rb = self.create_request(Method::DELETE, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<DeleteFileResponse>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Downloads the contents of a file.
+    pub async fn retrieve_file_content(&self, file_id: &str) -> Result<Vec<u8>> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/files/{}/content", escape_path_segment(file_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and read the contents...
+        let res = check_status(res).await?;
+        let data = res.bytes().await?;
+
+        // Return the data...
+        Ok(data.to_vec())
+    }
+
+    /// Creates a fine-tuning job, which starts training a
+    /// new model from `req.model` and the uploaded training
+    /// file.
+    pub async fn create_fine_tuning_job(
+        &self,
+        req: CreateFineTuningJobRequest,
+    ) -> Result<FineTuningJob> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/fine_tuning/jobs")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<FineTuningJob>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of your organization's fine-tuning jobs.
+    pub async fn list_fine_tuning_jobs(&self, params: ListParams) -> Result<Page<FineTuningJob>> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::GET, "/v1/fine_tuning/jobs")?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<FineTuningJob>>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Retrieves a single fine-tuning job by its ID.
+    pub async fn retrieve_fine_tuning_job(&self, job_id: &str) -> Result<FineTuningJob> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/fine_tuning/jobs/{}", escape_path_segment(job_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<FineTuningJob>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Cancels a fine-tuning job that's still running.
+    pub async fn cancel_fine_tuning_job(&self, job_id: &str) -> Result<FineTuningJob> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!(
+            "/v1/fine_tuning/jobs/{}/cancel",
+            escape_path_segment(job_id)
+        );
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<FineTuningJob>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of a fine-tuning job's status updates.
+    pub async fn list_fine_tuning_events(
+        &self,
+        job_id: &str,
+        params: ListParams,
+    ) -> Result<Page<FineTuningJobEvent>> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!(
+            "/v1/fine_tuning/jobs/{}/events",
+            escape_path_segment(job_id)
+        );
+        let rb = self.create_request(Method::GET, &path)?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<FineTuningJobEvent>>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Creates an assistant.
+    pub async fn create_assistant(&self, req: CreateAssistantRequest) -> Result<Assistant> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/assistants")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Assistant>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of assistants.
+    pub async fn list_assistants(&self, params: ListParams) -> Result<Page<Assistant>> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::GET, "/v1/assistants")?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<Assistant>>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Retrieves a single assistant by its ID.
+    pub async fn retrieve_assistant(&self, assistant_id: &str) -> Result<Assistant> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/assistants/{}", escape_path_segment(assistant_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Assistant>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Modifies an assistant, changing only the fields set
+    /// in `req`.
+    pub async fn modify_assistant(
+        &self,
+        assistant_id: &str,
+        req: ModifyAssistantRequest,
+    ) -> Result<Assistant> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/assistants/{}", escape_path_segment(assistant_id));
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Assistant>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Deletes an assistant.
+    pub async fn delete_assistant(&self, assistant_id: &str) -> Result<DeleteAssistantResponse> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/assistants/{}", escape_path_segment(assistant_id));
+        let rb = self.create_request(Method::DELETE, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<DeleteAssistantResponse>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Creates a thread, optionally starting it with some
+    /// messages.
+    pub async fn create_thread(&self, req: CreateThreadRequest) -> Result<Thread> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/threads")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Thread>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Retrieves a single thread by its ID.
+    pub async fn retrieve_thread(&self, thread_id: &str) -> Result<Thread> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/threads/{}", escape_path_segment(thread_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Thread>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Deletes a thread.
+    pub async fn delete_thread(&self, thread_id: &str) -> Result<DeleteThreadResponse> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/threads/{}", escape_path_segment(thread_id));
+        let rb = self.create_request(Method::DELETE, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<DeleteThreadResponse>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Adds a message to a thread.
+    pub async fn create_message(
+        &self,
+        thread_id: &str,
+        req: CreateMessageRequest,
+    ) -> Result<ThreadMessage> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/threads/{}/messages", escape_path_segment(thread_id));
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ThreadMessage>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of a thread's messages.
+    pub async fn list_messages(
+        &self,
+        thread_id: &str,
+        params: ListParams,
+    ) -> Result<Page<ThreadMessage>> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/threads/{}/messages", escape_path_segment(thread_id));
+        let rb = self.create_request(Method::GET, &path)?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<ThreadMessage>>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Retrieves a single message in a thread.
+    pub async fn retrieve_message(
+        &self,
+        thread_id: &str,
+        message_id: &str,
+    ) -> Result<ThreadMessage> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!(
+            "/v1/threads/{}/messages/{}",
+            escape_path_segment(thread_id),
+            escape_path_segment(message_id)
+        );
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<ThreadMessage>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Starts a run of an assistant on a thread.
+    pub async fn create_run(&self, thread_id: &str, req: CreateRunRequest) -> Result<Run> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/threads/{}/runs", escape_path_segment(thread_id));
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Run>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of a thread's runs.
+    pub async fn list_runs(&self, thread_id: &str, params: ListParams) -> Result<Page<Run>> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/threads/{}/runs", escape_path_segment(thread_id));
+        let rb = self.create_request(Method::GET, &path)?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<Run>>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Retrieves a single run on a thread.
+    pub async fn retrieve_run(&self, thread_id: &str, run_id: &str) -> Result<Run> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!(
+            "/v1/threads/{}/runs/{}",
+            escape_path_segment(thread_id),
+            escape_path_segment(run_id)
+        );
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Run>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Cancels a run that's in progress.
+    pub async fn cancel_run(&self, thread_id: &str, run_id: &str) -> Result<Run> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!(
+            "/v1/threads/{}/runs/{}/cancel",
+            escape_path_segment(thread_id),
+            escape_path_segment(run_id)
+        );
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Run>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Sends the outputs of the tool calls a run is waiting
+    /// on (i.e. its status is `requires_action`), so it can
+    /// continue.
+    pub async fn submit_tool_outputs(
+        &self,
+        thread_id: &str,
+        run_id: &str,
+        req: SubmitToolOutputsRequest,
+    ) -> Result<Run> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!(
+            "/v1/threads/{}/runs/{}/submit_tool_outputs",
+            escape_path_segment(thread_id),
+            escape_path_segment(run_id)
+        );
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Run>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Polls a run every `poll_interval` until it stops,
+    /// and returns it.
+    ///
+    /// A run has stopped when its status is terminal (see
+    /// `RunStatus::is_terminal`) or `requires_action`, since
+    /// a run waiting on tool outputs won't progress until
+    /// they're submitted. Returns an `Error::Timeout` if the
+    /// run is still going after `max_wait`.
+    pub async fn wait_for_run(
+        &self,
+        thread_id: &str,
+        run_id: &str,
+        poll_interval: Duration,
+        max_wait: Duration,
+    ) -> Result<Run> {
+        let deadline = Instant::now() + max_wait;
+        loop {
+            // Check the run's status...
+            let run = self.retrieve_run(thread_id, run_id).await?;
+            if run.status.is_terminal() || run.status == RunStatus::RequiresAction {
+                return Ok(run);
+            }
+
+            // Give up if the next poll would be too late...
+            if Instant::now() + poll_interval > deadline {
+                return Err(Error::Timeout(format!(
+                    "run {} was still {:?} after {:?}",
+                    run_id, run.status, max_wait
+                )));
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Creates a batch from an uploaded file of requests,
+    /// which are run asynchronously at a lower cost.
+    pub async fn create_batch(&self, req: CreateBatchRequest) -> Result<Batch> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::POST, "/v1/batches")?;
+
+        // Add the body...
+        let rb = rb.json(&req);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Batch>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists a page of the organization's batches.
+    pub async fn list_batches(&self, params: ListParams) -> Result<Page<Batch>> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let rb = self.create_request(Method::GET, "/v1/batches")?;
+        let rb = rb.query(&params);
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Page<Batch>>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Lists every one of the organization's batches whose
+    /// `metadata` has all of the given key-value pairs.
+    ///
+    /// The API can't filter batches by metadata, so every
+    /// page is fetched (with `page::list_all`) and filtered
+    /// client-side.
+    pub async fn list_batches_filtered(&self, metadata: &[(&str, &str)]) -> Result<Vec<Batch>> {
+        // Fetch every page...
+        let batches =
+            page::list_all(ListParams::default(), |params| self.list_batches(params)).await?;
+
+        // Keep the batches that match every pair...
+        Ok(batches
+            .into_iter()
+            .filter(|batch| {
+                metadata.iter().all(|(key, value)| {
+                    batch.metadata.get(*key).map(String::as_str) == Some(*value)
+                })
+            })
+            .collect())
+    }
+
+    /// Retrieves a single batch by its ID.
+    pub async fn retrieve_batch(&self, batch_id: &str) -> Result<Batch> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/batches/{}", escape_path_segment(batch_id));
+        let rb = self.create_request(Method::GET, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Batch>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Cancels a batch that's in progress. Its status will
+    /// be `cancelling` until in-flight requests finish.
+    pub async fn cancel_batch(&self, batch_id: &str) -> Result<Batch> {
+        // Wait for a free slot...
+        let _permit = self.acquire_permit().await?;
+
+        // Format the URL...
+        let path = format!("/v1/batches/{}/cancel", escape_path_segment(batch_id));
+        let rb = self.create_request(Method::POST, &path)?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status and parse the response as json...
+        let data = handle_response::<Batch>(res).await?;
+
+        // Return the data...
+        Ok(data)
+    }
+
+    /// Start building a chat completion request for the
+    /// given model, to be sent by this client.
+    ///
+    /// The model can be a name or a `dtypes::Model`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::{dtypes::Model, Client};
+    ///
+    /// # async fn run() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// let res = client.chat("gpt-4o").user("Hi!").send().await?;
+    /// let res = client.chat(Model::Gpt4oMini).user("Hi!").send().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn chat(&self, model: impl Into<String>) -> ChatBuilder<'_> {
+        ChatBuilder::new(self, model)
+    }
+
+    /// Sends `prompt` to `model` as a single user message,
+    /// and returns the text of the first choice.
+    ///
+    /// Use `chat` to set anything else about the request.
+    /// Returns `Error::NoContent` if the reply has no text
+    /// (e.g. it's a tool call).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::Client;
+    ///
+    /// # async fn run() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// let answer = client.ask("gpt-4o-mini", "What's 2 + 2?").await?;
+    /// println!("{}", answer);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn ask(&self, model: impl Into<String>, prompt: &str) -> Result<String> {
+        let res = self.chat(model).user(prompt).send().await?;
+        match res.first_content() {
+            Some(content) => Ok(content.to_string()),
+            None => Err(Error::NoContent(Box::new(res))),
+        }
+    }
+
+    /// Runs `base` once for every combination of the given
+    /// temperatures and seeds.
+    ///
+    /// At most `concurrency` requests (and at least one) are in
+    /// flight at once, on top of any `max_concurrency` limit set
+    /// on the client. Results are yielded as they complete (not
+    /// in grid order), each tagged with the temperature and seed
+    /// that produced it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    /// use ohairs::{dtypes::ChatCompletionRequest, Client};
+    ///
+    /// # async fn run() {
+    /// let client = Client::new("test");
+    /// let base = ChatCompletionRequest {
+    ///     model: "gpt-3.5-turbo".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let mut results = client.sweep(base, &[0.2, 0.8], &[1, 2, 3], 4);
+    /// while let Some((temperature, seed, res)) = results.next().await {
+    ///     println!("{} {} {:?}", temperature, seed, res.is_ok());
+    /// }
+    /// # }
+    /// ```
+    pub fn sweep<'a>(
+        &'a self,
+        base: ChatCompletionRequest,
+        temperatures: &[f64],
+        seeds: &[i64],
+        concurrency: usize,
+    ) -> impl Stream<Item = (f64, i64, Result<ChatCompletionObject>)> + Unpin + 'a {
+        // Build the grid of parameters...
+        let params: Vec<(f64, i64)> = temperatures
+            .iter()
+            .flat_map(|&temperature| seeds.iter().map(move |&seed| (temperature, seed)))
+            .collect();
+
+        // Run a request for each set of parameters...
+        let requests = futures_util::stream::iter(params).map(move |(temperature, seed)| {
+            let req = ChatCompletionRequest {
+                temperature: Some(temperature),
+                seed: Some(seed),
+                ..base.clone()
+            };
+            async move { (temperature, seed, self.create_chat_completion(req).await) }
+        });
+
+        // Bound the number in flight...
+        Box::pin(requests.buffer_unordered(concurrency.max(1)))
+    }
+
+    /// Creates a chat completion, streaming the response back
+    /// as a series of partial `ChatCompletionChunk`s.
+    ///
+    /// `req.stream` is always set to `true`. The stream ends
+    /// when the API sends `data: [DONE]`.
+    ///
+    /// If the API rejects the request (e.g. an unknown model),
+    /// the error is returned before any chunks are streamed.
+    ///
+    /// The response body is only read as the stream is polled,
+    /// so a slow consumer applies backpressure to the connection
+    /// rather than causing chunks to be buffered in memory.
+    ///
+    /// To cancel a completion part-way through (e.g. when the
+    /// user hits stop), drop the stream. This closes the
+    /// underlying connection, so the API stops generating (and
+    /// billing for) further tokens, and releases the client's
+    /// concurrency permit.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    /// use ohairs::{dtypes::ChatCompletionRequest, Client};
+    ///
+    /// # async fn run() -> ohairs::Result<()> {
+    /// let client = Client::new("test");
+    /// let req = ChatCompletionRequest {
+    ///     model: "gpt-3.5-turbo".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let mut stream = client.create_chat_completion_stream(req).await?;
+    /// while let Some(chunk) = stream.next().await {
+    ///     println!("{:?}", chunk?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_chat_completion_stream(
+        &self,
+        mut req: ChatCompletionRequest,
+    ) -> Result<impl Stream<Item = Result<ChatCompletionChunk>> + Unpin + Send> {
+        // Build the request...
+        req.stream = Some(true);
+        let rb = self.chat_completion_request(&req)?;
+
+        // Wait for a free slot (held until the stream is dropped)...
+        let permit = self.acquire_permit().await?;
+
+        // Send the request...
+        let res = self.send(rb).await?;
+
+        // Check the status before reading any events...
+        let res = check_status(res).await?;
+
+        // Parse the body as a stream of chunks...
+        Ok(chunk_stream(
+            res.bytes_stream(),
+            ChatCompletionChunk::parse_event,
+            permit,
+        ))
+    }
+
+    /// Creates a streamed chat completion, like
+    /// `create_chat_completion_stream`, but yields
+    /// `stream::StreamEvent`s rather than raw chunks.
+    ///
+    /// The first event is always a `StreamEvent::Start` with
+    /// the message's role, sent before any content.
+    pub async fn create_chat_completion_events(
+        &self,
+        req: ChatCompletionRequest,
+    ) -> Result<impl Stream<Item = Result<stream::StreamEvent>> + Unpin + Send> {
+        let chunks = self.create_chat_completion_stream(req).await?;
+        Ok(stream::stream_events(chunks))
+    }
+
+    /// Creates a chat completion, forwarding the streamed
+    /// chunks into `tx` from a spawned task.
+    ///
+    /// This suits consumers that live elsewhere (e.g. a UI
+    /// task) better than polling a stream. Errors, including
+    /// the API rejecting the request, are sent into the channel
+    /// as an `Err`. The task drops `tx` after `[DONE]` or the
+    /// first error, closing the channel. If the receiver is
+    /// dropped first, the task stops and the completion is
+    /// cancelled.
+    ///
+    /// Must be called from within a Tokio runtime.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::{dtypes::ChatCompletionRequest, Client};
+    /// use tokio::sync::mpsc;
+    ///
+    /// # async fn run() {
+    /// let client = Client::new("test");
+    /// let req = ChatCompletionRequest {
+    ///     model: "gpt-4o".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let (tx, mut rx) = mpsc::channel(16);
+    /// client.create_chat_completion_channel(req, tx);
+    /// while let Some(chunk) = rx.recv().await {
+    ///     println!("{:?}", chunk);
+    /// }
+    /// # }
+    /// ```
+    pub fn create_chat_completion_channel(
+        &self,
+        req: ChatCompletionRequest,
+        tx: mpsc::Sender<Result<ChatCompletionChunk>>,
+    ) -> JoinHandle<()> {
+        let client = self.clone();
+        tokio::spawn(async move {
+            // Start the stream...
+            let mut stream = match client.create_chat_completion_stream(req).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    let _ = tx.send(Err(err)).await;
+                    return;
+                }
+            };
+
+            // Forward each chunk, until the end, an error, or the receiver goes away...
+            while let Some(chunk) = stream.next().await {
+                let failed = chunk.is_err();
+                if tx.send(chunk).await.is_err() || failed {
+                    return;
+                }
+            }
+        })
+    }
+
+    /// Creates a chat completion, like
+    /// `create_chat_completion_channel`, but creates the
+    /// channel (holding up to `buffer` chunks) and returns its
+    /// receiver.
+    ///
+    /// Must be called from within a Tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer` is `0`, like `mpsc::channel`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ohairs::{dtypes::ChatCompletionRequest, Client};
+    ///
+    /// # async fn run() {
+    /// let client = Client::new("test");
+    /// let req = ChatCompletionRequest {
+    ///     model: "gpt-4o".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let mut rx = client.create_chat_completion_receiver(req, 16);
+    /// while let Some(chunk) = rx.recv().await {
+    ///     println!("{:?}", chunk);
+    /// }
+    /// # }
+    /// ```
+    pub fn create_chat_completion_receiver(
+        &self,
+        req: ChatCompletionRequest,
+        buffer: usize,
+    ) -> mpsc::Receiver<Result<ChatCompletionChunk>> {
+        let (tx, rx) = mpsc::channel(buffer);
+        self.create_chat_completion_channel(req, tx);
+        rx
+    }
+}
+
+/// Returns the cosine similarity of two vectors, or `0.0`
+/// if either has no magnitude.
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Escapes a value so it can be used as a single segment
+/// of a URL path.
+fn escape_path_segment(segment: &str) -> String {
+    utf8_percent_encode(segment, PATH_SEGMENT).to_string()
+}
+
+/// Returns the `OpenAI-Beta` header value needed by the
+/// endpoint at `path`, if any.
+fn beta_header(path: &str) -> Option<&'static str> {
+    ASSISTANTS_PATHS
+        .iter()
+        .any(|prefix| {
+            path.strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+        .then_some(ASSISTANTS_BETA)
+}
+
+/// Masks an API key for display, keeping only enough of
+/// the start to tell which kind of key it is (e.g. `sk-****`).
+fn mask_key(api_key: &str) -> String {
+    match api_key.get(..3) {
+        Some(prefix) if api_key.len() > 8 => format!("{}****", prefix),
+        _ => "****".to_string(),
+    }
+}
+
+/// Joins an API path (e.g. `/v1/models`) onto the base URL,
+/// keeping any path prefix the base URL has (e.g. a gateway
+/// that serves the API under `https://host/openai/`).
+///
+/// The path's `v1/` is swapped for `version_prefix`. It's
+/// dropped rather than repeated if the base URL already ends
+/// in the prefix, as is common for OpenAI-compatible servers
+/// (e.g. `http://localhost:11434/v1`).
+fn join_path(base_url: &Url, version_prefix: &str, path: &str) -> Result<Url> {
+    // Treat the base's path as a directory...
+    let mut base_url = base_url.clone();
+    if !base_url.path().ends_with('/') {
+        let dir = format!("{}/", base_url.path());
+        base_url.set_path(&dir);
+    }
+
+    // Make the path relative to it...
+    let path = path.trim_start_matches('/');
+    let Some(endpoint) = path.strip_prefix("v1/") else {
+        return Ok(base_url.join(path)?);
+    };
+
+    // ...under the version prefix, unless it's already there...
+    let version_prefix = version_prefix.trim_matches('/');
+    let has_prefix = base_url.path().ends_with(&format!("/{}/", version_prefix));
+    if version_prefix.is_empty() || has_prefix {
+        return Ok(base_url.join(endpoint)?);
+    }
+    Ok(base_url.join(&format!("{}/{}", version_prefix, endpoint))?)
+}
+
+/// Creates the multipart part for an uploaded file.
+fn file_part(file: Vec<u8>, filename: &str) -> reqwest::multipart::Part {
+    reqwest::multipart::Part::bytes(file).file_name(filename.to_string())
+}
+
+/// Builds the full URL for an API path, mapping it onto
+/// Azure's layout if needed.
+fn format_url(base_url: &str, version_prefix: &str, api_type: &ApiType, path: &str) -> Result<Url> {
+    let base_url = Url::parse(base_url)?;
+    match api_type {
+        ApiType::OpenAi => join_path(&base_url, version_prefix, path),
+        ApiType::Azure {
+            deployment,
+            api_version,
+        } => {
+            // Map the OpenAI path onto Azure's...
+            let endpoint = path.trim_start_matches('/');
+            let endpoint = endpoint.strip_prefix("v1/").unwrap_or(endpoint);
+            let path = if AZURE_DEPLOYMENT_ENDPOINTS.contains(&endpoint) {
+                format!(
+                    "/openai/deployments/{}/{}",
+                    escape_path_segment(deployment),
+                    endpoint
+                )
+            } else {
+                format!("/openai/{}", endpoint)
+            };
+
+            // Add the API version...
+            let mut url = join_path(&base_url, version_prefix, &path)?;
+            url.query_pairs_mut()
+                .append_pair("api-version", api_version);
+            Ok(url)
+        }
+    }
+}
+
+/// Returns the response unchanged if it has a success status,
+/// otherwise reads the body and returns it as an `Error::Api`.
+async fn check_status(res: Response) -> Result<Response> {
+    // Is it a success?
+    let status = res.status();
+    if status.is_success() {
+        return Ok(res);
+    }
+
+    // Read the body for the error message...
+    let body = res.text().await?;
+    Err(api_error(status, &body))
+}
+
+/// The most characters of a non-JSON error body (e.g. a
+/// proxy's HTML error page) kept in an `Error::Api`.
+const MAX_ERROR_BODY_CHARS: usize = 512;
+
+/// Builds the error for a non-success response from its
+/// status and body, parsing the error envelope and falling
+/// back to a snippet of the raw body.
+fn api_error(status: reqwest::StatusCode, body: &str) -> Error {
+    let error = match serde_json::from_str::<ApiErrorEnvelope>(body) {
+        Ok(envelope) => envelope.error,
+        Err(_) => ApiError {
+            message: match body.trim() {
+                "" => status
+                    .canonical_reason()
+                    .unwrap_or("Unknown error")
+                    .to_string(),
+                body => body_snippet(body),
+            },
+            ..Default::default()
+        },
+    };
+    Error::Api {
+        status: status.as_u16(),
+        error,
+    }
+}
+
+/// Collapses the whitespace in a raw response body and
+/// truncates it to `MAX_ERROR_BODY_CHARS`, so it's readable
+/// in an error message.
+fn body_snippet(body: &str) -> String {
+    let collapsed = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    match collapsed.char_indices().nth(MAX_ERROR_BODY_CHARS) {
+        Some((end, _)) => format!("{}...", &collapsed[..end]),
+        None => collapsed,
+    }
+}
+
+/// Checks the status of a response and parses its
+/// body as json.
+async fn handle_response<T: DeserializeOwned>(res: Response) -> Result<T> {
+    // Check the status...
+    let res = check_status(res).await?;
+
+    // Parse the response as json...
+    let body = res.bytes().await?;
+    let data = serde_json::from_slice::<T>(&body)?;
+    Ok(data)
+}
+
+/// The state of a `chunk_stream`.
+struct ChunkStreamState<S, T> {
+    /// The response body.
+    body: S,
+
+    /// Parses each event into a chunk.
+    parse: fn(&str) -> Result<ChunkEvent<T>>,
+
+    /// Splits the body into events.
+    decoder: SseDecoder,
+
+    /// Has the body been fully read?
+    eof: bool,
+
+    /// Has the stream ended (either `[DONE]` or an error)?
+    finished: bool,
+
+    /// The client's concurrency permit, if any, which is
+    /// released when the stream is dropped.
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+/// Turns a response body, read as a stream of byte frames,
+/// into a stream of chunks, each event parsed by `parse`.
+///
+/// See `SseDecoder` for how frames are split into events.
+fn chunk_stream<S, B, E, T>(
+    body: S,
+    parse: fn(&str) -> Result<ChunkEvent<T>>,
+    permit: Option<OwnedSemaphorePermit>,
+) -> impl Stream<Item = Result<T>> + Unpin + Send
+where
+    S: Stream<Item = std::result::Result<B, E>> + Unpin + Send,
+    B: AsRef<[u8]>,
+    Error: From<E>,
+    T: Send,
+{
+    let state = ChunkStreamState {
+        body,
+        parse,
+        decoder: SseDecoder::new(),
+        eof: false,
+        finished: false,
+        _permit: permit,
+    };
+    Box::pin(futures_util::stream::unfold(
+        state,
+        |mut state| async move {
+            loop {
+                // Has the stream already ended?
+                if state.finished {
+                    return None;
+                }
+
+                // Is there a complete event?
+                if let Some(event) = state.decoder.next_event() {
+                    let res = event.and_then(|event| (state.parse)(&event));
+                    match res {
+                        Ok(ChunkEvent::Chunk(chunk)) => return Some((Ok(chunk), state)),
+                        Ok(ChunkEvent::Done) => return None,
+                        Ok(ChunkEvent::KeepAlive) => continue,
+                        Err(err) => {
+                            state.finished = true;
+                            return Some((Err(err), state));
+                        }
+                    }
+                }
+
+                // Was that the last of the body?
+                if state.eof {
+                    return None;
+                }
+
+                // Otherwise, read the next frame...
+                match state.body.next().await {
+                    Some(Ok(frame)) => state.decoder.feed(frame.as_ref()),
+                    Some(Err(err)) => {
+                        state.finished = true;
+                        return Some((Err(err.into()), state));
+                    }
+                    None => {
+                        state.eof = true;
+                        state.decoder.finish();
+                    }
+                }
+            }
+        },
+    ))
+}
+
+/// A builder for configuring a `Client`.
+#[derive(Default, Clone)]
+pub struct ClientBuilder {
+    api_key: Option<String>,
+    org_id: Option<String>,
+    project_id: Option<String>,
+    base_url: Option<String>,
+    version_prefix: Option<String>,
+    api_type: ApiType,
+    timeout: Option<Duration>,
+    proxies: Vec<reqwest::Proxy>,
+    proxy_urls: Vec<String>,
+    no_proxy: Option<String>,
+    req_client: Option<reqwest::Client>,
+    max_concurrency: Option<usize>,
+    retry: RetryPolicy,
+    idempotency_key: Option<IdempotencyKeyFn>,
+    validate_requests: bool,
+    default_headers: HeaderMap,
+    hooks: Hooks,
+}
+
+impl fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("api_key", &self.api_key.as_deref().map(mask_key))
+            .field("org_id", &self.org_id)
+            .field("project_id", &self.project_id)
+            .field("base_url", &self.base_url)
+            .field("version_prefix", &self.version_prefix)
+            .field("api_type", &self.api_type)
+            .field("timeout", &self.timeout)
+            .field("proxies", &(self.proxies.len() + self.proxy_urls.len()))
+            .field("no_proxy", &self.no_proxy)
+            .field("max_concurrency", &self.max_concurrency)
+            .field("retry", &self.retry)
+            .field("validate_requests", &self.validate_requests)
+            .field("default_headers", &hooks::redact(&self.default_headers))
+            .field("hooks", &self.hooks)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ClientBuilder {
+    /// Set the API key used to authenticate requests.
+    pub fn api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    /// Set the organization ID sent with each request.
+    pub fn org_id(mut self, org_id: &str) -> Self {
+        self.org_id = Some(org_id.to_string());
+        self
+    }
+
+    /// Set the project ID sent with each request (for
+    /// project-scoped API keys).
+    pub fn project_id(mut self, project_id: &str) -> Self {
+        self.project_id = Some(project_id.to_string());
+        self
+    }
+
+    /// Set the base URL requests are sent to.
+    ///
+    /// It may include a path prefix (e.g. a gateway serving
+    /// the API under `https://host/openai/`), which is kept.
+    /// A base ending in `/v1` is also accepted.
+    ///
+    /// Defaults to `BASE_URL`.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// Set the version prefix the API's paths are sent
+    /// under, or `""` for none (for servers that serve the
+    /// API at the root of `base_url`, or under a different
+    /// version).
+    ///
+    /// A base URL that already ends in the prefix (e.g.
+    /// `http://localhost:11434/v1`) doesn't need this.
+    ///
+    /// Defaults to `VERSION_PREFIX`.
+    pub fn version_prefix(mut self, version_prefix: &str) -> Self {
+        self.version_prefix = Some(version_prefix.to_string());
+        self
+    }
+
+    /// Talk to the Azure OpenAI Service, sending model-specific
+    /// requests to the given deployment.
+    ///
+    /// The `base_url` should be set to your resource's endpoint.
+    pub fn azure(mut self, deployment: &str, api_version: &str) -> Self {
+        self.api_type = ApiType::Azure {
+            deployment: deployment.to_string(),
+            api_version: api_version.to_string(),
+        };
+        self
+    }
+
+    /// Set a timeout for each request, from when it starts
+    /// connecting until the response body has been read.
+    ///
+    /// Defaults to no timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send requests through the given proxy. It can be
+    /// limited to `http` or `https` URLs with
+    /// `reqwest::Proxy::http` or `reqwest::Proxy::https`.
+    ///
+    /// Setting any proxy turns off the default of reading one
+    /// from the `HTTP_PROXY`/`HTTPS_PROXY` environment
+    /// variables.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::{reqwest::Proxy, Client};
+    ///
+    /// # fn main() -> ohairs::Result<()> {
+    /// let client = Client::builder()
+    ///     .api_key("test")
+    ///     .proxy(Proxy::https("http://proxy.internal:3128")?)
+    ///     .no_proxy("localhost,.internal")
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Send every request (`http` and `https`) through the
+    /// proxy at the given URL, e.g. `http://proxy.internal:3128`.
+    ///
+    /// The URL is parsed when the client is built. See `proxy`.
+    pub fn proxy_url(mut self, url: &str) -> Self {
+        self.proxy_urls.push(url.to_string());
+        self
+    }
+
+    /// Bypass the proxies for the given hosts, as a
+    /// comma-separated list (in the `NO_PROXY` format, e.g.
+    /// `localhost,10.0.0.0/8,.internal`).
+    pub fn no_proxy(mut self, hosts: &str) -> Self {
+        self.no_proxy = Some(hosts.to_string());
+        self
+    }
+
+    /// Send requests using the given `reqwest::Client`, rather
+    /// than one built from the builder's settings.
+    ///
+    /// This can't be combined with settings that configure the
+    /// inner client (`timeout`, `proxy`, `proxy_url` and
+    /// `no_proxy`), since those have to be applied when the
+    /// `reqwest::Client` is built. `build` returns an
+    /// `Error::Config` if any of them are set.
+    pub fn http_client(mut self, req_client: reqwest::Client) -> Self {
+        self.req_client = Some(req_client);
+        self
+    }
+
+    /// Limit the number of requests the client will have in
+    /// flight at once. Additional requests wait until one of
+    /// the in-flight requests finishes.
+    ///
+    /// This only bounds requests made through the built client
+    /// instance. Separate clients (even with the same API key)
+    /// don't share a limit.
+    ///
+    /// Must be at least `1`, or `build` returns an
+    /// `Error::Config`.
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = Some(max_concurrency);
+        self
+    }
+
+    /// Retry requests that fail with a transient error
+    /// (`429` or a `5xx`) up to `max_retries` times.
+    ///
+    /// Defaults to `0` (no retries).
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.retry.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry, which doubles
+    /// with each further retry up to `max_delay`.
+    ///
+    /// A `Retry-After` header on the failed response takes
+    /// precedence over these delays.
+    ///
+    /// Defaults to 500ms and 8s.
+    pub fn retry_delay(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.retry.base_delay = base_delay;
+        self.retry.max_delay = max_delay;
+        self
+    }
+
+    /// Override how idempotency keys are generated (e.g. to
+    /// make them predictable in tests).
+    ///
+    /// When retries are enabled, each `POST` request is sent
+    /// with a new `Idempotency-Key` header, which is kept
+    /// when the request is retried. This way the API can
+    /// ignore a retry whose original attempt succeeded but
+    /// whose response was lost. Defaults to a random UUID.
+    pub fn idempotency_key(
+        mut self,
+        generate: impl Fn() -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.idempotency_key = Some(Arc::new(generate));
+        self
+    }
+
+    /// Check chat completion requests with
+    /// `ChatCompletionRequest::validate` before sending them,
+    /// returning an `Error::Validation` without a round-trip
+    /// if they're invalid.
+    ///
+    /// Defaults to `false`.
+    pub fn validate_requests(mut self, validate_requests: bool) -> Self {
+        self.validate_requests = validate_requests;
+        self
+    }
+
+    /// Set headers to send with every request (e.g. a
+    /// `User-Agent`, or a header required by a proxy).
+    ///
+    /// These can't override the auth header, or the
+    /// `OpenAI-Organization` and `OpenAI-Project` headers if
+    /// `org_id` and `project_id` are set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::Client;
+    /// use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+    ///
+    /// # fn main() -> ohairs::Result<()> {
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert(USER_AGENT, HeaderValue::from_static("my-app/1.0"));
+    /// headers.insert("x-my-org", HeaderValue::from_static("research"));
+    ///
+    /// let client = Client::builder()
+    ///     .api_key("test")
+    ///     .default_headers(headers)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers = headers;
+        self
+    }
+
+    /// Set a callback to run before each request is sent
+    /// (including retries), e.g. to log it.
+    ///
+    /// The callback sees the request's method, URL, headers
+    /// and body, with the auth headers redacted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::Client;
+    ///
+    /// # fn main() -> ohairs::Result<()> {
+    /// let client = Client::builder()
+    ///     .api_key("test")
+    ///     .on_request(|req| {
+    ///         eprintln!("{} {} {}", req.method, req.url, req.body.as_deref().unwrap_or(""));
+    ///     })
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_request(mut self, hook: impl Fn(&RequestLog) + Send + Sync + 'static) -> Self {
+        self.hooks.on_request = Some(Arc::new(hook));
+        self
+    }
+
+    /// Set a callback to run after each response is
+    /// received, e.g. to log it.
+    ///
+    /// Setting this means each response body is read in
+    /// full before it's parsed (streamed responses are left
+    /// alone, and their body isn't shown to the callback).
+    pub fn on_response(mut self, hook: impl Fn(&ResponseLog) + Send + Sync + 'static) -> Self {
+        self.hooks.on_response = Some(Arc::new(hook));
+        self
+    }
+
+    /// Build the client.
+    ///
+    /// Returns an `Error::MissingApiKey` if no (non-empty) API
+    /// key was set, rather than building a client whose
+    /// requests would all fail with a `401`.
+    pub fn build(self) -> Result<Client> {
+        let api_key = match self.api_key {
+            Some(api_key) if !api_key.trim().is_empty() => api_key,
+            _ => return Err(Error::MissingApiKey),
+        };
+
+        // A limit of zero would block every request forever...
+        if self.max_concurrency == Some(0) {
+            return Err(Error::Config(
+                "max_concurrency must be at least 1".to_string(),
+            ));
+        }
+
+        // Use the given HTTP client, or build one...
+        let req_client = match self.req_client {
+            Some(_) if self.timeout.is_some() => {
+                return Err(Error::Config(
+                    "A timeout can't be set when using a custom HTTP client".to_string(),
+                ))
+            }
+            Some(_)
+                if !self.proxies.is_empty()
+                    || !self.proxy_urls.is_empty()
+                    || self.no_proxy.is_some() =>
+            {
+                return Err(Error::Config(
+                    "A proxy can't be set when using a custom HTTP client".to_string(),
+                ))
+            }
+            Some(req_client) => req_client,
+            None => {
+                let mut req_client = reqwest::Client::builder();
+                if let Some(timeout) = self.timeout {
+                    req_client = req_client.timeout(timeout);
+                }
+
+                // Add the proxies, minus any excluded hosts...
+                let mut proxies = self.proxies;
+                for url in &self.proxy_urls {
+                    let proxy = reqwest::Proxy::all(url.as_str()).map_err(|err| {
+                        Error::Config(format!("Invalid proxy URL {:?}: {}", url, err))
+                    })?;
+                    proxies.push(proxy);
+                }
+                let no_proxy = self.no_proxy.as_deref().and_then(NoProxy::from_string);
+                for proxy in proxies {
+                    req_client = req_client.proxy(proxy.no_proxy(no_proxy.clone()));
+                }
+                req_client.build()?
+            }
+        };
+
+        // Build the client...
+        let mut client = Client::with_client(api_key.as_str(), req_client);
+        client.org_id = self.org_id;
+        client.project_id = self.project_id;
+        client.api_type = self.api_type;
+        if let Some(base_url) = self.base_url {
+            client.base_url = base_url;
+        }
+        if let Some(version_prefix) = self.version_prefix {
+            client.version_prefix = version_prefix;
+        }
+        client.concurrency = self.max_concurrency.map(|n| Arc::new(Semaphore::new(n)));
+        client.retry = self.retry;
+        client.idempotency_key = self.idempotency_key;
+        client.validate_requests = self.validate_requests;
+        client.default_headers = self.default_headers;
+        client.hooks = self.hooks;
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn it_works() -> Result<()> {
+        let _ = Client::new("test");
+        Ok(())
+    }
+
+    /// Starts a mock server that answers chat completions
+    /// with a canned response.
+    #[cfg(feature = "mock")]
+    async fn chat_completion_server() -> Result<mock::MockServer> {
+        let server = mock::MockServer::start().await?;
+        let res = ChatCompletionObject {
+            id: "chatcmpl-123".to_string(),
+            object: "chat.completion".to_string(),
+            created: 1677652288,
+            model: "gpt-3.5-turbo".to_string(),
+            choices: vec![dtypes::ChatCompletionChoice {
+                message: dtypes::ChatCompletionMessage {
+                    role: dtypes::Role::Assistant,
+                    content: Some("Hello!".into()),
+                    ..Default::default()
+                },
+                finish_reason: dtypes::FinishReason::Stop,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        server.mock(
+            "POST",
+            "/v1/chat/completions",
+            mock::MockResponse::json(200, &res),
+        );
+        Ok(server)
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn create_chat_completion_sync() -> Result<()> {
+        // Create a client...
+        let server = chat_completion_server().await?;
+        let client = server.client();
+
+        let req = ChatCompletionRequest {
+            model: "gpt-3.5-turbo".to_string(),
+            stream: Some(false),
+            messages: vec![],
+            ..Default::default()
+        };
+
+        let res = client.create_chat_completion(req).await?;
+        assert_eq!(res.first_content(), Some("Hello!"));
+        Ok(())
+    }
+
+    #[cfg(feature = "mock")]
+    #[tokio::test]
+    async fn create_chat_completion_async() -> Result<()> {
+        // Create a client...
+        let server = chat_completion_server().await?;
+        let client = server.client();
+
+        let req = ChatCompletionRequest {
+            model: "gpt-3.5-turbo".to_string(),
+            stream: Some(false),
+            messages: vec![],
+            ..Default::default()
+        };
+
+        // Send several requests at once...
+        let (a, b) = tokio::join!(
+            client.create_chat_completion(req.clone()),
+            client.create_chat_completion(req)
+        );
+        assert_eq!(a?.first_content(), Some("Hello!"));
+        assert_eq!(b?.first_content(), Some("Hello!"));
+        Ok(())
+    }
+
+    fn response(status: u16, body: &str) -> Response {
+        http::Response::builder()
+            .status(status)
+            .body(body.to_string())
+            .unwrap()
+            .into()
+    }
+
+    #[tokio::test]
+    async fn handle_response_checks_status() {
+        // A success is parsed as json...
+        let res = response(200, r#"{"object": "list", "data": []}"#);
+        let data = handle_response::<ListModelsResponse>(res).await.unwrap();
+        assert_eq!(data.object, "list");
+
+        // An error surfaces the status and body...
+        let res = response(500, "upstream connect error");
+        let err = handle_response::<ListModelsResponse>(res)
+            .await
+            .unwrap_err();
+        match err {
+            Error::Api { status, error } => {
+                assert_eq!(status, 500);
+                assert_eq!(error.message, "upstream connect error");
+                assert_eq!(error.code, None);
+            }
+            err => panic!("expected an API error, got {:?}", err),
+        }
+
+        // An empty error body falls back to the status reason...
+        let res = response(429, "");
+        let err = handle_response::<ListModelsResponse>(res)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Api { status: 429, ref error } if error.message == "Too Many Requests"
+        ));
+    }
+
+    #[tokio::test]
+    async fn handle_response_parses_error_envelope() {
+        let body = r#"{
+            "error": {
+                "message": "You exceeded your current quota, please check your plan and billing details.",
+                "type": "insufficient_quota",
+                "param": null,
+                "code": "insufficient_quota"
+            }
+        }"#;
+        let res = response(429, body);
+        let err = handle_response::<ChatCompletionObject>(res)
+            .await
+            .unwrap_err();
+        match err {
+            Error::Api { status, error } => {
+                assert_eq!(status, 429);
+                assert_eq!(error.code.as_deref(), Some("insufficient_quota"));
+                assert_eq!(error.type_.as_deref(), Some("insufficient_quota"));
+                assert!(error.message.starts_with("You exceeded your current quota"));
+            }
+            err => panic!("expected an API error, got {:?}", err),
+        }
+    }
+
+    /// Collects the chunks parsed from a body split into the
+    /// given frames.
+    async fn collect_chunks(frames: Vec<&'static str>) -> Vec<crate::Result<ChatCompletionChunk>> {
+        let body = futures_util::stream::iter(frames.into_iter().map(Ok::<_, Error>));
+        chunk_stream(body, ChatCompletionChunk::parse_event, None)
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn chunk_stream_handles_frame_boundaries() {
+        let first = r#"data: {"id": "a", "object": "", "created": 1, "model": "", "choices": []}"#;
+        let second = r#"data: {"id": "b", "object": "", "created": 2, "model": "", "choices": []}"#;
+
+        // Define cases to test...
+        let full = format!("{}\n\n{}\n\ndata: [DONE]\n\n", first, second);
+        let full: &'static str = Box::leak(full.into_boxed_str());
+        let cases: Vec<Vec<&'static str>> = vec![
+            // Everything in one frame...
+            vec![full],
+            // One event split across frames...
+            vec![&full[..10], &full[10..40], &full[40..]],
+            // A frame per byte...
+            (0..full.len()).map(|i| &full[i..i + 1]).collect(),
+            // No trailing newline after [DONE]...
+            vec![full.trim_end()],
+        ];
+
+        // Iterate over cases and test...
+        for (i, frames) in cases.into_iter().enumerate() {
+            let ids: Vec<String> = collect_chunks(frames)
+                .await
+                .into_iter()
+                .map(|chunk| chunk.expect("expected chunk to parse").id)
+                .collect();
+            assert_eq!(ids, vec!["a", "b"], "case {} didn't match", i);
+        }
+    }
+
+    #[tokio::test]
+    async fn chunk_stream_stops_at_done_and_errors() {
+        // Nothing after [DONE] is read...
+        let chunks = collect_chunks(vec!["data: [DONE]\n\n", "data: {\n\n"]).await;
+        assert!(chunks.is_empty());
+
+        // A malformed event ends the stream with an error...
+        let chunks = collect_chunks(vec!["data: {\n\n", "data: [DONE]\n\n"]).await;
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].is_err());
+
+        // A trailing event without a newline is still parsed...
+        let chunks = collect_chunks(vec![
+            r#"data: {"id": "a", "object": "", "created": 1, "model": "", "choices": []}"#,
+        ])
+        .await;
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].is_ok());
+    }
+
+    #[tokio::test]
+    async fn chunk_stream_skips_keep_alive_comments() {
+        let chunks = collect_chunks(vec![
+            ": keep-alive\n\n",
+            r#"data: {"id": "a", "object": "", "created": 1, "model": "", "choices": []}"#,
+            "\n\n:\n\ndata: [DONE]\n\n",
+        ])
+        .await;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].as_ref().unwrap().id, "a");
+    }
+
+    #[test]
+    fn escape_path_segment_keeps_ids_in_one_segment() -> Result<()> {
+        let client = Client::new("test");
+
+        // Define cases to test...
+        let cases = vec![
+            ("gpt-4", "https://api.openai.com/v1/models/gpt-4"),
+            (
+                "ft:gpt-3.5-turbo:my-org:custom_suffix:id",
+                "https://api.openai.com/v1/models/ft:gpt-3.5-turbo:my-org:custom_suffix:id",
+            ),
+            ("a/b?c#d", "https://api.openai.com/v1/models/a%2Fb%3Fc%23d"),
+            ("a b%", "https://api.openai.com/v1/models/a%20b%25"),
+        ];
+
+        // Iterate over cases and test...
+        for (i, (model_id, expected)) in cases.into_iter().enumerate() {
+            let path = format!("/v1/models/{}", escape_path_segment(model_id));
+            let url = client.format_url(&path)?;
+            assert_eq!(url.as_str(), expected, "case {} didn't match", i);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn format_url_keeps_base_path_prefix() -> Result<()> {
+        // Define cases to test...
+        let cases = vec![
+            (
+                "https://api.openai.com/",
+                "https://api.openai.com/v1/models",
+            ),
+            ("https://api.openai.com", "https://api.openai.com/v1/models"),
+            ("https://host/openai/", "https://host/openai/v1/models"),
+            ("https://host/openai", "https://host/openai/v1/models"),
+            ("https://host/v1/", "https://host/v1/models"),
+            (
+                "http://localhost:11434/v1",
+                "http://localhost:11434/v1/models",
+            ),
+            (
+                "https://openrouter.ai/api/v1",
+                "https://openrouter.ai/api/v1/models",
+            ),
+        ];
+
+        // Iterate over cases and test...
+        for (base_url, expected) in cases {
+            let url = format_url(base_url, VERSION_PREFIX, &ApiType::OpenAi, "/v1/models")?;
+            assert_eq!(url.as_str(), expected, "base URL {}", base_url);
+        }
+
+        // Azure's paths are joined the same way...
+        let api_type = ApiType::Azure {
+            deployment: "my-gpt-4o".to_string(),
+            api_version: "2024-02-01".to_string(),
+        };
+        let url = format_url(
+            "https://gateway/azure",
+            VERSION_PREFIX,
+            &api_type,
+            "/v1/models",
+        )?;
+        assert_eq!(
+            url.as_str(),
+            "https://gateway/azure/openai/models?api-version=2024-02-01"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn builder_default_headers() -> Result<()> {
+        let mut headers = HeaderMap::new();
+        headers.insert("user-agent", "my-app/1.0".parse()?);
+        headers.insert("x-my-org", "research".parse()?);
+        headers.insert("authorization", "Bearer not-the-key".parse()?);
+        headers.insert("openai-organization", "org-other".parse()?);
+        headers.insert("openai-project", "proj-other".parse()?);
+        let client = Client::builder()
+            .api_key("sk-test")
+            .org_id("org-123")
+            .project_id("proj-123")
+            .default_headers(headers)
+            .build()?;
+
+        // The defaults are sent...
+        let req = client.create_request(Method::GET, "/v1/models")?.build()?;
+        assert_eq!(req.headers()["user-agent"], "my-app/1.0");
+        assert_eq!(req.headers()["x-my-org"], "research");
+
+        // ...but the auth and org headers win...
+        let auth: Vec<_> = req.headers().get_all("authorization").iter().collect();
+        assert_eq!(auth, vec!["Bearer sk-test"]);
+        let org: Vec<_> = req
+            .headers()
+            .get_all("openai-organization")
+            .iter()
+            .collect();
+        assert_eq!(org, vec!["org-123"]);
+        let project: Vec<_> = req.headers().get_all("openai-project").iter().collect();
+        assert_eq!(project, vec!["proj-123"]);
+        Ok(())
+    }
+
+    #[test]
+    fn azure_urls_and_auth() -> Result<()> {
+        let client = Client::new_azure(
+            "https://my-resource.openai.azure.com/",
+            "azure-key",
+            "my-gpt-4o",
+            "2024-02-01",
+        );
+
+        // Define cases to test...
+        let cases = vec![
+            (
+                "/v1/chat/completions",
+                "https://my-resource.openai.azure.com/openai/deployments/my-gpt-4o/chat/completions?api-version=2024-02-01",
+            ),
+            (
+                "/v1/embeddings",
+                "https://my-resource.openai.azure.com/openai/deployments/my-gpt-4o/embeddings?api-version=2024-02-01",
+            ),
+            (
+                "/v1/images/edits",
+                "https://my-resource.openai.azure.com/openai/deployments/my-gpt-4o/images/edits?api-version=2024-02-01",
+            ),
+            (
+                "/v1/images/variations",
+                "https://my-resource.openai.azure.com/openai/deployments/my-gpt-4o/images/variations?api-version=2024-02-01",
+            ),
+            (
+                "/v1/models",
+                "https://my-resource.openai.azure.com/openai/models?api-version=2024-02-01",
+            ),
+        ];
+
+        // Iterate over cases and test...
+        for (i, (path, expected)) in cases.into_iter().enumerate() {
+            let url = client.format_url(path)?;
+            assert_eq!(url.as_str(), expected, "case {} didn't match", i);
+        }
+
+        // The key is sent in the `api-key` header...
+        let req = client
+            .create_request(Method::POST, "/v1/chat/completions")?
+            .build()?;
+        assert_eq!(req.headers()["api-key"], "azure-key");
+        assert!(req.headers().get("authorization").is_none());
+
+        // ...while OpenAI uses a bearer token...
+        let req = Client::new("sk-test")
+            .create_request(Method::POST, "/v1/chat/completions")?
+            .build()?;
+        assert_eq!(req.headers()["authorization"], "Bearer sk-test");
+        assert!(req.headers().get("api-key").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn from_lookup_reads_variables() -> Result<()> {
+        // Missing and empty keys are errors naming the variable...
+        for key in [None, Some(""), Some("  ")] {
+            let res = Client::from_lookup(|name| match name {
+                API_KEY_ENV_VAR => key.map(str::to_string),
+                _ => None,
+            });
+            let err = res.expect_err("expected a missing key to be an error");
+            assert!(matches!(err, Error::MissingEnvVar(ref name) if name == "OPENAI_API_KEY"));
+            assert!(err.to_string().contains("OPENAI_API_KEY"));
+        }
+
+        // Only the key is required...
+        let client = Client::from_lookup(|name| match name {
+            API_KEY_ENV_VAR => Some("sk-test".to_string()),
+            _ => None,
+        })?;
+        assert_eq!(client.api_key, "sk-test");
+        assert_eq!(client.org_id, None);
+        assert_eq!(client.project_id, None);
+        assert_eq!(client.base_url, BASE_URL);
+
+        // The optional settings are applied when set...
+        let client = Client::from_lookup(|name| match name {
+            API_KEY_ENV_VAR => Some("sk-test".to_string()),
+            ORG_ID_ENV_VAR => Some("org-123".to_string()),
+            PROJECT_ID_ENV_VAR => Some("proj-123".to_string()),
+            BASE_URL_ENV_VAR => Some("http://localhost:1323".to_string()),
+            _ => None,
+        })?;
+        assert_eq!(client.org_id.as_deref(), Some("org-123"));
+        assert_eq!(client.project_id.as_deref(), Some("proj-123"));
+        assert_eq!(client.base_url, "http://localhost:1323");
+        Ok(())
+    }
+
+    #[test]
+    fn builder_sets_fields() -> Result<()> {
+        // Check the defaults match `Client::new`...
+        let client = Client::builder().api_key("test").build()?;
+        assert_eq!(client.api_key, "test");
+        assert_eq!(client.base_url, BASE_URL);
+        assert_eq!(client.org_id, None);
+
+        // Check the fields are set...
+        let client = Client::builder()
+            .api_key("test")
+            .org_id("org-123")
+            .project_id("proj-123")
+            .base_url("http://localhost:1323")
+            .build()?;
+        assert_eq!(client.org_id.as_deref(), Some("org-123"));
+        assert_eq!(client.project_id.as_deref(), Some("proj-123"));
+        assert_eq!(client.base_url, "http://localhost:1323");
+        Ok(())
+    }
+
+    #[test]
+    fn builder_http_client_conflicts_with_inner_settings() {
+        let res = Client::builder()
+            .api_key("test")
+            .http_client(reqwest::Client::new())
+            .build();
+        assert!(res.is_ok());
+
+        // Define cases to test...
+        let cases: Vec<fn(ClientBuilder) -> ClientBuilder> = vec![
+            |b| b.timeout(Duration::from_secs(1)),
+            |b| b.proxy_url("http://proxy.internal:3128"),
+            |b| b.no_proxy("localhost"),
+        ];
+
+        // Iterate over cases and test...
+        for (i, configure) in cases.into_iter().enumerate() {
+            let builder = Client::builder()
+                .api_key("test")
+                .http_client(reqwest::Client::new());
+            let res = configure(builder).build();
+            assert!(matches!(res, Err(Error::Config(_))), "case {}", i);
+        }
+    }
+
+    #[tokio::test]
+    async fn builder_timeout_applies_to_requests() -> Result<()> {
+        // Start a server that accepts connections but never responds...
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let mut conns = Vec::new();
+            while let Ok((conn, _)) = listener.accept().await {
+                conns.push(conn);
+            }
+        });
+
+        // The request should time out rather than hang...
+        let client = Client::builder()
+            .api_key("test")
+            .base_url(&format!("http://{}", addr))
+            .timeout(Duration::from_millis(100))
+            .build()?;
+        let err = client.list_models().await.unwrap_err();
+        assert!(matches!(err, Error::Http(ref err) if err.is_timeout()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn builder_max_concurrency_limits_permits() -> Result<()> {
+        // Without a limit, no permit is needed...
+        let client = Client::new("test");
+        assert!(client.acquire_permit().await?.is_none());
+
+        // With a limit, permits are handed out until exhausted...
+        let client = Client::builder()
+            .api_key("test")
+            .max_concurrency(2)
+            .build()?;
+        let a = client.acquire_permit().await?;
+        let _b = client.acquire_permit().await?;
+        assert!(a.is_some());
+        assert_eq!(client.concurrency.as_ref().unwrap().available_permits(), 0);
+
+        // ...and returned when dropped...
+        drop(a);
+        assert_eq!(client.concurrency.as_ref().unwrap().available_permits(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn builder_rejects_zero_max_concurrency() {
+        let err = Client::builder()
+            .api_key("sk-test")
+            .max_concurrency(0)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, Error::Config(_)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn sweep_covers_every_combination() -> Result<()> {
+        // Create a client that can't reach a server...
+        let mut client = Client::new("test");
+        client.base_url = "http://localhost:1".to_string();
+
+        // Run the sweep...
+        let base = ChatCompletionRequest {
+            model: "gpt-3.5-turbo".to_string(),
+            ..Default::default()
+        };
+        let mut params: Vec<(f64, i64)> = client
+            .sweep(base, &[0.0, 1.0], &[1, 2, 3], 2)
+            .map(|(temperature, seed, res)| {
+                assert!(res.is_err());
+                (temperature, seed)
+            })
+            .collect()
+            .await;
+
+        // Every combination should be present exactly once...
+        params.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            params,
+            vec![(0.0, 1), (0.0, 2), (0.0, 3), (1.0, 1), (1.0, 2), (1.0, 3)]
+        );
+
+        // A concurrency of zero still makes progress...
+        let base = ChatCompletionRequest {
+            model: "gpt-3.5-turbo".to_string(),
+            ..Default::default()
+        };
+        let count = client.sweep(base, &[0.0], &[1, 2], 0).count().await;
+        assert_eq!(count, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_models_cached_reuses_fresh_result() -> Result<()> {
+        // Create a client that can't reach a server...
+        let mut client = Client::new("test");
+        client.base_url = "http://localhost:1".to_string();
+
+        // Seed the cache...
+        let models = ListModelsResponse {
+            object: "list".to_string(),
+            data: vec![],
+        };
+        *client.models_cache.write().unwrap() = Some((Instant::now(), models.clone()));
+
+        // A fresh cache should be returned without a request...
+        let res = client.list_models_cached(Duration::from_secs(60)).await?;
+        assert_eq!(res, models);
+
+        // A stale cache should trigger a reload (which fails here)...
+        let res = client.list_models_cached(Duration::ZERO).await;
+        assert!(res.is_err());
+        Ok(())
+    }
+
+    /// Starts a server that answers each connection with the
+    /// next of the given raw HTTP responses, and returns its
+    /// URL along with a count of the requests it received.
+    async fn serve(responses: Vec<&'static str>) -> Result<(String, Arc<AtomicUsize>)> {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let count = Arc::new(AtomicUsize::new(0));
+        let counter = count.clone();
+        tokio::spawn(async move {
+            for res in responses {
+                let Ok((mut conn, _)) = listener.accept().await else {
+                    return;
+                };
+
+                // Read until the end of the request's headers...
+                let mut req = Vec::new();
+                let mut buf = [0; 1024];
+                while !req.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match conn.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => req.extend_from_slice(&buf[..n]),
+                    }
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = conn.write_all(res.as_bytes()).await;
+            }
+        });
+        Ok((url, count))
+    }
+
+    #[tokio::test]
+    async fn hooks_see_redacted_requests_and_responses() -> Result<()> {
+        let ok = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 30\r\nconnection: close\r\n\r\n{\"object\": \"list\", \"data\": []}";
+        let (url, _) = serve(vec![ok]).await?;
+
+        // Record what the hooks see...
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let responses = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (req_log, res_log) = (requests.clone(), responses.clone());
+        let client = Client::builder()
+            .api_key("sk-secret")
+            .base_url(&url)
+            .on_request(move |req| req_log.lock().unwrap().push(req.clone()))
+            .on_response(move |res| res_log.lock().unwrap().push(res.clone()))
+            .build()?;
+        let models = client.list_models().await?;
+        assert!(models.data.is_empty());
+
+        // The key is redacted from the request...
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, Method::GET);
+        assert_eq!(requests[0].url.path(), "/v1/models");
+        assert_eq!(requests[0].headers["authorization"], hooks::REDACTED);
+        assert!(!format!("{:?}", requests[0]).contains("sk-secret"));
+
+        // ...and the response body is seen, but still parsed...
+        let responses = responses.lock().unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].status, 200);
+        assert_eq!(
+            responses[0].body.as_deref(),
+            Some("{\"object\": \"list\", \"data\": []}")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn send_retries_transient_errors() -> Result<()> {
+        let unavailable = "HTTP/1.1 503 Service Unavailable\r\nretry-after-ms: 10\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+        let ok = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 30\r\nconnection: close\r\n\r\n{\"object\": \"list\", \"data\": []}";
+        let unauthorized =
+            "HTTP/1.1 401 Unauthorized\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+
+        // A transient error is retried...
+        let (url, count) = serve(vec![unavailable, ok]).await?;
+        let client = Client::builder()
+            .api_key("test")
+            .base_url(&url)
+            .max_retries(2)
+            .build()?;
+        client.list_models().await?;
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        // ...but only up to the limit...
+        let (url, count) = serve(vec![unavailable, unavailable, ok]).await?;
+        let client = Client::builder()
+            .api_key("test")
+            .base_url(&url)
+            .max_retries(1)
+            .build()?;
+        let err = client.list_models().await.unwrap_err();
+        assert!(matches!(err, Error::Api { status: 503, .. }));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        // ...and other errors fail immediately...
+        let (url, count) = serve(vec![unauthorized, ok]).await?;
+        let client = Client::builder()
+            .api_key("test")
+            .base_url(&url)
+            .max_retries(2)
+            .build()?;
+        let err = client.list_models().await.unwrap_err();
+        assert!(matches!(err, Error::Api { status: 401, .. }));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn with_timeout_overrides_client_timeout() -> Result<()> {
+        // Start a server that accepts connections but never responds...
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            let mut conns = Vec::new();
+            while let Ok((conn, _)) = listener.accept().await {
+                conns.push(conn);
+            }
+        });
+
+        // The override applies to requests from the handle...
+        let client = Client::builder()
+            .api_key("test")
+            .base_url(&format!("http://{}", addr))
+            .timeout(Duration::from_secs(60))
+            .build()?;
+        let start = Instant::now();
+        let err = client
+            .with_timeout(Duration::from_millis(100))
+            .list_models()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Http(ref err) if err.is_timeout()));
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        // ...and the original client is unchanged...
+        assert_eq!(client.request_timeout, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn dropping_stream_closes_connection() -> Result<()> {
+        // Start a server that sends one chunk, then stalls...
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let server = tokio::spawn(async move {
+            let (mut conn, _) = listener.accept().await?;
+            let mut buf = [0; 4096];
+            let _ = conn.read(&mut buf).await?;
+            let event = "data: {\"id\": \"a\", \"object\": \"chat.completion.chunk\", \"created\": 1, \"model\": \"m\", \"choices\": []}\n\n";
+            let res = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n{:x}\r\n{}\r\n",
+                event.len(),
+                event
+            );
+            conn.write_all(res.as_bytes()).await?;
+
+            // Wait for the client to hang up...
+            loop {
+                if conn.read(&mut buf).await? == 0 {
+                    return Ok::<_, std::io::Error>(());
+                }
+            }
+        });
+
+        // Read the first chunk, then drop the stream...
+        let client = Client::builder()
+            .api_key("test")
+            .base_url(&url)
+            .max_concurrency(1)
+            .build()?;
+        let mut stream = client
+            .create_chat_completion_stream(ChatCompletionRequest::default())
+            .await?;
+        assert_eq!(stream.next().await.unwrap()?.id, "a");
+        drop(stream);
+
+        // The connection should be closed promptly...
+        tokio::time::timeout(Duration::from_secs(5), server).await???;
+
+        // ...and the permit released...
+        let permit =
+            tokio::time::timeout(Duration::from_secs(5), client.acquire_permit()).await??;
+        assert!(permit.is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn builder_validate_requests_checks_before_sending() -> Result<()> {
+        // The base URL is never reached, since validation fails first...
+        let client = Client::builder()
+            .api_key("test")
+            .base_url("http://127.0.0.1:9")
+            .validate_requests(true)
+            .build()?;
+        let req = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            top_p: Some(2.0),
+            ..Default::default()
+        };
+        let err = client
+            .create_chat_completion(req.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Validation(ref msg) if msg.contains("top_p")));
+        let err = client
+            .create_chat_completion_stream(req)
+            .await
+            .err()
+            .expect("expected validation to fail");
+        assert!(matches!(err, Error::Validation(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn debug_masks_api_key() -> Result<()> {
+        let key = "sk-proj-abcdefghijklmnop";
+        let client = Client::new(key);
+        let debug = format!("{:?}", client);
+        assert!(!debug.contains(key));
+        assert!(debug.contains("sk-****"));
+        assert!(debug.contains("https://api.openai.com/"));
+
+        // The builder and blocking client are masked too...
+        let builder = Client::builder().api_key(key);
+        assert!(!format!("{:?}", builder).contains(key));
+        let blocking = blocking::Client::new(key);
+        assert!(!format!("{:?}", blocking).contains(key));
+
+        // Short keys are masked entirely...
+        assert_eq!(mask_key("test"), "****");
+        Ok(())
+    }
+
+    #[test]
+    fn assistants_requests_send_beta_header() -> Result<()> {
+        let client = Client::new("sk-test");
+
+        // Define cases to test...
+        let cases = vec![
+            ("/v1/assistants", Some(ASSISTANTS_BETA)),
+            ("/v1/assistants/asst_abc123", Some(ASSISTANTS_BETA)),
+            ("/v1/assistantsx", None),
+            ("/v1/threads", Some(ASSISTANTS_BETA)),
+            ("/v1/threads/thread_abc123/messages", Some(ASSISTANTS_BETA)),
+            ("/v1/models", None),
+        ];
+
+        // Iterate over cases and test...
+        for (path, expected) in cases {
+            let req = client.create_request(Method::GET, path)?.build()?;
+            let beta = req.headers().get(OPENAI_BETA_HEADER);
+            assert_eq!(
+                beta.map(|value| value.to_str().unwrap()),
+                expected,
+                "{}",
+                path
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wait_for_run_polls_until_stopped() -> Result<()> {
+        let run = |status: &str| -> &'static str {
+            let body = format!(
+                r#"{{"id": "run_abc123", "object": "thread.run", "created_at": 1699063290, "thread_id": "thread_abc123", "assistant_id": "asst_abc123", "status": "{}"}}"#,
+                status
+            );
+            Box::leak(
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .into_boxed_str(),
+            )
+        };
+
+        // Poll until the run completes...
+        let (url, count) = serve(vec![run("queued"), run("in_progress"), run("completed")]).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .build()?;
+        let res = client
+            .wait_for_run(
+                "thread_abc123",
+                "run_abc123",
+                Duration::from_millis(1),
+                Duration::from_secs(5),
+            )
+            .await?;
+        assert_eq!(res.status, RunStatus::Completed);
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+
+        // Give up once the max wait has passed...
+        let (url, _) = serve(vec![run("in_progress"); 10]).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .build()?;
+        let err = client
+            .wait_for_run(
+                "thread_abc123",
+                "run_abc123",
+                Duration::from_millis(20),
+                Duration::from_millis(50),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Timeout(ref msg) if msg.contains("InProgress")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chat_completion_without_choices_is_an_error() -> Result<()> {
+        let body = r#"{"id": "chatcmpl-123", "object": "chat.completion", "created": 1677652288, "model": "gpt-4o", "choices": [], "usage": {"prompt_tokens": 9, "completion_tokens": 0, "total_tokens": 9}}"#;
+        let res: &'static str = Box::leak(
+            format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .into_boxed_str(),
+        );
+        let (url, _) = serve(vec![res]).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .build()?;
+
+        // The error keeps the response, so the usage isn't lost...
+        let err = client
+            .chat("gpt-4o")
+            .user("Hello!")
+            .send()
+            .await
+            .unwrap_err();
+        match err {
+            Error::EmptyChoices(res) => {
+                assert_eq!(res.id, "chatcmpl-123");
+                assert_eq!(res.usage.total_tokens, 9);
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn raw_responses_are_status_checked() -> Result<()> {
+        let ok = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\nx-request-id: req_123\r\ncontent-length: 2\r\nconnection: close\r\n\r\n{}";
+        let not_found = "HTTP/1.1 404 Not Found\r\ncontent-type: application/json\r\ncontent-length: 35\r\nconnection: close\r\n\r\n{\"error\": {\"message\": \"Not found\"}}";
+        let (url, _) = serve(vec![ok, not_found]).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .build()?;
+
+        // The response is returned as-is, without being parsed...
+        let req = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![dtypes::ChatCompletionMessage {
+                role: dtypes::Role::User,
+                content: Some("Hello!".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let res = client.create_chat_completion_raw(req).await?;
+        assert_eq!(res.headers()["x-request-id"], "req_123");
+        assert_eq!(res.text().await?, "{}");
+
+        // ...but errors are still errors...
+        let rb = client.raw_request(Method::GET, "/v1/unknown")?;
+        let err = client.send_raw(rb).await.unwrap_err();
+        assert!(
+            matches!(err, Error::Api { status: 404, ref error } if error.message == "Not found")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn retries_keep_the_idempotency_key() -> Result<()> {
+        let unavailable = "HTTP/1.1 503 Service Unavailable\r\nretry-after-ms: 10\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+        let ok = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 2\r\nconnection: close\r\n\r\n{}";
+        let (url, _) = serve(vec![unavailable, ok, ok]).await?;
+
+        // Record the keys sent with each attempt...
+        let keys = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let generated = Arc::new(AtomicUsize::new(0));
+        let (log, counter) = (keys.clone(), generated.clone());
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .max_retries(1)
+            .idempotency_key(move || format!("key-{}", counter.fetch_add(1, Ordering::SeqCst)))
+            .on_request(move |req| {
+                let key = req.headers.get(IDEMPOTENCY_KEY_HEADER).cloned();
+                log.lock().unwrap().push(key);
+            })
+            .build()?;
+
+        // A retried POST keeps its key...
+        let rb = client
+            .raw_request(Method::POST, "/v1/threads")?
+            .json(&serde_json::json!({}));
+        client.send_raw(rb).await?;
+        // ...and a GET doesn't get one...
+        let rb = client.raw_request(Method::GET, "/v1/models")?;
+        client.send_raw(rb).await?;
+
+        let keys = keys.lock().unwrap();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys[0].as_ref().unwrap(), "key-0");
+        assert_eq!(keys[1].as_ref().unwrap(), "key-0");
+        assert_eq!(keys[2], None);
+        assert_eq!(generated.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn builder_requires_api_key() {
+        // Define cases to test...
+        let cases = vec![
+            (None, false),
+            (Some(""), false),
+            (Some("  "), false),
+            (Some("sk-test"), true),
+        ];
+
+        // Iterate over cases and test...
+        for (api_key, ok) in cases {
+            let mut builder = Client::builder();
+            if let Some(api_key) = api_key {
+                builder = builder.api_key(api_key);
+            }
+            match builder.build() {
+                Ok(_) => assert!(ok, "{:?}", api_key),
+                Err(Error::MissingApiKey) => assert!(!ok, "{:?}", api_key),
+                Err(err) => panic!("unexpected error: {}", err),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn chat_completion_channel_forwards_chunks() -> Result<()> {
+        let body = "data: {\"id\": \"a\", \"object\": \"chat.completion.chunk\", \"created\": 1, \"model\": \"gpt-4o\", \"choices\": []}\n\ndata: {\"id\": \"b\", \"object\": \"chat.completion.chunk\", \"created\": 1, \"model\": \"gpt-4o\", \"choices\": []}\n\ndata: [DONE]\n\n";
+        let stream: &'static str = Box::leak(
+            format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .into_boxed_str(),
+        );
+        let unauthorized =
+            "HTTP/1.1 401 Unauthorized\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+        let (url, _) = serve(vec![stream, unauthorized]).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .build()?;
+        let req = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            ..Default::default()
+        };
+
+        // Each chunk is forwarded, then the channel closes...
+        let (tx, mut rx) = mpsc::channel(1);
+        let handle = client.create_chat_completion_channel(req.clone(), tx);
+        let mut ids = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            ids.push(chunk?.id);
+        }
+        assert_eq!(ids, vec!["a", "b"]);
+        handle.await?;
+
+        // ...and errors are forwarded too...
+        let (tx, mut rx) = mpsc::channel(1);
+        client.create_chat_completion_channel(req, tx);
+        let err = rx.recv().await.unwrap().unwrap_err();
+        assert!(matches!(err, Error::Api { status: 401, .. }));
+        assert!(rx.recv().await.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chat_completion_receiver_forwards_chunks() -> Result<()> {
+        let body = "data: {\"id\": \"a\", \"object\": \"chat.completion.chunk\", \"created\": 1, \"model\": \"gpt-4o\", \"choices\": []}\n\ndata: [DONE]\n\n";
+        let stream: &'static str = Box::leak(
+            format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .into_boxed_str(),
+        );
+        let (url, _) = serve(vec![stream]).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .build()?;
+
+        // The chunks arrive on the returned receiver, which then closes...
+        let req = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            ..Default::default()
+        };
+        let mut rx = client.create_chat_completion_receiver(req, 4);
+        assert_eq!(rx.recv().await.unwrap()?.id, "a");
+        assert!(rx.recv().await.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn build_chat_completion_body_matches_request() -> Result<()> {
+        let client = Client::builder()
+            .api_key("sk-test")
+            .validate_requests(true)
+            .build()?;
+        let req = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            temperature: Some(0.5),
+            ..Default::default()
+        };
+
+        // The body is the request's JSON...
+        let body = client.build_chat_completion_body(&req)?;
+        assert_eq!(body, serde_json::to_string(&req)?);
+        let value: serde_json::Value = serde_json::from_str(&body)?;
+        assert_eq!(value["temperature"], 0.5);
+        assert!(value.get("seed").is_none());
+
+        // ...and invalid requests are rejected, as when sending...
+        let req = ChatCompletionRequest {
+            temperature: Some(3.0),
+            ..req
+        };
+        let err = client.build_chat_completion_body(&req).unwrap_err();
+        assert!(matches!(err, Error::Validation(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn api_error_falls_back_to_body_snippet() {
+        // An HTML error page from a proxy is kept, but tidied up...
+        let html = "<html>\r\n<head><title>502 Bad Gateway</title></head>\r\n<body>\r\n<center><h1>502 Bad Gateway</h1></center>\r\n</body>\r\n</html>\r\n";
+        let err = api_error(reqwest::StatusCode::BAD_GATEWAY, html);
+        match &err {
+            Error::Api { status, error } => {
+                assert_eq!(*status, 502);
+                assert_eq!(
+                    error.message,
+                    "<html> <head><title>502 Bad Gateway</title></head> <body> <center><h1>502 Bad Gateway</h1></center> </body> </html>"
+                );
+                assert_eq!(error.type_, None);
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+        assert!(err.to_string().contains("status 502"));
+
+        // ...long bodies are truncated...
+        let long = "é".repeat(MAX_ERROR_BODY_CHARS * 2);
+        let err = api_error(reqwest::StatusCode::SERVICE_UNAVAILABLE, &long);
+        let Error::Api { error, .. } = err else {
+            panic!("expected an API error");
+        };
+        assert_eq!(error.message.chars().count(), MAX_ERROR_BODY_CHARS + 3);
+        assert!(error.message.ends_with("..."));
+
+        // ...and empty bodies use the status's reason...
+        let err = api_error(reqwest::StatusCode::BAD_GATEWAY, "  ");
+        let Error::Api { error, .. } = err else {
+            panic!("expected an API error");
+        };
+        assert_eq!(error.message, "Bad Gateway");
+    }
+
+    #[tokio::test]
+    async fn create_completion_stream_yields_text() -> Result<()> {
+        let body = concat!(
+            "data: {\"id\": \"cmpl-1\", \"object\": \"text_completion\", \"created\": 1, \"model\": \"m\", \"choices\": [{\"text\": \"Hel\", \"index\": 0, \"logprobs\": null, \"finish_reason\": null}]}\n\n",
+            ": keep-alive\n\n",
+            "data: {\"id\": \"cmpl-1\", \"object\": \"text_completion\", \"created\": 1, \"model\": \"m\", \"choices\": [{\"text\": \"lo\", \"index\": 0, \"logprobs\": null, \"finish_reason\": \"stop\"}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let res = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let (url, _) = serve(vec![Box::leak(res.into_boxed_str())]).await?;
+        let client = Client::builder().api_key("test").base_url(&url).build()?;
+
+        let req = CompletionRequest {
+            model: "m".to_string(),
             ..Default::default()
         };
+        let chunks: Vec<CompletionChunk> = client
+            .create_completion_stream(req)
+            .await?
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<crate::Result<_>>()?;
+        let text: String = chunks.iter().filter_map(|chunk| chunk.text()).collect();
+        assert_eq!(text, "Hello");
+        assert_eq!(
+            chunks[1].choices[0].finish_reason,
+            Some(dtypes::FinishReason::Stop)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn format_url_uses_version_prefix() -> Result<()> {
+        // Define cases to test...
+        let cases = vec![
+            (
+                "https://api.openai.com/",
+                "v1",
+                "/v1/models",
+                "https://api.openai.com/v1/models",
+            ),
+            (
+                "http://localhost:8080",
+                "",
+                "/v1/models",
+                "http://localhost:8080/models",
+            ),
+            (
+                "http://localhost:8080/v1",
+                "v1",
+                "/v1/models",
+                "http://localhost:8080/v1/models",
+            ),
+            (
+                "http://localhost:8080/v1",
+                "",
+                "/v1/models",
+                "http://localhost:8080/v1/models",
+            ),
+            (
+                "https://host/api",
+                "v2",
+                "/v1/models",
+                "https://host/api/v2/models",
+            ),
+            (
+                "https://host/api/v2/",
+                "v2",
+                "/v1/models",
+                "https://host/api/v2/models",
+            ),
+            (
+                "https://host",
+                "/v1/",
+                "/v1/models",
+                "https://host/v1/models",
+            ),
+            ("https://host", "v2", "/health", "https://host/health"),
+        ];
+
+        // Iterate over cases and test...
+        for (base_url, prefix, path, expected) in cases {
+            let url = format_url(base_url, prefix, &ApiType::OpenAi, path)?;
+            assert_eq!(url.as_str(), expected, "{} with {:?}", base_url, prefix);
+        }
+
+        // The builder sets it on the client...
+        let client = Client::builder()
+            .api_key("test")
+            .base_url("http://localhost:8080/openai")
+            .version_prefix("")
+            .build()?;
+        let url = client.format_url("/v1/chat/completions")?;
+        assert_eq!(
+            url.as_str(),
+            "http://localhost:8080/openai/chat/completions"
+        );
+        assert_eq!(Client::new("test").version_prefix, VERSION_PREFIX);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ask_returns_first_content() -> Result<()> {
+        let answer = r#"{"id": "chatcmpl-1", "object": "chat.completion", "created": 1677652288, "model": "gpt-4o", "choices": [{"index": 0, "message": {"role": "assistant", "content": "4"}, "finish_reason": "stop"}], "usage": {"prompt_tokens": 9, "completion_tokens": 1, "total_tokens": 10}}"#;
+        let tool_call = r#"{"id": "chatcmpl-2", "object": "chat.completion", "created": 1677652288, "model": "gpt-4o", "choices": [{"index": 0, "message": {"role": "assistant", "content": null, "tool_calls": [{"id": "call_1", "type": "function", "function": {"name": "add", "arguments": "{}"}}]}, "finish_reason": "tool_calls"}], "usage": {"prompt_tokens": 9, "completion_tokens": 5, "total_tokens": 14}}"#;
+        let responses = [answer, tool_call]
+            .into_iter()
+            .map(|body| -> &'static str {
+                Box::leak(
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                    .into_boxed_str(),
+                )
+            })
+            .collect();
+        let (url, _) = serve(responses).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .build()?;
+
+        // The text is returned...
+        assert_eq!(client.ask("gpt-4o", "What's 2 + 2?").await?, "4");
+
+        // ...or an error if there isn't any...
+        match client.ask("gpt-4o", "What's 2 + 2?").await.unwrap_err() {
+            Error::NoContent(res) => assert_eq!(res.id, "chatcmpl-2"),
+            err => panic!("unexpected error: {}", err),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn builder_sends_requests_through_proxy() -> Result<()> {
+        let ok = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 30\r\nconnection: close\r\n\r\n{\"object\": \"list\", \"data\": []}";
+
+        // The proxy is asked for the (unresolvable) API host...
+        let (proxy_url, count) = serve(vec![ok]).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url("http://api.example.invalid/")
+            .proxy_url(&proxy_url)
+            .build()?;
+        client.list_models().await?;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // ...unless the host is excluded...
+        let (url, count) = serve(vec![ok]).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .proxy(reqwest::Proxy::http("http://127.0.0.1:9")?)
+            .no_proxy("127.0.0.1")
+            .build()?;
+        client.list_models().await?;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // ...and a bad proxy URL is a config error...
+        let err = Client::builder()
+            .api_key("sk-test")
+            .proxy_url("not a url")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, Error::Config(_)), "{}", err);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn semantic_search_ranks_corpus_by_similarity() -> Result<()> {
+        // The embeddings come back out of order, query first...
+        let body = r#"{"object": "list", "data": [{"object": "embedding", "index": 2, "embedding": [1.0, 0.0]}, {"object": "embedding", "index": 0, "embedding": [1.0, 0.0]}, {"object": "embedding", "index": 3, "embedding": [1.0, 1.0]}, {"object": "embedding", "index": 1, "embedding": [0.0, 1.0]}], "model": "text-embedding-3-small", "usage": {"prompt_tokens": 8, "total_tokens": 8}}"#;
+        let res: &'static str = Box::leak(
+            format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .into_boxed_str(),
+        );
+        let (url, count) = serve(vec![res]).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .build()?;
+
+        // The corpus is embedded in a single request...
+        let corpus = vec!["cat".to_string(), "dog".to_string(), "cow".to_string()];
+        let res = client
+            .semantic_search("text-embedding-3-small", "kitten", &corpus, 2)
+            .await?;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        // ...and the top k are returned, most similar first...
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0], (1, 1.0));
+        assert_eq!(res[1].0, 2);
+        assert!((res[1].1 - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_batches_filtered_pages_and_filters() -> Result<()> {
+        let page = |body: String| -> &'static str {
+            Box::leak(
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .into_boxed_str(),
+            )
+        };
+        let batch = |id: &str, run_id: &str| {
+            format!(
+                r#"{{"id": "{}", "object": "batch", "endpoint": "/v1/chat/completions", "input_file_id": "file-abc123", "completion_window": "24h", "status": "completed", "created_at": 1711471533, "metadata": {{"run_id": "{}"}}}}"#,
+                id, run_id
+            )
+        };
+
+        // The matching batches are spread across two pages...
+        let first = page(format!(
+            r#"{{"object": "list", "data": [{}, {}], "has_more": true, "last_id": "batch_2"}}"#,
+            batch("batch_1", "a"),
+            batch("batch_2", "b")
+        ));
+        let second = page(format!(
+            r#"{{"object": "list", "data": [{}], "has_more": false, "last_id": "batch_3"}}"#,
+            batch("batch_3", "a")
+        ));
+        let (url, count) = serve(vec![first, second]).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .build()?;
+
+        // Only the batches for the run are returned...
+        let batches = client.list_batches_filtered(&[("run_id", "a")]).await?;
+        let ids: Vec<_> = batches.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, vec!["batch_1", "batch_3"]);
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_all_files_follows_cursors() -> Result<()> {
+        let page = |ids: &[&str], has_more: bool| -> &'static str {
+            let files: Vec<String> = ids
+                .iter()
+                .map(|id| {
+                    format!(
+                        r#"{{"id": "{}", "object": "file", "bytes": 1, "created_at": 1, "filename": "{}.jsonl", "purpose": "batch"}}"#,
+                        id, id
+                    )
+                })
+                .collect();
+            let body = format!(
+                r#"{{"object": "list", "data": [{}], "has_more": {}}}"#,
+                files.join(", "),
+                has_more
+            );
+            Box::leak(
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .into_boxed_str(),
+            )
+        };
+        let pages = vec![page(&["file-1", "file-2"], true), page(&["file-3"], false)];
+
+        // Every page is fetched up front...
+        let (url, count) = serve(pages.clone()).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .build()?;
+        let files = client.list_all_files().await?;
+        let ids: Vec<_> = files.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, vec!["file-1", "file-2", "file-3"]);
+        assert_eq!(count.load(Ordering::SeqCst), 2);
 
-        let _res = client.create_chat_completion(req).await?;
+        // ...or lazily, as the stream is consumed...
+        let (url, count) = serve(pages).await?;
+        let client = Client::builder()
+            .api_key("sk-test")
+            .base_url(&url)
+            .build()?;
+        let mut files = client.list_files_stream();
+        assert_eq!(files.next().await.unwrap()?.id, "file-1");
+        assert_eq!(files.next().await.unwrap()?.id, "file-2");
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(files.next().await.unwrap()?.id, "file-3");
+        assert!(files.next().await.is_none());
+        assert_eq!(count.load(Ordering::SeqCst), 2);
         Ok(())
     }
 }
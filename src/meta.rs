@@ -0,0 +1,254 @@
+use reqwest::header::HeaderMap;
+use std::time::Duration;
+
+/// The header OpenAI uses to report how long the request
+/// took to process on their end, in milliseconds.
+pub const PROCESSING_MS_HEADER: &str = "openai-processing-ms";
+
+/// The headers OpenAI uses to report the rate limits that
+/// apply to the request, and how much of them is left.
+pub const LIMIT_REQUESTS_HEADER: &str = "x-ratelimit-limit-requests";
+pub const LIMIT_TOKENS_HEADER: &str = "x-ratelimit-limit-tokens";
+pub const REMAINING_REQUESTS_HEADER: &str = "x-ratelimit-remaining-requests";
+pub const REMAINING_TOKENS_HEADER: &str = "x-ratelimit-remaining-tokens";
+pub const RESET_REQUESTS_HEADER: &str = "x-ratelimit-reset-requests";
+pub const RESET_TOKENS_HEADER: &str = "x-ratelimit-reset-tokens";
+
+/// Information about a response, parsed from its headers
+/// rather than its body.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ResponseMeta {
+    /// How long the server spent processing the request,
+    /// from the `openai-processing-ms` header.
+    ///
+    /// Comparing this with the total time of the call
+    /// separates OpenAI's processing time from time spent
+    /// on the network.
+    pub processing_time: Option<Duration>,
+
+    /// The rate limits that applied to the request.
+    pub rate_limit: RateLimitInfo,
+}
+
+/// The rate limits that applied to a request, parsed from
+/// the `x-ratelimit-*` headers.
+///
+/// Useful for throttling proactively, rather than waiting
+/// to be rejected with a `429`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RateLimitInfo {
+    /// The maximum number of requests allowed before the
+    /// limit is exhausted.
+    pub limit_requests: Option<u64>,
+
+    /// The maximum number of tokens allowed before the
+    /// limit is exhausted.
+    pub limit_tokens: Option<u64>,
+
+    /// The number of requests remaining before the limit
+    /// is exhausted.
+    pub remaining_requests: Option<u64>,
+
+    /// The number of tokens remaining before the limit is
+    /// exhausted.
+    pub remaining_tokens: Option<u64>,
+
+    /// The time until the request limit resets to its
+    /// initial state.
+    pub reset_requests: Option<Duration>,
+
+    /// The time until the token limit resets to its
+    /// initial state.
+    pub reset_tokens: Option<Duration>,
+}
+
+impl RateLimitInfo {
+    /// Parses the rate limit info from a set of headers.
+    ///
+    /// Headers that are missing or malformed are left as `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::meta::RateLimitInfo;
+    /// use reqwest::header::HeaderMap;
+    /// use std::time::Duration;
+    ///
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert("x-ratelimit-remaining-tokens", "149984".parse().unwrap());
+    /// headers.insert("x-ratelimit-reset-tokens", "6m0s".parse().unwrap());
+    /// let info = RateLimitInfo::from_headers(&headers);
+    /// assert_eq!(info.remaining_tokens, Some(149984));
+    /// assert_eq!(info.reset_tokens, Some(Duration::from_secs(360)));
+    /// ```
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self {
+            limit_requests: parse_count(headers, LIMIT_REQUESTS_HEADER),
+            limit_tokens: parse_count(headers, LIMIT_TOKENS_HEADER),
+            remaining_requests: parse_count(headers, REMAINING_REQUESTS_HEADER),
+            remaining_tokens: parse_count(headers, REMAINING_TOKENS_HEADER),
+            reset_requests: parse_reset(headers, RESET_REQUESTS_HEADER),
+            reset_tokens: parse_reset(headers, RESET_TOKENS_HEADER),
+        }
+    }
+}
+
+impl ResponseMeta {
+    /// Parses the response metadata from a set of headers.
+    ///
+    /// Headers that are missing or malformed are left as `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::meta::ResponseMeta;
+    /// use reqwest::header::HeaderMap;
+    /// use std::time::Duration;
+    ///
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert("openai-processing-ms", "250".parse().unwrap());
+    /// let meta = ResponseMeta::from_headers(&headers);
+    /// assert_eq!(meta.processing_time, Some(Duration::from_millis(250)));
+    /// ```
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self {
+            processing_time: parse_millis(headers, PROCESSING_MS_HEADER),
+            rate_limit: RateLimitInfo::from_headers(headers),
+        }
+    }
+}
+
+/// Parses a header containing a (possibly fractional)
+/// number of milliseconds.
+fn parse_millis(headers: &HeaderMap, name: &str) -> Option<Duration> {
+    let value = headers.get(name)?.to_str().ok()?.trim();
+    let millis: f64 = value.parse().ok()?;
+    Duration::try_from_secs_f64(millis / 1000.0).ok()
+}
+
+/// Parses a header containing a whole number.
+fn parse_count(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Parses a header containing a duration, formatted as
+/// a sequence of numbers with units (e.g. `1s`, `6m0s` or
+/// `20ms`).
+fn parse_reset(headers: &HeaderMap, name: &str) -> Option<Duration> {
+    parse_duration(headers.get(name)?.to_str().ok()?.trim())
+}
+
+/// Parses a duration like `1h2m3.5s`, with units of `h`,
+/// `m`, `s` or `ms`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    if value.is_empty() {
+        return None;
+    }
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        // Split off the number...
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(end);
+        let number: f64 = number.parse().ok()?;
+
+        // Split off the unit...
+        let end = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(end);
+        let secs = match unit {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        total += number * secs;
+        rest = tail;
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn response_meta_from_headers() {
+        // Define cases to test...
+        let cases = vec![
+            (None, None),
+            (Some("0"), Some(Duration::ZERO)),
+            (Some("1234"), Some(Duration::from_millis(1234))),
+            (Some(" 12.5 "), Some(Duration::from_micros(12_500))),
+            (Some("-1"), None),
+            (Some("abc"), None),
+            (Some("1e30"), None),
+            (Some("inf"), None),
+        ];
+
+        // Iterate over cases and test...
+        for (i, (value, expected)) in cases.into_iter().enumerate() {
+            let mut headers = HeaderMap::new();
+            if let Some(value) = value {
+                headers.insert(PROCESSING_MS_HEADER, value.parse().unwrap());
+            }
+            let meta = ResponseMeta::from_headers(&headers);
+            assert_eq!(meta.processing_time, expected, "case {} didn't match", i);
+        }
+    }
+
+    #[test]
+    fn parse_duration_values() {
+        // Define cases to test...
+        let cases = vec![
+            ("1s", Some(Duration::from_secs(1))),
+            ("6m0s", Some(Duration::from_secs(360))),
+            ("20ms", Some(Duration::from_millis(20))),
+            ("1h2m3.5s", Some(Duration::from_millis(3_723_500))),
+            ("0.5s", Some(Duration::from_millis(500))),
+            ("", None),
+            ("5", None),
+            ("5d", None),
+            ("s", None),
+            ("99999999999999999999h", None),
+        ];
+
+        // Iterate over cases and test...
+        for (value, expected) in cases {
+            assert_eq!(
+                parse_duration(value),
+                expected,
+                "failed to parse {:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn rate_limit_info_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(LIMIT_REQUESTS_HEADER, "60".parse().unwrap());
+        headers.insert(LIMIT_TOKENS_HEADER, "150000".parse().unwrap());
+        headers.insert(REMAINING_REQUESTS_HEADER, "59".parse().unwrap());
+        headers.insert(REMAINING_TOKENS_HEADER, "bad".parse().unwrap());
+        headers.insert(RESET_REQUESTS_HEADER, "1s".parse().unwrap());
+        headers.insert(RESET_TOKENS_HEADER, "6m0s".parse().unwrap());
+
+        let meta = ResponseMeta::from_headers(&headers);
+        assert_eq!(
+            meta.rate_limit,
+            RateLimitInfo {
+                limit_requests: Some(60),
+                limit_tokens: Some(150000),
+                remaining_requests: Some(59),
+                remaining_tokens: None,
+                reset_requests: Some(Duration::from_secs(1)),
+                reset_tokens: Some(Duration::from_secs(360)),
+            }
+        );
+    }
+}
@@ -0,0 +1,435 @@
+//! An in-process mock of the OpenAI API, for testing code
+//! that uses this crate without hitting the real API.
+//!
+//! Enabled with the `mock` feature.
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::dtypes::{ApiError, ApiErrorEnvelope, ChatCompletionChunk};
+use crate::Client;
+
+/// The registered responses, keyed by method and path.
+type Routes = Arc<Mutex<HashMap<(String, String), Route>>>;
+
+/// The responses registered for a single method and path.
+#[derive(Debug, Default)]
+struct Route {
+    /// One-off responses, returned in order before falling
+    /// back to `default`.
+    queue: VecDeque<MockResponse>,
+
+    /// The response returned once the queue is empty.
+    default: Option<MockResponse>,
+}
+
+/// A canned response returned by a `MockServer`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockResponse {
+    /// The HTTP status code.
+    pub status: u16,
+
+    /// The response headers (not including `content-length`,
+    /// which is set automatically).
+    pub headers: Vec<(String, String)>,
+
+    /// The response body.
+    pub body: Vec<u8>,
+}
+
+impl MockResponse {
+    /// Creates a response with the given status whose body
+    /// is `data` serialized as JSON.
+    pub fn json<T: Serialize>(status: u16, data: &T) -> Self {
+        Self {
+            status,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: serde_json::to_vec(data).expect("mock response should serialize"),
+        }
+    }
+
+    /// Creates an error response with the given status,
+    /// wrapping `error` in the API's `{"error": {...}}`
+    /// envelope.
+    pub fn error(status: u16, error: ApiError) -> Self {
+        Self::json(status, &ApiErrorEnvelope { error })
+    }
+
+    /// Creates a streamed chat completion response, sending
+    /// each chunk as a server-sent event followed by
+    /// `data: [DONE]`.
+    pub fn chat_completion_stream(chunks: &[ChatCompletionChunk]) -> Self {
+        let mut body = String::new();
+        for chunk in chunks {
+            let chunk = serde_json::to_string(chunk).expect("mock chunk should serialize");
+            body.push_str(&format!("data: {}\n\n", chunk));
+        }
+        body.push_str("data: [DONE]\n\n");
+        Self {
+            status: 200,
+            headers: vec![("content-type".to_string(), "text/event-stream".to_string())],
+            body: body.into_bytes(),
+        }
+    }
+}
+
+/// A local HTTP server that answers requests with the
+/// responses registered for their method and path.
+///
+/// Requests are answered with the responses queued by
+/// `expect` (in order), then with the response registered by
+/// `mock`. Requests to a path without a response get a `404`
+/// with an API error body. The server shuts down when it's
+/// dropped.
+///
+/// # Example
+///
+/// ```
+/// use ohairs::dtypes::ListModelsResponse;
+/// use ohairs::mock::{MockResponse, MockServer};
+///
+/// # #[tokio::main]
+/// # async fn main() -> ohairs::Result<()> {
+/// let server = MockServer::start().await?;
+/// let models = ListModelsResponse {
+///     object: "list".to_string(),
+///     data: vec![],
+/// };
+/// server.mock("GET", "/v1/models", MockResponse::json(200, &models));
+///
+/// let models = server.client().list_models().await?;
+/// assert!(models.data.is_empty());
+/// # Ok(())
+/// # }
+/// ```
+pub struct MockServer {
+    url: String,
+    routes: Routes,
+    handle: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts a server listening on a random local port.
+    pub async fn start() -> crate::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/", listener.local_addr()?);
+        let routes = Routes::default();
+
+        // Answer each connection in its own task...
+        let server_routes = routes.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((conn, _)) = listener.accept().await {
+                tokio::spawn(handle_conn(conn, server_routes.clone()));
+            }
+        });
+        Ok(Self {
+            url,
+            routes,
+            handle,
+        })
+    }
+
+    /// The base URL of the server.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Creates a client that sends requests to this server.
+    pub fn client(&self) -> Client {
+        let mut client = Client::new("test");
+        client.base_url = self.url.clone();
+        client
+    }
+
+    /// Registers the response for requests with the given
+    /// method and path (e.g. `"POST"` and `"/v1/chat/completions"`),
+    /// replacing any previous response.
+    pub fn mock(&self, method: &str, path: &str, response: MockResponse) {
+        self.routes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry((method.to_uppercase(), path.to_string()))
+            .or_default()
+            .default = Some(response);
+    }
+
+    /// Queues a response to be returned once, for the next
+    /// request with the given method and path that doesn't
+    /// match an earlier queued response.
+    ///
+    /// Queue several responses to script a sequence, e.g. a
+    /// `429` followed by a success to exercise retries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ohairs::dtypes::{ApiError, ListModelsResponse};
+    /// use ohairs::mock::{MockResponse, MockServer};
+    /// use ohairs::Error;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> ohairs::Result<()> {
+    /// let server = MockServer::start().await?;
+    /// server.expect_error(
+    ///     "GET",
+    ///     "/v1/models",
+    ///     429,
+    ///     ApiError {
+    ///         message: "Rate limit reached".to_string(),
+    ///         ..Default::default()
+    ///     },
+    /// );
+    /// let models = ListModelsResponse {
+    ///     object: "list".to_string(),
+    ///     data: vec![],
+    /// };
+    /// server.expect("GET", "/v1/models", MockResponse::json(200, &models));
+    ///
+    /// let client = server.client();
+    /// let err = client.list_models().await.unwrap_err();
+    /// assert!(matches!(err, Error::Api { status: 429, .. }));
+    /// assert!(client.list_models().await.is_ok());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn expect(&self, method: &str, path: &str, response: MockResponse) {
+        self.routes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry((method.to_uppercase(), path.to_string()))
+            .or_default()
+            .queue
+            .push_back(response);
+    }
+
+    /// Queues an error response to be returned once. See
+    /// `expect` and `MockResponse::error`.
+    pub fn expect_error(&self, method: &str, path: &str, status: u16, error: ApiError) {
+        self.expect(method, path, MockResponse::error(status, error));
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Reads a single request from the connection and writes
+/// back the matching response.
+async fn handle_conn(conn: TcpStream, routes: Routes) {
+    let mut conn = BufReader::new(conn);
+
+    // Read the request line...
+    let mut line = String::new();
+    if conn.read_line(&mut line).await.is_err() {
+        return;
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default();
+    let path = target.split('?').next().unwrap_or_default().to_string();
+
+    // Read the headers, keeping the body's length...
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        match conn.read_line(&mut line).await {
+            Ok(0) | Err(_) => return,
+            Ok(_) if line.trim().is_empty() => break,
+            Ok(_) => {}
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    // Skip the body...
+    let mut body = vec![0; content_length];
+    if conn.read_exact(&mut body).await.is_err() {
+        return;
+    }
+
+    // Find the response...
+    let response = routes
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_mut(&(method, path.clone()))
+        .and_then(|route| route.queue.pop_front().or_else(|| route.default.clone()))
+        .unwrap_or_else(|| {
+            MockResponse::error(
+                404,
+                ApiError {
+                    message: format!("No mock response for {}", path),
+                    type_: Some("invalid_request_error".to_string()),
+                    ..Default::default()
+                },
+            )
+        });
+
+    // Write it back...
+    let mut head = format!("HTTP/1.1 {} Mock\r\n", response.status);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "content-length: {}\r\nconnection: close\r\n\r\n",
+        response.body.len()
+    ));
+    let conn = conn.get_mut();
+    let _ = conn.write_all(head.as_bytes()).await;
+    let _ = conn.write_all(&response.body).await;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dtypes::{
+        ChatCompletionChunkChoice, ChatCompletionDelta, ChatCompletionRequest, FinishReason, Role,
+    };
+    use crate::stream::StreamEvent;
+    use futures_util::StreamExt;
+    use std::time::Duration;
+
+    fn chunk(content: &str) -> ChatCompletionChunk {
+        ChatCompletionChunk {
+            id: "chatcmpl-123".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: 1677652288,
+            model: "gpt-4o".to_string(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: 0,
+                delta: ChatCompletionDelta {
+                    content: Some(content.to_string()),
+                    ..Default::default()
+                },
+                finish_reason: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn mock_server_streams_chat_completion() -> crate::Result<()> {
+        let server = MockServer::start().await?;
+        server.mock(
+            "POST",
+            "/v1/chat/completions",
+            MockResponse::chat_completion_stream(&[chunk("Hello"), chunk(", world!")]),
+        );
+
+        // Drive the stream to the end...
+        let client = server.client();
+        let mut stream = client
+            .create_chat_completion_stream(ChatCompletionRequest::default())
+            .await?;
+        let mut content = String::new();
+        while let Some(chunk) = stream.next().await {
+            if let Some(text) = &chunk?.choices[0].delta.content {
+                content.push_str(text);
+            }
+        }
+        assert_eq!(content, "Hello, world!");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mock_server_unregistered_path_is_not_found() -> crate::Result<()> {
+        let server = MockServer::start().await?;
+        let err = server.client().list_models().await.unwrap_err();
+        assert!(matches!(err, crate::Error::Api { status: 404, .. }));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mock_server_scripts_retries() -> crate::Result<()> {
+        let server = MockServer::start().await?;
+        let rate_limited = ApiError {
+            message: "Rate limit reached".to_string(),
+            type_: Some("requests".to_string()),
+            ..Default::default()
+        };
+        server.expect_error("GET", "/v1/models", 429, rate_limited.clone());
+        server.expect_error("GET", "/v1/models", 429, rate_limited.clone());
+        server.mock(
+            "GET",
+            "/v1/models",
+            MockResponse::json(200, &serde_json::json!({"object": "list", "data": []})),
+        );
+
+        // A client that retries gets through...
+        let client = Client::builder()
+            .api_key("test")
+            .base_url(server.url())
+            .max_retries(2)
+            .retry_delay(Duration::from_millis(1), Duration::from_millis(10))
+            .build()?;
+        assert!(client.list_models().await.is_ok());
+
+        // ...and once the queue is drained, the default is returned...
+        server.expect_error("GET", "/v1/models", 429, rate_limited.clone());
+        let err = server.client().list_models().await.unwrap_err();
+        match err {
+            crate::Error::Api { status, error } => {
+                assert_eq!(status, 429);
+                assert_eq!(error, rate_limited);
+            }
+            err => panic!("expected an API error, got {:?}", err),
+        }
+        assert!(server.client().list_models().await.is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mock_server_streams_chat_completion_events() -> crate::Result<()> {
+        let server = MockServer::start().await?;
+        let start = ChatCompletionChunk {
+            choices: vec![ChatCompletionChunkChoice {
+                index: 0,
+                delta: ChatCompletionDelta {
+                    role: Some(Role::Assistant),
+                    ..Default::default()
+                },
+                finish_reason: None,
+            }],
+            ..chunk("")
+        };
+        let mut stop = chunk("");
+        stop.choices[0].finish_reason = Some(FinishReason::Stop);
+        server.mock(
+            "POST",
+            "/v1/chat/completions",
+            MockResponse::chat_completion_stream(&[start, chunk("Hello"), chunk(", world!"), stop]),
+        );
+
+        // The role-only delta starts the message, before any content...
+        let events: Vec<StreamEvent> = server
+            .client()
+            .create_chat_completion_events(ChatCompletionRequest::default())
+            .await?
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<crate::Result<_>>()?;
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::Start {
+                    role: Role::Assistant
+                },
+                StreamEvent::Content("Hello".to_string()),
+                StreamEvent::Content(", world!".to_string()),
+                StreamEvent::Done {
+                    finish_reason: FinishReason::Stop
+                },
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+//! Looks up facts about models that the API doesn't return,
+//! like the size of their context windows.
+//!
+//! Like the prices in `pricing`, the built-in values are a
+//! snapshot, so a `ContextWindows` table can be updated (or
+//! built from scratch) without waiting for a release.
+
+use std::collections::HashMap;
+
+/// The built-in context windows, in tokens.
+const DEFAULT_CONTEXT_WINDOWS: &[(&str, u32)] = &[
+    ("gpt-4o", 128_000),
+    ("gpt-4o-mini", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4-turbo-preview", 128_000),
+    ("gpt-4-1106-preview", 128_000),
+    ("gpt-4-0125-preview", 128_000),
+    ("gpt-4-vision-preview", 128_000),
+    ("gpt-4", 8_192),
+    ("gpt-4-0314", 8_192),
+    ("gpt-4-0613", 8_192),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4-32k-0314", 32_768),
+    ("gpt-4-32k-0613", 32_768),
+    ("gpt-3.5-turbo", 16_385),
+    ("gpt-3.5-turbo-0125", 16_385),
+    ("gpt-3.5-turbo-1106", 16_385),
+    ("gpt-3.5-turbo-0613", 4_096),
+    ("gpt-3.5-turbo-0301", 4_096),
+    ("gpt-3.5-turbo-16k", 16_385),
+    ("gpt-3.5-turbo-16k-0613", 16_385),
+    ("gpt-3.5-turbo-instruct", 4_096),
+    ("gpt-3.5-turbo-instruct-0914", 4_096),
+    ("o1", 200_000),
+    ("o1-preview", 128_000),
+    ("o1-mini", 128_000),
+    ("text-embedding-3-small", 8_191),
+    ("text-embedding-3-large", 8_191),
+    ("text-embedding-ada-002", 8_191),
+];
+
+/// A table of model context windows, in tokens.
+///
+/// A model is looked up by its exact name, or else with its
+/// `-YYYY-MM-DD` date suffix removed (so dated snapshots like
+/// `gpt-4o-2024-08-06` use the `gpt-4o` window). Older
+/// snapshots (e.g. `gpt-3.5-turbo-0613`) don't always share
+/// their base model's window, so they're listed by name.
+///
+/// # Example
+///
+/// ```
+/// use ohairs::models::ContextWindows;
+///
+/// let mut windows = ContextWindows::default();
+/// windows.insert("llama3", 8_192);
+///
+/// assert_eq!(windows.get("llama3"), Some(8_192));
+/// assert_eq!(windows.get("gpt-4o-2024-08-06"), Some(128_000));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextWindows {
+    windows: HashMap<String, u32>,
+}
+
+impl Default for ContextWindows {
+    /// Returns a table with the built-in context windows.
+    fn default() -> Self {
+        let windows = DEFAULT_CONTEXT_WINDOWS
+            .iter()
+            .map(|&(model, tokens)| (model.to_string(), tokens))
+            .collect();
+        Self { windows }
+    }
+}
+
+impl ContextWindows {
+    /// Creates an empty table, without the built-in context
+    /// windows.
+    pub fn empty() -> Self {
+        Self {
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Sets (or replaces) the context window of a model.
+    pub fn insert(&mut self, model: &str, tokens: u32) {
+        self.windows.insert(model.to_string(), tokens);
+    }
+
+    /// Removes a model's context window.
+    pub fn remove(&mut self, model: &str) -> Option<u32> {
+        self.windows.remove(model)
+    }
+
+    /// Returns the context window of a model, or `None` if
+    /// it's not in the table.
+    pub fn get(&self, model: &str) -> Option<u32> {
+        lookup(&self.windows, model).copied()
+    }
+}
+
+/// Returns the context window (in tokens) of `model`, using
+/// the built-in table, or `None` if it isn't known.
+///
+/// Use a `ContextWindows` table to override or add models.
+///
+/// # Example
+///
+/// ```
+/// use ohairs::models::model_context_window;
+///
+/// assert_eq!(model_context_window("gpt-4o-mini"), Some(128_000));
+/// assert_eq!(model_context_window("my-local-model"), None);
+/// ```
+pub fn model_context_window(model: &str) -> Option<u32> {
+    ContextWindows::default().get(model)
+}
+
+/// Looks up a model in a table keyed by model name, by its
+/// exact name or else by its name without a `-YYYY-MM-DD`
+/// date suffix.
+pub(crate) fn lookup<'a, T>(table: &'a HashMap<String, T>, model: &str) -> Option<&'a T> {
+    if let Some(value) = table.get(model) {
+        return Some(value);
+    }
+    table.get(strip_date(model)?)
+}
+
+/// Returns `model` without its `-YYYY-MM-DD` date suffix, or
+/// `None` if it doesn't have one.
+fn strip_date(model: &str) -> Option<&str> {
+    let split = model.len().checked_sub(11)?;
+    let (base, date) = (model.get(..split)?, model.get(split..)?.as_bytes());
+    let is_date = date.iter().enumerate().all(|(i, b)| match i {
+        0 | 5 | 8 => *b == b'-',
+        _ => b.is_ascii_digit(),
+    });
+    match is_date && !base.is_empty() {
+        true => Some(base),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn context_windows_match_snapshots() {
+        let windows = ContextWindows::default();
+
+        // Define cases to test...
+        let cases = vec![
+            ("gpt-4o", Some(128_000)),
+            ("gpt-4o-2024-08-06", Some(128_000)),
+            ("gpt-4-0613", Some(8_192)),
+            ("gpt-4-32k-0613", Some(32_768)),
+            ("gpt-3.5-turbo-instruct-0914", Some(4_096)),
+            ("gpt-3.5-turbo-0613", Some(4_096)),
+            ("gpt-3.5-turbo-0301", Some(4_096)),
+            ("gpt-3.5-turbo-0125", Some(16_385)),
+            ("gpt-4-turbo-2024-04-09", Some(128_000)),
+            ("gpt-4-0314-preview", None),
+            ("gpt-4o-2024-08", None),
+            ("gpt-4ox", None),
+            ("my-model", None),
+        ];
+
+        // Iterate over cases and test...
+        for (model, expected) in cases {
+            assert_eq!(windows.get(model), expected, "{}", model);
+        }
+    }
+
+    #[test]
+    fn context_windows_can_be_overridden() {
+        let mut windows = ContextWindows::default();
+        windows.insert("gpt-4o", 64_000);
+        assert_eq!(windows.get("gpt-4o-2024-08-06"), Some(64_000));
+
+        windows.remove("gpt-4o");
+        assert_eq!(windows.get("gpt-4o"), None);
+        assert_eq!(ContextWindows::empty().get("gpt-4"), None);
+    }
+}
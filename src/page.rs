@@ -0,0 +1,311 @@
+use futures_core::Stream;
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+use crate::Result;
+
+/// A single page of a cursor-paginated list (e.g. files or
+/// fine-tuning jobs).
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Page<T> {
+    /// The object type, which is always `list`.
+    pub object: String,
+
+    /// The items in this page.
+    pub data: Vec<T>,
+
+    /// Are there more items after this page?
+    #[serde(default)]
+    pub has_more: bool,
+
+    /// The ID of the first item in this page.
+    #[serde(default)]
+    pub first_id: Option<String>,
+
+    /// The ID of the last item in this page, which is the
+    /// cursor for the next page.
+    #[serde(default)]
+    pub last_id: Option<String>,
+}
+
+/// An item in a paginated list, whose ID is the cursor
+/// for the items after it.
+pub trait HasId {
+    /// The item's ID.
+    fn id(&self) -> &str;
+}
+
+impl<T: HasId> Page<T> {
+    /// Returns the params to fetch the page after this one,
+    /// or `None` if this is the last page.
+    ///
+    /// The cursor is `last_id`, or else the ID of the last
+    /// item, since some lists (e.g. fine-tuning jobs) don't
+    /// return `last_id`. The `limit` is carried over from
+    /// `params`.
+    pub fn next_params(&self, params: &ListParams) -> Option<ListParams> {
+        if !self.has_more {
+            return None;
+        }
+        let after = match &self.last_id {
+            Some(last_id) => last_id.clone(),
+            None => self.data.last()?.id().to_string(),
+        };
+        Some(ListParams {
+            after: Some(after),
+            limit: params.limit,
+        })
+    }
+}
+
+/// The query parameters for fetching a page of a list.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct ListParams {
+    /// A cursor, i.e. the ID of the item to start after.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+
+    /// The maximum number of items to return in the page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+/// Fetches every item in a paginated list, following the
+/// `after` cursors until the last page.
+///
+/// `fetch` is called with the params for each page (starting
+/// with `params`) and should return that page, e.g. by
+/// calling a `Client` list method.
+///
+/// # Example
+///
+/// ```no_run
+/// use ohairs::page::{list_all, ListParams};
+/// use ohairs::Client;
+///
+/// # async fn run() -> ohairs::Result<()> {
+/// let client = Client::new("test");
+/// let files = list_all(ListParams::default(), |params| client.list_files(params)).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn list_all<T, F, Fut>(params: ListParams, mut fetch: F) -> Result<Vec<T>>
+where
+    T: HasId,
+    F: FnMut(ListParams) -> Fut,
+    Fut: Future<Output = Result<Page<T>>>,
+{
+    let mut items = Vec::new();
+    let mut params = Some(params);
+    while let Some(current) = params {
+        let page = fetch(current.clone()).await?;
+        params = page.next_params(&current);
+        items.extend(page.data);
+    }
+    Ok(items)
+}
+
+/// Streams every item in a paginated list, fetching each
+/// page only once the items before it have been consumed.
+///
+/// Like `list_all`, `fetch` is called with the params for
+/// each page (starting with `params`). The stream ends after
+/// the last page, or after the first error.
+///
+/// # Example
+///
+/// ```no_run
+/// use futures_util::StreamExt;
+/// use ohairs::page::{list_stream, ListParams};
+/// use ohairs::Client;
+///
+/// # async fn run() -> ohairs::Result<()> {
+/// let client = Client::new("test");
+/// let mut files = list_stream(ListParams::default(), |params| client.list_files(params));
+/// while let Some(file) = files.next().await {
+///     println!("{}", file?.filename);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn list_stream<T, F, Fut>(
+    params: ListParams,
+    mut fetch: F,
+) -> impl Stream<Item = Result<T>> + Unpin
+where
+    T: HasId,
+    F: FnMut(ListParams) -> Fut,
+    Fut: Future<Output = Result<Page<T>>>,
+{
+    let pages = stream::unfold(Some(params), move |params| {
+        // Start fetching the next page, if there is one...
+        let next = params.map(|params| (fetch(params.clone()), params));
+        async move {
+            let (page, params) = next?;
+            match page.await {
+                Ok(page) => {
+                    let next = page.next_params(&params);
+                    Some((page.data.into_iter().map(Ok).collect(), next))
+                }
+                Err(err) => Some((vec![Err(err)], None)),
+            }
+        }
+    });
+    Box::pin(pages.flat_map(stream::iter))
+}
+
+/// Fetches every item in a paginated list with a blocking
+/// `fetch` (e.g. a `blocking::Client` list method), following
+/// the `after` cursors until the last page.
+pub fn list_all_blocking<T, F>(params: ListParams, mut fetch: F) -> Result<Vec<T>>
+where
+    T: HasId,
+    F: FnMut(ListParams) -> Result<Page<T>>,
+{
+    let mut items = Vec::new();
+    let mut params = Some(params);
+    while let Some(current) = params {
+        let page = fetch(current.clone())?;
+        params = page.next_params(&current);
+        items.extend(page.data);
+    }
+    Ok(items)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    impl HasId for &str {
+        fn id(&self) -> &str {
+            self
+        }
+    }
+
+    impl HasId for serde_json::Value {
+        fn id(&self) -> &str {
+            self["id"].as_str().unwrap_or_default()
+        }
+    }
+
+    #[tokio::test]
+    async fn list_all_follows_cursors() {
+        // Define the pages, keyed by the cursor that fetches them...
+        let pages = [
+            (None, vec!["a", "b"], true),
+            (Some("b"), vec!["c", "d"], true),
+            (Some("d"), vec!["e"], false),
+        ];
+
+        // Fetch them all...
+        let mut seen = Vec::new();
+        let items = list_all(
+            ListParams {
+                limit: Some(2),
+                ..Default::default()
+            },
+            |params| {
+                seen.push(params.clone());
+                let (_, data, has_more) = pages
+                    .iter()
+                    .find(|(after, _, _)| after.as_deref() == params.after.as_deref())
+                    .expect("expected a page for the cursor")
+                    .clone();
+                async move {
+                    Ok(Page {
+                        object: "list".to_string(),
+                        first_id: data.first().map(|id| id.to_string()),
+                        last_id: data.last().map(|id| id.to_string()),
+                        data,
+                        has_more,
+                    })
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+        // Check every item was returned, and the limit was kept...
+        assert_eq!(items, vec!["a", "b", "c", "d", "e"]);
+        assert_eq!(seen.len(), 3);
+        assert!(seen.iter().all(|params| params.limit == Some(2)));
+    }
+
+    #[test]
+    fn page_deserializes_without_cursor_fields() {
+        let page: Page<serde_json::Value> =
+            serde_json::from_str(r#"{"object": "list", "data": [{}]}"#).unwrap();
+        assert_eq!(page.data.len(), 1);
+        assert!(!page.has_more);
+        assert_eq!(page.next_params(&ListParams::default()), None);
+    }
+
+    #[test]
+    fn next_params_falls_back_to_last_item() {
+        let page: Page<serde_json::Value> = serde_json::from_str(
+            r#"{"object": "list", "data": [{"id": "ftjob-1"}, {"id": "ftjob-2"}], "has_more": true}"#,
+        )
+        .unwrap();
+        let params = ListParams {
+            limit: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            page.next_params(&params),
+            Some(ListParams {
+                after: Some("ftjob-2".to_string()),
+                limit: Some(2),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn list_stream_fetches_pages_lazily() {
+        let page = |data: Vec<&'static str>, has_more: bool| Page {
+            object: "list".to_string(),
+            last_id: data.last().map(|id| id.to_string()),
+            data,
+            has_more,
+            ..Default::default()
+        };
+
+        // Fetch the pages, counting each request...
+        let fetched = std::cell::Cell::new(0);
+        let mut items = list_stream(ListParams::default(), |params| {
+            fetched.set(fetched.get() + 1);
+            let page = match params.after.as_deref() {
+                None => page(vec!["a", "b"], true),
+                Some("b") => page(vec!["c"], false),
+                Some(after) => panic!("unexpected cursor {}", after),
+            };
+            async move { Ok(page) }
+        });
+
+        // The second page isn't fetched until the first is used up...
+        assert_eq!(items.next().await.unwrap().unwrap(), "a");
+        assert_eq!(items.next().await.unwrap().unwrap(), "b");
+        assert_eq!(fetched.get(), 1);
+        assert_eq!(items.next().await.unwrap().unwrap(), "c");
+        assert!(items.next().await.is_none());
+        assert_eq!(fetched.get(), 2);
+    }
+
+    #[test]
+    fn list_all_blocking_follows_cursors() {
+        let items = list_all_blocking(ListParams::default(), |params| {
+            let (data, has_more) = match params.after.as_deref() {
+                None => (vec!["a", "b"], true),
+                _ => (vec!["c"], false),
+            };
+            Ok(Page {
+                object: "list".to_string(),
+                data,
+                has_more,
+                ..Default::default()
+            })
+        })
+        .unwrap();
+        assert_eq!(items, vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,253 @@
+//! Estimates the dollar cost of a request from its token
+//! usage.
+//!
+//! The built-in prices are a snapshot and will drift as
+//! OpenAI changes them, so a `PriceTable` can be updated
+//! (or built from scratch) without waiting for a release.
+
+use std::collections::HashMap;
+
+use crate::dtypes::ChatCompletionUsage;
+use crate::models::lookup;
+
+/// The built-in prices, in USD per 1K tokens, as
+/// `(model, input, output, cached input)`.
+const DEFAULT_PRICES: &[(&str, f64, f64, Option<f64>)] = &[
+    ("gpt-4o", 0.0025, 0.01, Some(0.00125)),
+    ("gpt-4o-mini", 0.00015, 0.0006, Some(0.000075)),
+    ("gpt-4-turbo", 0.01, 0.03, None),
+    ("gpt-4-turbo-preview", 0.01, 0.03, None),
+    ("gpt-4-1106-preview", 0.01, 0.03, None),
+    ("gpt-4-0125-preview", 0.01, 0.03, None),
+    ("gpt-4-vision-preview", 0.01, 0.03, None),
+    ("gpt-4", 0.03, 0.06, None),
+    ("gpt-4-0314", 0.03, 0.06, None),
+    ("gpt-4-0613", 0.03, 0.06, None),
+    ("gpt-4-32k", 0.06, 0.12, None),
+    ("gpt-4-32k-0314", 0.06, 0.12, None),
+    ("gpt-4-32k-0613", 0.06, 0.12, None),
+    ("gpt-3.5-turbo", 0.0005, 0.0015, None),
+    ("gpt-3.5-turbo-0125", 0.0005, 0.0015, None),
+    ("gpt-3.5-turbo-1106", 0.001, 0.002, None),
+    ("gpt-3.5-turbo-0613", 0.0015, 0.002, None),
+    ("gpt-3.5-turbo-16k", 0.003, 0.004, None),
+    ("gpt-3.5-turbo-16k-0613", 0.003, 0.004, None),
+    ("gpt-3.5-turbo-instruct", 0.0015, 0.002, None),
+    ("gpt-3.5-turbo-instruct-0914", 0.0015, 0.002, None),
+    ("o1", 0.015, 0.06, Some(0.0075)),
+    ("o1-preview", 0.015, 0.06, Some(0.0075)),
+    ("o1-mini", 0.003, 0.012, Some(0.0015)),
+    ("text-embedding-3-small", 0.00002, 0.0, None),
+    ("text-embedding-3-large", 0.00013, 0.0, None),
+    ("text-embedding-ada-002", 0.0001, 0.0, None),
+];
+
+/// The price of a model, in USD per 1K tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    /// The price of prompt tokens.
+    pub input: f64,
+
+    /// The price of completion tokens (including reasoning
+    /// tokens).
+    pub output: f64,
+
+    /// The discounted price of prompt tokens read from the
+    /// prompt cache, if the model has one. Cached tokens are
+    /// billed at `input` otherwise.
+    pub cached_input: Option<f64>,
+}
+
+impl ModelPrice {
+    /// Returns the cost of the given usage at this price.
+    pub fn cost(&self, usage: &ChatCompletionUsage) -> f64 {
+        let cached = usage
+            .prompt_tokens_details
+            .map_or(0, |details| details.cached_tokens)
+            .min(usage.prompt_tokens);
+        let uncached = usage.prompt_tokens - cached;
+        let cached_input = self.cached_input.unwrap_or(self.input);
+        (uncached as f64 * self.input
+            + cached as f64 * cached_input
+            + usage.completion_tokens as f64 * self.output)
+            / 1000.0
+    }
+}
+
+/// A table of model prices.
+///
+/// A model's price is looked up by its exact name, or else
+/// with its `-YYYY-MM-DD` date suffix removed (so dated
+/// snapshots like `gpt-4o-2024-08-06` use the `gpt-4o`
+/// price). Previews and older snapshots are priced by name.
+///
+/// # Example
+///
+/// ```
+/// use ohairs::dtypes::ChatCompletionUsage;
+/// use ohairs::pricing::{ModelPrice, PriceTable};
+///
+/// let mut prices = PriceTable::default();
+/// prices.insert(
+///     "my-fine-tune",
+///     ModelPrice {
+///         input: 0.003,
+///         output: 0.006,
+///         cached_input: None,
+///     },
+/// );
+///
+/// let usage = ChatCompletionUsage {
+///     prompt_tokens: 1000,
+///     completion_tokens: 1000,
+///     total_tokens: 2000,
+///     ..Default::default()
+/// };
+/// let cost = prices.estimate_cost("my-fine-tune", &usage).unwrap();
+/// assert!((cost - 0.009).abs() < 1e-9);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceTable {
+    prices: HashMap<String, ModelPrice>,
+}
+
+impl Default for PriceTable {
+    /// Returns a table with the built-in prices.
+    fn default() -> Self {
+        let prices = DEFAULT_PRICES
+            .iter()
+            .map(|&(model, input, output, cached_input)| {
+                let price = ModelPrice {
+                    input,
+                    output,
+                    cached_input,
+                };
+                (model.to_string(), price)
+            })
+            .collect();
+        Self { prices }
+    }
+}
+
+impl PriceTable {
+    /// Creates an empty table, without the built-in prices.
+    pub fn empty() -> Self {
+        Self {
+            prices: HashMap::new(),
+        }
+    }
+
+    /// Sets (or replaces) the price of a model.
+    pub fn insert(&mut self, model: &str, price: ModelPrice) {
+        self.prices.insert(model.to_string(), price);
+    }
+
+    /// Removes a model's price.
+    pub fn remove(&mut self, model: &str) -> Option<ModelPrice> {
+        self.prices.remove(model)
+    }
+
+    /// Returns the price of a model, or `None` if it's not
+    /// in the table.
+    pub fn get(&self, model: &str) -> Option<&ModelPrice> {
+        lookup(&self.prices, model)
+    }
+
+    /// Estimates the cost, in USD, of a request to `model`
+    /// that used `usage`, or `None` if the model's price
+    /// isn't known.
+    pub fn estimate_cost(&self, model: &str, usage: &ChatCompletionUsage) -> Option<f64> {
+        self.get(model).map(|price| price.cost(usage))
+    }
+}
+
+/// Estimates the cost, in USD, of a request to `model` that
+/// used `usage`, using the built-in prices. Returns `None` if
+/// the model's price isn't known.
+///
+/// Use a `PriceTable` to override or add prices.
+pub fn estimate_cost(model: &str, usage: &ChatCompletionUsage) -> Option<f64> {
+    PriceTable::default().estimate_cost(model, usage)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dtypes::PromptTokensDetails;
+
+    fn usage(prompt_tokens: u64, completion_tokens: u64) -> ChatCompletionUsage {
+        ChatCompletionUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn price_table_matches_snapshots() {
+        let prices = PriceTable::default();
+
+        // Define cases to test...
+        let cases = vec![
+            ("gpt-4o", Some(0.0025)),
+            ("gpt-4o-2024-08-06", Some(0.0025)),
+            ("gpt-4o-mini-2024-07-18", Some(0.00015)),
+            ("gpt-4-0613", Some(0.03)),
+            ("gpt-4-turbo-2024-04-09", Some(0.01)),
+            ("gpt-4-1106-preview", Some(0.01)),
+            ("gpt-4-vision-preview", Some(0.01)),
+            ("gpt-3.5-turbo-instruct", Some(0.0015)),
+            ("gpt-3.5-turbo-0613", Some(0.0015)),
+            ("gpt-4-0613-preview", None),
+            ("gpt-4ox", None),
+            ("my-model", None),
+        ];
+
+        // Iterate over cases and test...
+        for (model, input) in cases {
+            let price = prices.get(model).map(|price| price.input);
+            assert_eq!(price, input, "{}", model);
+        }
+    }
+
+    #[test]
+    fn estimate_cost_uses_cached_price() {
+        // 1K prompt and 1K completion tokens...
+        let cost = estimate_cost("gpt-4o", &usage(1000, 1000)).unwrap();
+        assert!((cost - 0.0125).abs() < 1e-9, "{}", cost);
+
+        // ...with half the prompt cached...
+        let cached = ChatCompletionUsage {
+            prompt_tokens_details: Some(PromptTokensDetails {
+                cached_tokens: 500,
+                audio_tokens: 0,
+            }),
+            ..usage(1000, 1000)
+        };
+        let cost = estimate_cost("gpt-4o", &cached).unwrap();
+        assert!((cost - 0.011875).abs() < 1e-9, "{}", cost);
+
+        // ...and unknown models aren't guessed...
+        assert_eq!(estimate_cost("my-model", &usage(1000, 1000)), None);
+    }
+
+    #[test]
+    fn price_table_can_be_overridden() {
+        let mut prices = PriceTable::default();
+        prices.insert(
+            "gpt-4o",
+            ModelPrice {
+                input: 0.001,
+                output: 0.002,
+                cached_input: None,
+            },
+        );
+        let cost = prices.estimate_cost("gpt-4o", &usage(1000, 1000)).unwrap();
+        assert!((cost - 0.003).abs() < 1e-9, "{}", cost);
+
+        prices.remove("gpt-4o");
+        assert_eq!(prices.estimate_cost("gpt-4o", &usage(1, 1)), None);
+        assert_eq!(PriceTable::empty().get("gpt-4"), None);
+    }
+}
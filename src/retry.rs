@@ -0,0 +1,242 @@
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The header the API uses to say how many seconds to wait
+/// before retrying.
+pub const RETRY_AFTER_HEADER: &str = "retry-after";
+
+/// The header the API uses to say how many milliseconds to
+/// wait before retrying. Takes precedence over `retry-after`.
+pub const RETRY_AFTER_MS_HEADER: &str = "retry-after-ms";
+
+/// The header retried `POST` requests carry, so the API can
+/// tell repeats of the same request apart from new ones.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// The longest `retry-after` delay a client waits for. If
+/// the API asks for a longer wait, the response is returned
+/// instead of retried.
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// A function that generates a new idempotency key.
+pub type IdempotencyKeyFn = Arc<dyn Fn() -> String + Send + Sync>;
+
+/// Controls how a `Client` retries requests that failed with
+/// a transient error.
+///
+/// A request is retried if the API responded with `429 Too
+/// Many Requests` or a `500`, `502` or `503` server error.
+/// Other errors (e.g. `400` or `401`) are returned
+/// immediately, since retrying wouldn't change the outcome.
+///
+/// Between attempts the client waits for the time given by
+/// the response's `retry-after-ms` or `retry-after` header
+/// (up to `MAX_RETRY_AFTER`), or otherwise backs off
+/// exponentially (with jitter) from `base_delay`, up to
+/// `max_delay`.
+///
+/// The default policy doesn't retry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximum number of times a request is retried
+    /// (so it's sent at most `max_retries + 1` times).
+    pub max_retries: u32,
+
+    /// The delay before the first retry, which doubles
+    /// with each further retry.
+    pub base_delay: Duration,
+
+    /// The longest the backoff delay can grow to.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns true if a response with the given status
+    /// should be retried.
+    pub fn is_retryable(status: StatusCode) -> bool {
+        matches!(status.as_u16(), 429 | 500 | 502 | 503)
+    }
+
+    /// Returns how long to wait before the next attempt,
+    /// given the number of retries already made and the
+    /// headers of the failed response.
+    ///
+    /// Returns `None` if the response asked for a wait longer
+    /// than `MAX_RETRY_AFTER`, so it shouldn't be retried.
+    pub fn delay(&self, retries: u32, headers: &HeaderMap) -> Option<Duration> {
+        match retry_after(headers) {
+            Some(delay) if delay > MAX_RETRY_AFTER => None,
+            Some(delay) => Some(delay),
+            None => Some(self.backoff(retries)),
+        }
+    }
+
+    /// Returns the exponential backoff delay (before jitter
+    /// is applied) for the given number of retries.
+    fn max_backoff(&self, retries: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retries);
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// Returns the exponential backoff delay with jitter,
+    /// somewhere between half and all of `max_backoff`, so
+    /// that clients that failed together don't all retry
+    /// at the same moment.
+    fn backoff(&self, retries: u32) -> Duration {
+        let delay = self.max_backoff(retries);
+        let jitter = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        delay.mul_f64(0.5 + jitter / 2.0)
+    }
+}
+
+/// Generates a random (version 4) UUID, used as the default
+/// idempotency key.
+pub fn new_idempotency_key() -> String {
+    // Fill 16 bytes with randomness...
+    let mut bytes = [0u8; 16];
+    for chunk in bytes.chunks_mut(8) {
+        let random = RandomState::new().build_hasher().finish();
+        chunk.copy_from_slice(&random.to_le_bytes());
+    }
+
+    // Mark it as a version 4, variant 1 UUID...
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    // Format it as 8-4-4-4-12 hex digits...
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Parses the delay requested by the `retry-after-ms` or
+/// `retry-after` header, if present. Only the delay-seconds
+/// form of `retry-after` is supported (not HTTP dates).
+/// Values too large for a `Duration` saturate to
+/// `Duration::MAX`.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let parse = |name: &str, scale: f64| -> Option<Duration> {
+        let value = headers
+            .get(name)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse::<f64>()
+            .ok()?;
+        if value.is_nan() || value < 0.0 {
+            return None;
+        }
+        Some(Duration::try_from_secs_f64(value * scale).unwrap_or(Duration::MAX))
+    };
+    parse(RETRY_AFTER_MS_HEADER, 0.001).or_else(|| parse(RETRY_AFTER_HEADER, 1.0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn retry_policy_retryable_statuses() {
+        // Define cases to test...
+        let cases = vec![
+            (429, true),
+            (500, true),
+            (502, true),
+            (503, true),
+            (400, false),
+            (401, false),
+            (404, false),
+        ];
+
+        // Iterate over cases and test...
+        for (status, expected) in cases {
+            let status = StatusCode::from_u16(status).unwrap();
+            assert_eq!(RetryPolicy::is_retryable(status), expected, "{}", status);
+        }
+    }
+
+    #[test]
+    fn retry_policy_backoff_grows_and_caps() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+        assert_eq!(policy.max_backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.max_backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.max_backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.max_backoff(4), Duration::from_secs(1));
+        assert_eq!(policy.max_backoff(100), Duration::from_secs(1));
+
+        // Jitter keeps the delay within half to all of the backoff...
+        for retries in 0..5 {
+            let delay = policy.delay(retries, &HeaderMap::new()).unwrap();
+            let max = policy.max_backoff(retries);
+            assert!(delay >= max / 2 && delay <= max, "{:?} vs {:?}", delay, max);
+        }
+    }
+
+    #[test]
+    fn retry_policy_honors_retry_after() {
+        let policy = RetryPolicy::default();
+
+        // Define cases to test...
+        let cases = vec![
+            (vec![("retry-after", "2")], Some(Duration::from_secs(2))),
+            (
+                vec![("retry-after-ms", "150")],
+                Some(Duration::from_millis(150)),
+            ),
+            (
+                vec![("retry-after", "2"), ("retry-after-ms", "150")],
+                Some(Duration::from_millis(150)),
+            ),
+            (vec![("retry-after", "60")], Some(MAX_RETRY_AFTER)),
+            // Waits that are too long aren't retried...
+            (vec![("retry-after", "3600")], None),
+            (vec![("retry-after", "1e30")], None),
+            (vec![("retry-after-ms", "1e300")], None),
+        ];
+
+        // Iterate over cases and test...
+        for (headers, expected) in cases {
+            let mut map = HeaderMap::new();
+            for (name, value) in headers {
+                map.insert(name, value.parse().unwrap());
+            }
+            assert_eq!(policy.delay(5, &map), expected);
+        }
+    }
+
+    #[test]
+    fn idempotency_keys_are_uuids() {
+        let key = new_idempotency_key();
+        let groups: Vec<_> = key.split('-').map(str::len).collect();
+        assert_eq!(groups, vec![8, 4, 4, 4, 12]);
+        assert_eq!(&key[14..15], "4");
+        assert!(matches!(&key[19..20], "8" | "9" | "a" | "b"), "{}", key);
+        assert_ne!(key, new_idempotency_key());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{Error, Result};
+
+/// Splits a server-sent event stream, received as arbitrary
+/// chunks of bytes, into complete events.
+///
+/// The OpenAI API sends each event as a single line (e.g.
+/// `data: {...}`), separated by blank lines. The bytes read
+/// from a response body don't respect those boundaries, so
+/// the decoder buffers partial lines until they're complete
+/// and hands back each event exactly once. Blank separator
+/// lines are skipped.
+///
+/// # Example
+///
+/// ```
+/// use ohairs::sse::SseDecoder;
+///
+/// let mut decoder = SseDecoder::new();
+/// decoder.feed(b"data: {\"a\"");
+/// assert!(decoder.next_event().is_none());
+///
+/// decoder.feed(b": 1}\n\ndata: [DONE]");
+/// assert_eq!(decoder.next_event().unwrap().unwrap(), "data: {\"a\": 1}");
+/// assert!(decoder.next_event().is_none());
+///
+/// // At the end of the stream, the final line is flushed
+/// // even without a trailing newline...
+/// decoder.finish();
+/// assert_eq!(decoder.next_event().unwrap().unwrap(), "data: [DONE]");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct SseDecoder {
+    /// Bytes that have been fed in but not yet returned
+    /// as part of an event.
+    buffer: Vec<u8>,
+
+    /// Has the end of the stream been reached?
+    finished: bool,
+}
+
+impl SseDecoder {
+    /// Create a new, empty decoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the next chunk of bytes from the stream.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Mark the end of the stream.
+    ///
+    /// Any trailing line that wasn't terminated by a newline
+    /// is then returned by `next_event`, rather than waiting
+    /// for the rest of a line that will never arrive.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// Returns the next complete event, if one is available.
+    ///
+    /// The event is returned as its full line (e.g.
+    /// `data: {...}`) with surrounding whitespace trimmed, ready
+    /// to be parsed with `ChatCompletionChunk::from_chunk`.
+    /// Returns an error if a line isn't valid UTF-8.
+    pub fn next_event(&mut self) -> Option<Result<String>> {
+        loop {
+            // Find the end of the next line...
+            let line = match self.buffer.iter().position(|&b| b == b'\n') {
+                Some(pos) => self.buffer.drain(..=pos).collect::<Vec<u8>>(),
+                None if self.finished && !self.buffer.is_empty() => {
+                    std::mem::take(&mut self.buffer)
+                }
+                None => return None,
+            };
+
+            // Decode it...
+            let line = match String::from_utf8(line) {
+                Ok(line) => line,
+                Err(_) => {
+                    return Some(Err(Error::InvalidChunk(
+                        "Chunk wasn't valid UTF-8".to_string(),
+                    )))
+                }
+            };
+
+            // Skip the blank lines separating events...
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            return Some(Ok(line.to_string()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PAYLOAD: &str = "data: {\"id\": \"a\", \"content\": \"héllo 👋\"}\n\n\
+        data: {\"id\": \"b\"}\r\n\r\n\
+        data: {\"id\": \"c\"}\n\n\
+        data: [DONE]\n\n";
+
+    /// Feeds the given chunks into a decoder and returns
+    /// every event it produces.
+    fn decode(chunks: &[&[u8]]) -> Vec<String> {
+        let mut decoder = SseDecoder::new();
+        let mut events = Vec::new();
+        for chunk in chunks {
+            decoder.feed(chunk);
+            while let Some(event) = decoder.next_event() {
+                events.push(event.expect("expected event to decode"));
+            }
+        }
+        decoder.finish();
+        while let Some(event) = decoder.next_event() {
+            events.push(event.expect("expected event to decode"));
+        }
+        events
+    }
+
+    #[test]
+    fn sse_decoder_whole_payload() {
+        assert_eq!(
+            decode(&[PAYLOAD.as_bytes()]),
+            vec![
+                "data: {\"id\": \"a\", \"content\": \"héllo 👋\"}",
+                "data: {\"id\": \"b\"}",
+                "data: {\"id\": \"c\"}",
+                "data: [DONE]",
+            ]
+        );
+    }
+
+    #[test]
+    fn sse_decoder_split_at_every_offset() {
+        let payload = PAYLOAD.as_bytes();
+        let expected = decode(&[payload]);
+        for i in 0..=payload.len() {
+            let (a, b) = payload.split_at(i);
+            assert_eq!(decode(&[a, b]), expected, "split at {} didn't match", i);
+        }
+    }
+
+    #[test]
+    fn sse_decoder_byte_at_a_time() {
+        let payload = PAYLOAD.as_bytes();
+        let chunks: Vec<&[u8]> = payload.chunks(1).collect();
+        assert_eq!(decode(&chunks), decode(&[payload]));
+    }
+
+    #[test]
+    fn sse_decoder_flushes_trailing_line() {
+        // Without finishing, a partial line is held back...
+        let mut decoder = SseDecoder::new();
+        decoder.feed(b"data: {}\n\ndata: [DONE]");
+        assert_eq!(decoder.next_event().unwrap().unwrap(), "data: {}");
+        assert!(decoder.next_event().is_none());
+
+        // ...and returned once the stream ends...
+        decoder.finish();
+        assert_eq!(decoder.next_event().unwrap().unwrap(), "data: [DONE]");
+        assert!(decoder.next_event().is_none());
+    }
+
+    #[test]
+    fn sse_decoder_invalid_utf8() {
+        let mut decoder = SseDecoder::new();
+        decoder.feed(b"data: \xff\n\ndata: {}\n\n");
+        assert!(decoder.next_event().unwrap().is_err());
+        assert_eq!(decoder.next_event().unwrap().unwrap(), "data: {}");
+    }
+}
@@ -0,0 +1,620 @@
+use futures_core::Stream;
+use futures_util::stream::{self, StreamExt};
+use std::collections::BTreeMap;
+
+use crate::dtypes::{
+    ChatCompletionChoice, ChatCompletionChunk, ChatCompletionMessage, ChatCompletionObject,
+    ChatCompletionUsage, FinishReason, FunctionCall, MessageContent, Role, ToolCall, ToolType,
+};
+use crate::Result;
+
+/// Folds the chunks of a streamed chat completion back into
+/// the `ChatCompletionObject` a non-streamed request would
+/// have returned.
+///
+/// # Example
+///
+/// ```no_run
+/// use futures_util::StreamExt;
+/// use ohairs::{stream::ChatCompletionAccumulator, Client};
+///
+/// # async fn run() -> ohairs::Result<()> {
+/// let client = Client::new("test");
+/// let mut stream = client.chat("gpt-4o").user("Hi!").stream().await?;
+///
+/// let mut acc = ChatCompletionAccumulator::new();
+/// while let Some(chunk) = stream.next().await {
+///     acc.push(chunk?);
+/// }
+/// let res = acc.finish();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ChatCompletionAccumulator {
+    id: String,
+    created: u64,
+    model: String,
+
+    /// The choices assembled so far, keyed by their index.
+    choices: BTreeMap<u64, ChoiceAccumulator>,
+
+    /// The usage statistics, if the stream included them.
+    usage: Option<ChatCompletionUsage>,
+}
+
+/// The parts of a single choice assembled so far.
+#[derive(Debug, Default, Clone)]
+struct ChoiceAccumulator {
+    role: Option<Role>,
+    content: Option<String>,
+    function_call: Option<FunctionCall>,
+
+    /// The tool calls assembled so far, keyed by their index.
+    tool_calls: BTreeMap<u64, ToolCallAccumulator>,
+    finish_reason: Option<FinishReason>,
+}
+
+/// The parts of a single tool call assembled so far.
+///
+/// The ID, type and name are kept from the first fragment
+/// that has them, since some backends repeat them in every
+/// fragment.
+#[derive(Debug, Default, Clone)]
+struct ToolCallAccumulator {
+    id: Option<String>,
+    type_: Option<ToolType>,
+    name: Option<String>,
+    arguments: String,
+}
+
+impl ChatCompletionAccumulator {
+    /// Create a new, empty accumulator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the next chunk from the stream.
+    pub fn push(&mut self, chunk: ChatCompletionChunk) {
+        // Keep the response's metadata...
+        self.id = chunk.id;
+        self.created = chunk.created;
+        self.model = chunk.model;
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage;
+        }
+
+        // Merge each choice's delta into its message...
+        for choice in chunk.choices {
+            let acc = self.choices.entry(choice.index).or_default();
+            let delta = choice.delta;
+            if let Some(role) = delta.role {
+                acc.role = Some(role);
+            }
+            if let Some(content) = delta.content {
+                acc.content
+                    .get_or_insert_with(String::new)
+                    .push_str(&content);
+            }
+            if let Some(function_call) = delta.function_call {
+                let call = acc.function_call.get_or_insert_with(FunctionCall::default);
+                if let Some(name) = function_call.name.filter(|_| call.name.is_empty()) {
+                    call.name = name;
+                }
+                if let Some(arguments) = function_call.arguments {
+                    call.arguments.push_str(&arguments);
+                }
+            }
+            for tool_call in delta.tool_calls.into_iter().flatten() {
+                let call = acc.tool_calls.entry(tool_call.index).or_default();
+                if call.id.is_none() {
+                    call.id = tool_call.id;
+                }
+                if call.type_.is_none() {
+                    call.type_ = tool_call
+                        .type_
+                        .and_then(|type_| serde_json::from_value(type_.into()).ok());
+                }
+                if let Some(function) = tool_call.function {
+                    if call.name.is_none() {
+                        call.name = function.name;
+                    }
+                    if let Some(arguments) = function.arguments {
+                        call.arguments.push_str(&arguments);
+                    }
+                }
+            }
+            if choice.finish_reason.is_some() {
+                acc.finish_reason = choice.finish_reason;
+            }
+        }
+    }
+
+    /// Return the assembled chat completion.
+    pub fn finish(self) -> ChatCompletionObject {
+        let choices = self
+            .choices
+            .into_iter()
+            .map(|(index, acc)| ChatCompletionChoice {
+                index,
+                message: ChatCompletionMessage {
+                    role: acc.role.unwrap_or(Role::Assistant),
+                    content: acc.content.map(MessageContent::Text),
+                    function_call: acc.function_call,
+                    tool_calls: match acc.tool_calls.is_empty() {
+                        true => None,
+                        false => Some(
+                            acc.tool_calls
+                                .into_values()
+                                .map(|call| ToolCall {
+                                    id: call.id.unwrap_or_default(),
+                                    type_: call.type_.unwrap_or_default(),
+                                    function: FunctionCall {
+                                        name: call.name.unwrap_or_default(),
+                                        arguments: call.arguments,
+                                    },
+                                })
+                                .collect(),
+                        ),
+                    },
+                    ..Default::default()
+                },
+                finish_reason: acc.finish_reason.unwrap_or(FinishReason::Unknown),
+                ..Default::default()
+            })
+            .collect();
+        ChatCompletionObject {
+            id: self.id,
+            object: "chat.completion".to_string(),
+            created: self.created,
+            model: self.model,
+            choices,
+            usage: self.usage.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+/// An event in a streamed chat completion, derived from the
+/// first choice of each chunk.
+///
+/// A stream of events always begins with a `Start`, even if
+/// the API never sent a role-only delta, and ends with a
+/// `Done` once the choice has a finish reason.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// The message has started, with the given role.
+    Start {
+        /// The role of the message's author.
+        role: Role,
+    },
+
+    /// The next piece of the message's content.
+    Content(String),
+
+    /// The message is complete.
+    Done {
+        /// Why the model stopped generating.
+        finish_reason: FinishReason,
+    },
+}
+
+/// Turns a stream of chat completion chunks into a stream of
+/// `StreamEvent`s.
+///
+/// Only the first choice's role, content and finish reason
+/// are surfaced; use a `ChatCompletionAccumulator` for tool
+/// calls or multiple choices. Errors are passed through.
+///
+/// # Example
+///
+/// ```no_run
+/// use futures_util::StreamExt;
+/// use ohairs::stream::{stream_events, StreamEvent};
+/// use ohairs::Client;
+///
+/// # async fn run() -> ohairs::Result<()> {
+/// let client = Client::new("test");
+/// let chunks = client.chat("gpt-4o").user("Hi!").stream().await?;
+///
+/// let mut events = stream_events(chunks);
+/// while let Some(event) = events.next().await {
+///     match event? {
+///         StreamEvent::Start { role } => println!("{:?}:", role),
+///         StreamEvent::Content(text) => print!("{}", text),
+///         StreamEvent::Done { .. } => println!(),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn stream_events<S>(chunks: S) -> impl Stream<Item = Result<StreamEvent>> + Unpin + Send
+where
+    S: Stream<Item = Result<ChatCompletionChunk>> + Unpin + Send,
+{
+    let mut started = false;
+    chunks.flat_map(move |chunk| {
+        let events = match chunk {
+            Ok(chunk) => chunk_events(&mut started, chunk)
+                .into_iter()
+                .map(Ok)
+                .collect(),
+            Err(err) => vec![Err(err)],
+        };
+        stream::iter(events)
+    })
+}
+
+/// Turns an iterator of chat completion chunks (e.g. from
+/// the blocking client) into an iterator of `StreamEvent`s.
+///
+/// See `stream_events`.
+pub fn iter_events<I>(chunks: I) -> impl Iterator<Item = Result<StreamEvent>>
+where
+    I: Iterator<Item = Result<ChatCompletionChunk>>,
+{
+    let mut started = false;
+    chunks.flat_map(move |chunk| match chunk {
+        Ok(chunk) => chunk_events(&mut started, chunk)
+            .into_iter()
+            .map(Ok)
+            .collect(),
+        Err(err) => vec![Err(err)],
+    })
+}
+
+/// Returns the events for a single chunk, emitting a `Start`
+/// first if the message hasn't `started` yet.
+fn chunk_events(started: &mut bool, chunk: ChatCompletionChunk) -> Vec<StreamEvent> {
+    let mut events = Vec::new();
+    let Some(choice) = chunk.choices.into_iter().next() else {
+        return events;
+    };
+
+    // Start the message, from the role-only delta if there is one...
+    let delta = choice.delta;
+    let has_content = delta.content.as_deref().is_some_and(|c| !c.is_empty());
+    if !*started && (delta.role.is_some() || has_content || choice.finish_reason.is_some()) {
+        *started = true;
+        events.push(StreamEvent::Start {
+            role: delta.role.unwrap_or(Role::Assistant),
+        });
+    }
+
+    // Then any content and the finish reason...
+    if let Some(content) = delta.content.filter(|c| !c.is_empty()) {
+        events.push(StreamEvent::Content(content));
+    }
+    if let Some(finish_reason) = choice.finish_reason {
+        events.push(StreamEvent::Done { finish_reason });
+    }
+    events
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dtypes::{
+        ChatCompletionChunkChoice, ChatCompletionDelta, FunctionCallDelta, ToolCallDelta,
+    };
+
+    fn chunk(
+        delta: ChatCompletionDelta,
+        finish_reason: Option<FinishReason>,
+    ) -> ChatCompletionChunk {
+        ChatCompletionChunk {
+            id: "chatcmpl-123".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: 1677652288,
+            model: "gpt-3.5-turbo".to_string(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: 0,
+                delta,
+                finish_reason,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn content(text: &str) -> ChatCompletionDelta {
+        ChatCompletionDelta {
+            content: Some(text.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accumulator_concatenates_content() {
+        let mut acc = ChatCompletionAccumulator::new();
+        acc.push(chunk(
+            ChatCompletionDelta {
+                role: Some(Role::Assistant),
+                ..Default::default()
+            },
+            None,
+        ));
+        acc.push(chunk(content("Hello"), None));
+        acc.push(chunk(content(", world!"), None));
+        acc.push(chunk(
+            ChatCompletionDelta::default(),
+            Some(FinishReason::Stop),
+        ));
+        let res = acc.finish();
+
+        assert_eq!(res.id, "chatcmpl-123");
+        assert_eq!(res.object, "chat.completion");
+        assert_eq!(res.created, 1677652288);
+        assert_eq!(res.model, "gpt-3.5-turbo");
+        assert_eq!(
+            res.choices,
+            vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatCompletionMessage {
+                    role: Role::Assistant,
+                    content: Some("Hello, world!".into()),
+                    ..Default::default()
+                },
+                finish_reason: FinishReason::Stop,
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn accumulator_merges_function_call_fragments() {
+        let call = |name: Option<&str>, arguments: &str| ChatCompletionDelta {
+            function_call: Some(FunctionCallDelta {
+                name: name.map(str::to_string),
+                arguments: Some(arguments.to_string()),
+            }),
+            ..Default::default()
+        };
+
+        let mut acc = ChatCompletionAccumulator::new();
+        acc.push(chunk(call(Some("get_weather"), ""), None));
+        acc.push(chunk(call(None, "{\"loc\": "), None));
+        acc.push(chunk(call(None, "\"Los Angeles\"}"), None));
+        acc.push(chunk(
+            ChatCompletionDelta::default(),
+            Some(FinishReason::FunctionCall),
+        ));
+        let res = acc.finish();
+
+        let message = &res.choices[0].message;
+        assert_eq!(message.content, None);
+        assert_eq!(
+            message.function_call,
+            Some(FunctionCall {
+                name: "get_weather".to_string(),
+                arguments: "{\"loc\": \"Los Angeles\"}".to_string(),
+            })
+        );
+        assert_eq!(res.choices[0].finish_reason, FinishReason::FunctionCall);
+    }
+
+    #[test]
+    fn accumulator_keeps_usage_chunk() {
+        let mut acc = ChatCompletionAccumulator::new();
+        acc.push(chunk(content("Hi"), Some(FinishReason::Stop)));
+
+        // The usage arrives in a final chunk without choices...
+        let usage = ChatCompletionUsage {
+            prompt_tokens: 9,
+            completion_tokens: 1,
+            total_tokens: 10,
+            ..Default::default()
+        };
+        acc.push(ChatCompletionChunk {
+            choices: vec![],
+            usage: Some(usage.clone()),
+            ..chunk(ChatCompletionDelta::default(), None)
+        });
+        let res = acc.finish();
+        assert_eq!(res.usage, usage);
+        assert_eq!(res.choices.len(), 1);
+        assert_eq!(res.first_content(), Some("Hi"));
+    }
+
+    #[test]
+    fn accumulator_keeps_interleaved_choices_apart() {
+        let at = |index: u64, text: &str, finish_reason: Option<FinishReason>| {
+            let mut chunk = chunk(content(text), finish_reason);
+            chunk.choices[0].index = index;
+            chunk
+        };
+
+        // Interleave two choices, with index 1 arriving first...
+        let mut acc = ChatCompletionAccumulator::new();
+        acc.push(at(1, "Blue", None));
+        acc.push(at(0, "Red", None));
+        acc.push(at(0, " apple", Some(FinishReason::Stop)));
+        acc.push(at(1, " sky", None));
+        acc.push(at(1, " at noon", Some(FinishReason::Length)));
+        let res = acc.finish();
+
+        // Each choice is assembled on its own, in index order...
+        let choices: Vec<_> = res
+            .choices
+            .iter()
+            .map(|choice| (choice.index, choice.finish_reason))
+            .collect();
+        assert_eq!(
+            choices,
+            vec![(0, FinishReason::Stop), (1, FinishReason::Length)]
+        );
+        assert_eq!(
+            res.contents().collect::<Vec<_>>(),
+            vec!["Red apple", "Blue sky at noon"]
+        );
+    }
+
+    #[test]
+    fn accumulator_assembles_tool_call_fragments() {
+        let call = |index: u64, id: Option<&str>, name: Option<&str>, arguments: &str| {
+            ChatCompletionDelta {
+                tool_calls: Some(vec![ToolCallDelta {
+                    index,
+                    id: id.map(str::to_string),
+                    type_: id.map(|_| "function".to_string()),
+                    function: Some(FunctionCallDelta {
+                        name: name.map(str::to_string),
+                        arguments: Some(arguments.to_string()),
+                    }),
+                }]),
+                ..Default::default()
+            }
+        };
+
+        // Two parallel calls, with their arguments in fragments...
+        let mut acc = ChatCompletionAccumulator::new();
+        acc.push(chunk(
+            ChatCompletionDelta {
+                role: Some(Role::Assistant),
+                ..Default::default()
+            },
+            None,
+        ));
+        acc.push(chunk(
+            call(0, Some("call_a"), Some("get_weather"), ""),
+            None,
+        ));
+        acc.push(chunk(call(0, None, None, "{\"location\": "), None));
+        acc.push(chunk(call(0, None, None, "\"Paris\", \"unit\": "), None));
+        acc.push(chunk(
+            call(1, Some("call_b"), Some("get_time"), "{\"tz\""),
+            None,
+        ));
+        acc.push(chunk(call(0, None, None, "\"celsius\"}"), None));
+        acc.push(chunk(call(1, None, None, ": \"CET\"}"), None));
+        acc.push(chunk(
+            ChatCompletionDelta::default(),
+            Some(FinishReason::ToolCalls),
+        ));
+        let res = acc.finish();
+
+        // Each call keeps the ID and name from its first fragment...
+        let message = &res.choices[0].message;
+        assert_eq!(message.content, None);
+        assert_eq!(
+            message.tool_calls,
+            Some(vec![
+                ToolCall {
+                    id: "call_a".to_string(),
+                    type_: ToolType::Function,
+                    function: FunctionCall {
+                        name: "get_weather".to_string(),
+                        arguments: "{\"location\": \"Paris\", \"unit\": \"celsius\"}".to_string(),
+                    },
+                },
+                ToolCall {
+                    id: "call_b".to_string(),
+                    type_: ToolType::Function,
+                    function: FunctionCall {
+                        name: "get_time".to_string(),
+                        arguments: "{\"tz\": \"CET\"}".to_string(),
+                    },
+                },
+            ])
+        );
+        assert_eq!(res.choices[0].finish_reason, FinishReason::ToolCalls);
+
+        // ...and the arguments are valid JSON once assembled...
+        let args: serde_json::Value =
+            serde_json::from_str(&message.tool_calls.as_ref().unwrap()[0].function.arguments)
+                .unwrap();
+        assert_eq!(args["unit"], "celsius");
+    }
+
+    #[test]
+    fn chunk_events_start_before_content() {
+        let mut started = false;
+
+        // Define cases to test...
+        let cases = vec![
+            (
+                chunk(
+                    ChatCompletionDelta {
+                        role: Some(Role::Assistant),
+                        content: Some(String::new()),
+                        ..Default::default()
+                    },
+                    None,
+                ),
+                vec![StreamEvent::Start {
+                    role: Role::Assistant,
+                }],
+            ),
+            (
+                chunk(content("Hello"), None),
+                vec![StreamEvent::Content("Hello".to_string())],
+            ),
+            (
+                chunk(ChatCompletionDelta::default(), Some(FinishReason::Stop)),
+                vec![StreamEvent::Done {
+                    finish_reason: FinishReason::Stop,
+                }],
+            ),
+        ];
+
+        // Iterate over cases and test...
+        for (chunk, expected) in cases {
+            assert_eq!(chunk_events(&mut started, chunk), expected);
+        }
+    }
+
+    #[test]
+    fn chunk_events_start_without_a_role_delta() {
+        let mut started = false;
+        assert_eq!(
+            chunk_events(&mut started, chunk(content("Hi"), None)),
+            vec![
+                StreamEvent::Start {
+                    role: Role::Assistant
+                },
+                StreamEvent::Content("Hi".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn accumulator_keeps_first_name_when_repeated() {
+        // A backend that repeats the ID, type and name in every fragment...
+        let call = |arguments: &str| ChatCompletionDelta {
+            tool_calls: Some(vec![ToolCallDelta {
+                index: 0,
+                id: Some("call_a".to_string()),
+                type_: Some("function".to_string()),
+                function: Some(FunctionCallDelta {
+                    name: Some("get_weather".to_string()),
+                    arguments: Some(arguments.to_string()),
+                }),
+            }]),
+            function_call: Some(FunctionCallDelta {
+                name: Some("get_weather".to_string()),
+                arguments: Some(arguments.to_string()),
+            }),
+            ..Default::default()
+        };
+
+        let mut acc = ChatCompletionAccumulator::new();
+        acc.push(chunk(call("{\"loc\": "), None));
+        acc.push(chunk(call("\"Paris\"}"), Some(FinishReason::ToolCalls)));
+        let res = acc.finish();
+
+        // ...still gets a single name, with the arguments joined...
+        let expected = FunctionCall {
+            name: "get_weather".to_string(),
+            arguments: "{\"loc\": \"Paris\"}".to_string(),
+        };
+        let message = &res.choices[0].message;
+        assert_eq!(message.function_call.as_ref(), Some(&expected));
+        assert_eq!(
+            message.tool_calls,
+            Some(vec![ToolCall {
+                id: "call_a".to_string(),
+                type_: ToolType::Function,
+                function: expected,
+            }])
+        );
+    }
+}
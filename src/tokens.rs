@@ -0,0 +1,288 @@
+//! Estimates how many tokens a chat completion's prompt will
+//! use, so oversized requests can be caught before they're
+//! sent.
+//!
+//! Enabled with the `tokens` feature.
+
+use std::collections::HashMap;
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+use tiktoken_rs::CoreBPE;
+
+use crate::dtypes::{check_logit_bias, ChatCompletionMessage, ContentPart, MessageContent, Role};
+use crate::{Error, Result};
+
+/// The tokens every reply is primed with
+/// (`<|start|>assistant<|message|>`).
+const REPLY_PRIMING_TOKENS: usize = 3;
+
+/// Estimates the number of prompt tokens the given messages
+/// will use with `model`, including the per-message
+/// formatting overhead OpenAI documents. Compare it against
+/// `models::model_context_window` to check a request fits.
+///
+/// The count covers each message's role, text content, name
+/// and function/tool calls. Images aren't counted, so the
+/// estimate is low for messages that include them. Returns an
+/// error if there's no known tokenizer for the model.
+///
+/// # Example
+///
+/// ```
+/// use ohairs::dtypes::{ChatCompletionMessage, Role};
+/// use ohairs::models::model_context_window;
+/// use ohairs::tokens::estimate_tokens;
+///
+/// # fn main() -> ohairs::Result<()> {
+/// let messages = vec![ChatCompletionMessage {
+///     role: Role::User,
+///     content: Some("Hello!".into()),
+///     ..Default::default()
+/// }];
+/// let tokens = estimate_tokens("gpt-4o", &messages)?;
+/// assert!(tokens < model_context_window("gpt-4o").unwrap() as usize);
+/// # Ok(())
+/// # }
+/// ```
+pub fn estimate_tokens(model: &str, messages: &[ChatCompletionMessage]) -> Result<usize> {
+    with_bpe(model, |bpe| count_tokens(bpe, model, messages))
+}
+
+/// Counts the tokens in `messages` with the given tokenizer.
+fn count_tokens(bpe: &CoreBPE, model: &str, messages: &[ChatCompletionMessage]) -> usize {
+    let count = |text: &str| bpe.encode_with_special_tokens(text).len();
+
+    // The first gpt-3.5 snapshot formats messages slightly differently...
+    let (tokens_per_message, tokens_per_name): (usize, isize) = match model {
+        "gpt-3.5-turbo-0301" => (4, -1),
+        _ => (3, 1),
+    };
+
+    // Count each message...
+    let mut tokens = 0;
+    for msg in messages {
+        tokens += tokens_per_message;
+        tokens += count(role_name(msg.role));
+        match &msg.content {
+            Some(MessageContent::Text(text)) => tokens += count(text),
+            Some(MessageContent::Parts(parts)) => {
+                for part in parts {
+                    if let ContentPart::Text { text } = part {
+                        tokens += count(text);
+                    }
+                }
+            }
+            None => {}
+        }
+        if let Some(name) = &msg.name {
+            tokens = (tokens + count(name)).saturating_add_signed(tokens_per_name);
+        }
+        if let Some(call) = &msg.function_call {
+            tokens += count(&call.name) + count(&call.arguments);
+        }
+        for call in msg.tool_calls.iter().flatten() {
+            tokens += count(&call.function.name) + count(&call.function.arguments);
+        }
+    }
+
+    // Add the reply priming...
+    tokens + REPLY_PRIMING_TOKENS
+}
+
+/// Builds a request's `logit_bias` map from words, rather
+/// than the token IDs the API expects.
+///
+/// Each word is tokenized with `model`'s tokenizer, and must
+/// map to exactly one token. Note that a leading space is
+/// part of the token, so `"hello"` and `" hello"` are
+/// different tokens.
+///
+/// # Example
+///
+/// ```
+/// use ohairs::tokens::LogitBiasBuilder;
+///
+/// # fn main() -> ohairs::Result<()> {
+/// let logit_bias = LogitBiasBuilder::new("gpt-4o")
+///     .bias(" yes", 10.0)
+///     .bias(" no", -100.0)
+///     .build()?;
+/// assert_eq!(logit_bias.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogitBiasBuilder {
+    model: String,
+    biases: Vec<(String, f64)>,
+}
+
+impl LogitBiasBuilder {
+    /// Creates an empty builder for the given model.
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            biases: Vec::new(),
+        }
+    }
+
+    /// Adds a bias (from -100 to 100) for the given word.
+    pub fn bias(mut self, word: &str, bias: f64) -> Self {
+        self.biases.push((word.to_string(), bias));
+        self
+    }
+
+    /// Tokenizes the words and returns the map of token IDs
+    /// (as strings) to biases.
+    ///
+    /// Returns an `Error::Validation` if the model has no
+    /// known tokenizer, a word doesn't map to exactly one
+    /// token, or a bias is out of range.
+    pub fn build(self) -> Result<HashMap<String, f64>> {
+        with_bpe(&self.model, |bpe| {
+            let mut logit_bias = HashMap::new();
+            for (word, bias) in &self.biases {
+                match bpe.encode_ordinary(word).as_slice() {
+                    [token] => {
+                        logit_bias.insert(token.to_string(), *bias);
+                    }
+                    tokens => {
+                        return Err(Error::Validation(format!(
+                            "logit_bias word {:?} must be a single token, got {}",
+                            word,
+                            tokens.len()
+                        )))
+                    }
+                }
+            }
+            check_logit_bias(&logit_bias)?;
+            Ok(logit_bias)
+        })?
+    }
+}
+
+/// Calls `f` with the shared tokenizer for the given model.
+fn with_bpe<T>(model: &str, f: impl FnOnce(&CoreBPE) -> T) -> Result<T> {
+    let bpe = match get_tokenizer(model) {
+        Some(Tokenizer::O200kBase) => tiktoken_rs::o200k_base_singleton(),
+        Some(Tokenizer::Cl100kBase) => tiktoken_rs::cl100k_base_singleton(),
+        Some(Tokenizer::P50kBase) => tiktoken_rs::p50k_base_singleton(),
+        Some(Tokenizer::P50kEdit) => tiktoken_rs::p50k_edit_singleton(),
+        Some(Tokenizer::R50kBase | Tokenizer::Gpt2) => tiktoken_rs::r50k_base_singleton(),
+        None => {
+            return Err(Error::Validation(format!(
+                "no tokenizer is known for model {:?}",
+                model
+            )))
+        }
+    };
+    let bpe = bpe.lock();
+    Ok(f(&bpe))
+}
+
+/// Returns the name a role is sent as.
+fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+        Role::Function => "function",
+        Role::Tool => "tool",
+        Role::Unknown => "",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn message(role: Role, content: &str) -> ChatCompletionMessage {
+        ChatCompletionMessage {
+            role,
+            content: Some(content.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn estimate_tokens_includes_overhead() {
+        // One message: 3 overhead + 1 ("user") + 1 ("Hello") + 3 priming...
+        let messages = vec![message(Role::User, "Hello")];
+        assert_eq!(estimate_tokens("gpt-4o", &messages).unwrap(), 8);
+
+        // Each further message adds its own overhead...
+        let messages = vec![
+            message(Role::System, "You are helpful."),
+            message(Role::User, "Hello"),
+        ];
+        let tokens = estimate_tokens("gpt-4", &messages).unwrap();
+        assert!(tokens > 8 + 3, "{}", tokens);
+
+        // Names cost an extra token on top of their text...
+        let named = vec![ChatCompletionMessage {
+            name: Some("alice".to_string()),
+            ..message(Role::User, "Hello")
+        }];
+        assert!(estimate_tokens("gpt-4o", &named).unwrap() > 9);
+
+        // Define cases to test...
+        let cases = vec![
+            ("gpt-3.5-turbo", 8, 10),
+            ("gpt-3.5-turbo-0613", 8, 10),
+            ("gpt-3.5-turbo-0125", 8, 10),
+            ("gpt-3.5-turbo-0301", 9, 9),
+        ];
+
+        // Iterate over cases and test...
+        let messages = vec![message(Role::User, "Hello")];
+        for (model, expected, expected_named) in cases {
+            assert_eq!(
+                estimate_tokens(model, &messages).unwrap(),
+                expected,
+                "{}",
+                model
+            );
+            assert_eq!(
+                estimate_tokens(model, &named).unwrap(),
+                expected_named,
+                "{}",
+                model
+            );
+        }
+    }
+
+    #[test]
+    fn estimate_tokens_unknown_model() {
+        let err = estimate_tokens("not-a-model", &[]).unwrap_err();
+        assert!(matches!(err, Error::Validation(_)));
+    }
+
+    #[test]
+    fn logit_bias_builder_maps_words_to_tokens() {
+        let logit_bias = LogitBiasBuilder::new("gpt-4o")
+            .bias("Hello", 5.0)
+            .build()
+            .unwrap();
+
+        // The key is the word's token ID...
+        let token = with_bpe("gpt-4o", |bpe| bpe.encode_ordinary("Hello")).unwrap();
+        assert_eq!(token.len(), 1);
+        assert_eq!(logit_bias.get(&token[0].to_string()), Some(&5.0));
+
+        // Words that aren't a single token are rejected...
+        let err = LogitBiasBuilder::new("gpt-4o")
+            .bias("antidisestablishmentarianism", 5.0)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, Error::Validation(_)));
+        let err = LogitBiasBuilder::new("gpt-4o")
+            .bias("Hello", 150.0)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, Error::Validation(_)));
+        let err = LogitBiasBuilder::new("gpt-4o")
+            .bias("", 5.0)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, Error::Validation(_)));
+    }
+}